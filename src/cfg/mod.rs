@@ -0,0 +1,653 @@
+/*
+* The control flow graph splits the program into basic blocks, straight line sequences of
+* instructions which can only be entered at the start and left at the end, joined by jumps and
+* conditional branches. It is built from the analysed program once folding and the optimisations
+* over the syntax tree have run
+*
+* The optimiser and the generator both work on the syntax tree, so the graph, its SSA form and the
+* graph after SSA destruction are only built to be inspected with --emit=ir and --dump-cfg
+*
+* Variables are given unique names when the graph is built, a variable which shadows another with
+* the same name has a numbered suffix, e.g. the second declaration of x becomes x#1. Declarations and
+* assignments both become instructions which store the value of an expression in a variable
+*
+* Arrays are kept in memory rather than being treated as values, an assignment to one of their
+* elements is an instruction which only stores to part of the variable, and arrays are never put
+* into SSA form
+*
+* A match statement ends its block with a terminator which jumps to the block of the first arm whose
+* pattern covers the value. The variables bound by a pattern are assigned by that jump, so they have
+* no instructions and, since they are never assigned again, keep their name in SSA form
+*/
+
+mod ssa;
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::parser::{Expression, ExpressionKind, Pattern, PatternKind, Program, Statement, Type};
+
+pub use ssa::{construct_ssa, destruct_ssa};
+
+pub type BlockId = usize;
+
+/*
+* When only part of the destination is assigned, e.g. an element of an array, place is the
+* expression for that part. A value written through a pointer has a place but no destination
+*/
+#[derive(Debug, Clone)]
+pub struct Instruction {
+    pub dest: String,
+    pub place: Option<Expression>,
+    pub expr: Expression,
+}
+
+impl std::fmt::Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.place {
+            Some(x) => write!(f, "{} = {}", x, self.expr),
+            None => write!(f, "{} = {}", self.dest, self.expr),
+        }
+    }
+}
+
+/*
+* A phi node selects the version of a variable that was live at the end of whichever predecessor
+* control arrived from, they only exist while the graph is in SSA form
+*/
+#[derive(Debug, Clone)]
+pub struct Phi {
+    pub var: String,
+    pub dest: String,
+    pub args: Vec<(BlockId, String)>,
+}
+
+impl std::fmt::Display for Phi {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let args: Vec<String> = self
+            .args
+            .iter()
+            .map(|(block, arg)| format!("b{}: {}", block, arg))
+            .collect();
+        write!(f, "{} = phi({})", self.dest, args.join(", "))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Terminator {
+    Jump(BlockId),
+    Branch {
+        condition: Expression,
+        if_true: BlockId,
+        if_false: BlockId,
+    },
+    Match {
+        scrutinee: Expression,
+        arms: Vec<(Pattern, BlockId)>,
+    },
+    Exit,
+}
+
+impl Terminator {
+    pub fn successors(&self) -> Vec<BlockId> {
+        match self {
+            Terminator::Jump(x) => vec![*x],
+            Terminator::Branch {
+                if_true, if_false, ..
+            } => vec![*if_true, *if_false],
+            Terminator::Match { arms, .. } => arms.iter().map(|(_, x)| *x).collect(),
+            Terminator::Exit => vec![],
+        }
+    }
+
+    fn retarget(&mut self, from: BlockId, to: BlockId) {
+        match self {
+            Terminator::Jump(x) if *x == from => *x = to,
+            Terminator::Branch {
+                if_true, if_false, ..
+            } => {
+                if *if_true == from {
+                    *if_true = to;
+                }
+                if *if_false == from {
+                    *if_false = to;
+                }
+            }
+            Terminator::Match { arms, .. } => {
+                for (_, x) in arms.iter_mut().filter(|(_, x)| *x == from) {
+                    *x = to;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl std::fmt::Display for Terminator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Terminator::Jump(x) => write!(f, "jump b{}", x),
+            Terminator::Branch {
+                condition,
+                if_true,
+                if_false,
+            } => write!(f, "branch {}, b{}, b{}", condition, if_true, if_false),
+            Terminator::Match { scrutinee, arms } => {
+                let arms: Vec<String> = arms
+                    .iter()
+                    .map(|(pattern, block)| format!("{} => b{}", pattern, block))
+                    .collect();
+                write!(f, "match {}, {}", scrutinee, arms.join(", "))
+            }
+            Terminator::Exit => write!(f, "exit"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BasicBlock {
+    pub phis: Vec<Phi>,
+    pub instructions: Vec<Instruction>,
+    pub terminator: Terminator,
+}
+
+impl BasicBlock {
+    fn new() -> Self {
+        Self {
+            phis: Vec::new(),
+            instructions: Vec::new(),
+            terminator: Terminator::Exit,
+        }
+    }
+}
+
+/*
+* Block 0 is always the entry block, and every block is reachable from it
+*/
+#[derive(Debug, Clone)]
+pub struct Cfg {
+    pub blocks: Vec<BasicBlock>,
+    pub variables: BTreeMap<String, Type>,
+}
+
+impl std::fmt::Display for Cfg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (id, block) in self.blocks.iter().enumerate() {
+            writeln!(f, "b{}:", id)?;
+            for line in block_lines(block) {
+                writeln!(f, "  {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Cfg {
+    pub fn predecessors(&self) -> Vec<Vec<BlockId>> {
+        let mut predecessors = vec![Vec::new(); self.blocks.len()];
+        for (id, block) in self.blocks.iter().enumerate() {
+            for successor in block.terminator.successors() {
+                if !predecessors[successor].contains(&id) {
+                    predecessors[successor].push(id);
+                }
+            }
+        }
+        predecessors
+    }
+
+    /*
+     * Orders the blocks reachable from the entry so that every block comes before its successors,
+     * ignoring loop back edges. The depth first search keeps, for each block on the stack, the index
+     * of the next successor to visit
+     */
+    pub fn reverse_postorder(&self) -> Vec<BlockId> {
+        let mut visited = vec![false; self.blocks.len()];
+        let mut postorder = Vec::new();
+        let mut stack = vec![(0, 0)];
+        visited[0] = true;
+
+        while let Some((block, next)) = stack.pop() {
+            let successors = self.blocks[block].terminator.successors();
+            match successors.get(next) {
+                Some(&successor) => {
+                    stack.push((block, next + 1));
+                    if !visited[successor] {
+                        visited[successor] = true;
+                        stack.push((successor, 0));
+                    }
+                }
+                None => postorder.push(block),
+            }
+        }
+
+        postorder.reverse();
+        postorder
+    }
+
+    /*
+     * Computes the immediate dominator of every block using the iterative algorithm from Cooper,
+     * Harvey and Kennedy's "A Simple, Fast Dominance Algorithm". The entry block has no immediate
+     * dominator
+     */
+    pub fn immediate_dominators(&self) -> Vec<Option<BlockId>> {
+        let order = self.reverse_postorder();
+        let mut position = vec![usize::MAX; self.blocks.len()];
+        for (idx, block) in order.iter().enumerate() {
+            position[*block] = idx;
+        }
+        let predecessors = self.predecessors();
+
+        let mut idom: Vec<Option<BlockId>> = vec![None; self.blocks.len()];
+        idom[0] = Some(0);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &block in order.iter().skip(1) {
+                let mut processed = predecessors[block].iter().filter(|x| idom[**x].is_some());
+                let mut new_idom = match processed.next() {
+                    Some(x) => *x,
+                    None => continue,
+                };
+                for &predecessor in processed {
+                    new_idom = intersect(&idom, &position, predecessor, new_idom);
+                }
+                if idom[block] != Some(new_idom) {
+                    idom[block] = Some(new_idom);
+                    changed = true;
+                }
+            }
+        }
+
+        idom[0] = None;
+        idom
+    }
+
+    /*
+     * The dominance frontier of a block is the set of blocks where its dominance ends, i.e. blocks
+     * which it doesn't strictly dominate but which have a predecessor that it does dominate
+     */
+    pub fn dominance_frontiers(&self, idom: &[Option<BlockId>]) -> Vec<BTreeSet<BlockId>> {
+        let mut frontiers = vec![BTreeSet::new(); self.blocks.len()];
+        for (block, predecessors) in self.predecessors().iter().enumerate() {
+            if predecessors.len() < 2 {
+                continue;
+            }
+            for &predecessor in predecessors {
+                let mut runner = Some(predecessor);
+                while let Some(x) = runner {
+                    if Some(x) == idom[block] {
+                        break;
+                    }
+                    frontiers[x].insert(block);
+                    runner = idom[x];
+                }
+            }
+        }
+        frontiers
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut output =
+            String::from("digraph cfg {\n  node [shape=box, fontname=\"monospace\"];\n");
+        for (id, block) in self.blocks.iter().enumerate() {
+            let mut label = format!("b{}\\l", id);
+            for line in block_lines(block) {
+                label.push_str(&escape_dot(&line));
+                label.push_str("\\l");
+            }
+            output.push_str(&format!("  b{} [label=\"{}\"];\n", id, label));
+
+            match &block.terminator {
+                Terminator::Jump(x) => output.push_str(&format!("  b{} -> b{};\n", id, x)),
+                Terminator::Branch {
+                    if_true, if_false, ..
+                } => {
+                    output.push_str(&format!("  b{} -> b{} [label=\"true\"];\n", id, if_true));
+                    output.push_str(&format!("  b{} -> b{} [label=\"false\"];\n", id, if_false));
+                }
+                Terminator::Match { arms, .. } => {
+                    for (pattern, block) in arms {
+                        output.push_str(&format!(
+                            "  b{} -> b{} [label=\"{}\"];\n",
+                            id,
+                            block,
+                            escape_dot(&pattern.to_string())
+                        ));
+                    }
+                }
+                Terminator::Exit => {}
+            }
+        }
+        output.push_str("}\n");
+        output
+    }
+}
+
+fn intersect(
+    idom: &[Option<BlockId>],
+    position: &[usize],
+    mut x: BlockId,
+    mut y: BlockId,
+) -> BlockId {
+    while x != y {
+        while position[x] > position[y] {
+            x = idom[x].unwrap_or(0);
+        }
+        while position[y] > position[x] {
+            y = idom[y].unwrap_or(0);
+        }
+    }
+    x
+}
+
+fn block_lines(block: &BasicBlock) -> Vec<String> {
+    block
+        .phis
+        .iter()
+        .map(|x| x.to_string())
+        .chain(block.instructions.iter().map(|x| x.to_string()))
+        .chain(std::iter::once(block.terminator.to_string()))
+        .collect()
+}
+
+fn escape_dot(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+struct CfgBuilder {
+    blocks: Vec<BasicBlock>,
+    current: BlockId,
+    scopes: Vec<HashMap<String, String>>,
+    num_declarations: HashMap<String, usize>,
+    variables: BTreeMap<String, Type>,
+    payloads: HashMap<(String, String), Vec<Type>>,
+}
+
+pub fn build(program: &Program) -> Cfg {
+    let payloads = program
+        .statements
+        .iter()
+        .flat_map(|x| match x {
+            Statement::EnumStatement { name, variants, .. } => variants
+                .iter()
+                .map(|x| ((name.clone(), x.name.clone()), x.payload.clone()))
+                .collect(),
+            _ => Vec::new(),
+        })
+        .collect();
+    let mut builder = CfgBuilder {
+        blocks: vec![BasicBlock::new()],
+        current: 0,
+        scopes: vec![HashMap::new()],
+        num_declarations: HashMap::new(),
+        variables: BTreeMap::new(),
+        payloads,
+    };
+    builder.lower_statements(&program.statements);
+
+    let mut cfg = Cfg {
+        blocks: builder.blocks,
+        variables: builder.variables,
+    };
+    remove_unreachable_blocks(&mut cfg);
+    cfg
+}
+
+impl CfgBuilder {
+    fn new_block(&mut self) -> BlockId {
+        self.blocks.push(BasicBlock::new());
+        self.blocks.len() - 1
+    }
+
+    fn terminate(&mut self, terminator: Terminator) {
+        self.blocks[self.current].terminator = terminator;
+    }
+
+    fn push_instruction(&mut self, dest: String, place: Option<Expression>, expr: Expression) {
+        self.blocks[self.current]
+            .instructions
+            .push(Instruction { dest, place, expr });
+    }
+
+    fn declare(&mut self, v_name: &str, ty: Type) -> String {
+        let count = self.num_declarations.entry(v_name.to_string()).or_insert(0);
+        let unique_name = match *count {
+            0 => v_name.to_string(),
+            x => format!("{}#{}", v_name, x),
+        };
+        *count += 1;
+
+        self.variables.insert(unique_name.clone(), ty);
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(v_name.to_string(), unique_name.clone());
+        }
+        unique_name
+    }
+
+    fn resolve(&self, v_name: &str) -> String {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(v_name))
+            .cloned()
+            .unwrap_or_else(|| v_name.to_string())
+    }
+
+    fn rename(&self, expr: &Expression) -> Expression {
+        let mut renamed = expr.clone();
+        rename_variables(&mut renamed, &|x| self.resolve(x));
+        renamed
+    }
+
+    fn lower_statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.lower_statement(statement);
+        }
+    }
+
+    fn lower_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::DeclarationStatement {
+                v_name, ty, expr, ..
+            } => {
+                let expr = expr.as_ref().map(|x| self.rename(x));
+                let dest = self.declare(v_name, ty.clone());
+                if let Some(expr) = expr {
+                    self.push_instruction(dest, None, expr);
+                }
+            }
+            Statement::ConstStatement { .. }
+            | Statement::StructStatement { .. }
+            | Statement::EnumStatement { .. } => {}
+            Statement::AssignmentStatement { target, expr, .. } => {
+                let expr = self.rename(expr);
+                let target = self.rename(target);
+                let dest = target.root_variable().unwrap_or_default().to_string();
+                let place = match target.kind {
+                    ExpressionKind::VarExpr(_) => None,
+                    _ => Some(target),
+                };
+                self.push_instruction(dest, place, expr);
+            }
+            Statement::BlockStatement { statements, .. } => {
+                self.scopes.push(HashMap::new());
+                self.lower_statements(statements);
+                self.scopes.pop();
+            }
+            Statement::IfStatement {
+                condition,
+                then_block,
+                else_block,
+                ..
+            } => {
+                let condition = self.rename(condition);
+                let then_id = self.new_block();
+                let else_id = else_block.as_ref().map(|_| self.new_block());
+                let join_id = self.new_block();
+                self.terminate(Terminator::Branch {
+                    condition,
+                    if_true: then_id,
+                    if_false: else_id.unwrap_or(join_id),
+                });
+
+                self.current = then_id;
+                self.lower_statement(then_block);
+                self.terminate(Terminator::Jump(join_id));
+                if let (Some(else_id), Some(else_block)) = (else_id, else_block) {
+                    self.current = else_id;
+                    self.lower_statement(else_block);
+                    self.terminate(Terminator::Jump(join_id));
+                }
+                self.current = join_id;
+            }
+            Statement::WhileStatement {
+                condition, body, ..
+            } => {
+                let header_id = self.new_block();
+                let body_id = self.new_block();
+                let exit_id = self.new_block();
+                self.terminate(Terminator::Jump(header_id));
+
+                self.current = header_id;
+                self.terminate(Terminator::Branch {
+                    condition: self.rename(condition),
+                    if_true: body_id,
+                    if_false: exit_id,
+                });
+
+                self.current = body_id;
+                self.lower_statement(body);
+                self.terminate(Terminator::Jump(header_id));
+                self.current = exit_id;
+            }
+            Statement::MatchStatement {
+                scrutinee, arms, ..
+            } => {
+                let scrutinee = self.rename(scrutinee);
+                let match_id = self.current;
+                let arm_ids: Vec<BlockId> = arms.iter().map(|_| self.new_block()).collect();
+                let join_id = self.new_block();
+
+                let mut patterns = Vec::new();
+                for (arm, arm_id) in arms.iter().zip(arm_ids) {
+                    self.current = arm_id;
+                    self.scopes.push(HashMap::new());
+                    let pattern = self.declare_bindings(&arm.pattern);
+                    self.lower_statement(&arm.body);
+                    self.scopes.pop();
+                    self.terminate(Terminator::Jump(join_id));
+                    patterns.push((pattern, arm_id));
+                }
+                self.blocks[match_id].terminator = Terminator::Match {
+                    scrutinee,
+                    arms: patterns,
+                };
+                self.current = join_id;
+            }
+        }
+    }
+
+    /*
+     * Declares the variables bound by a pattern in the current scope, returning the pattern with
+     * their unique names
+     */
+    fn declare_bindings(&mut self, pattern: &Pattern) -> Pattern {
+        let mut declared = pattern.clone();
+        if let PatternKind::Variant(enum_name, variant, bindings) = &mut declared.kind {
+            let payload = self.payloads[&(enum_name.clone(), variant.clone())].clone();
+            for ((v_name, _), ty) in bindings.iter_mut().zip(payload) {
+                if v_name != "_" {
+                    *v_name = self.declare(v_name, ty);
+                }
+            }
+        }
+        declared
+    }
+}
+
+/*
+* Blocks after a loop that never ends can't be reached, they are removed so that every block has a
+* dominator. The remaining blocks keep their relative order
+*/
+fn remove_unreachable_blocks(cfg: &mut Cfg) {
+    let mut reachable = cfg.reverse_postorder();
+    reachable.sort();
+    if reachable.len() == cfg.blocks.len() {
+        return;
+    }
+
+    let mut new_ids = vec![None; cfg.blocks.len()];
+    for (new_id, old_id) in reachable.iter().enumerate() {
+        new_ids[*old_id] = Some(new_id);
+    }
+    let remap = |x: &mut BlockId| *x = new_ids[*x].unwrap_or(*x);
+
+    let blocks = std::mem::take(&mut cfg.blocks);
+    for (old_id, mut block) in blocks.into_iter().enumerate() {
+        if new_ids[old_id].is_none() {
+            continue;
+        }
+        match &mut block.terminator {
+            Terminator::Jump(x) => remap(x),
+            Terminator::Branch {
+                if_true, if_false, ..
+            } => {
+                remap(if_true);
+                remap(if_false);
+            }
+            Terminator::Match { arms, .. } => {
+                for (_, x) in arms {
+                    remap(x);
+                }
+            }
+            Terminator::Exit => {}
+        }
+        cfg.blocks.push(block);
+    }
+}
+
+fn rename_variables(expr: &mut Expression, rename: &dyn Fn(&str) -> String) {
+    match &mut expr.kind {
+        ExpressionKind::ValExpr(_) => {}
+        ExpressionKind::VarExpr(x) => *x = rename(x),
+        ExpressionKind::ArithmeticExpr(x, _, y) | ExpressionKind::BooleanExpr(x, _, y) => {
+            rename_variables(x, rename);
+            rename_variables(y, rename);
+        }
+        ExpressionKind::CastExpr(x, _)
+        | ExpressionKind::FieldExpr(x, _)
+        | ExpressionKind::AddressOf(x)
+        | ExpressionKind::Deref(x) => rename_variables(x, rename),
+        ExpressionKind::IndexExpr(x, index) => {
+            rename_variables(x, rename);
+            rename_variables(index, rename);
+        }
+        ExpressionKind::StructLiteral(_, fields) => {
+            for (_, value) in fields {
+                rename_variables(value, rename);
+            }
+        }
+        ExpressionKind::VariantLiteral(_, _, values) => {
+            for value in values {
+                rename_variables(value, rename);
+            }
+        }
+        ExpressionKind::MatchExpr(scrutinee, arms) => {
+            rename_variables(scrutinee, rename);
+            for arm in arms {
+                let bindings: Vec<String> = arm
+                    .pattern
+                    .bindings()
+                    .into_iter()
+                    .map(|(_, x, _)| x.to_string())
+                    .collect();
+                rename_variables(&mut arm.body, &|x| match bindings.iter().any(|y| y == x) {
+                    true => x.to_string(),
+                    false => rename(x),
+                });
+            }
+        }
+    }
+}
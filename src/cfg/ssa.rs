@@ -0,0 +1,348 @@
+/*
+* Conversion to and from static single assignment (SSA) form, where every variable is assigned
+* exactly once. Each assignment to a variable creates a new version of it, named with a numbered
+* suffix (x.0, x.1, ...), and phi nodes select between versions where control flow joins
+*
+* Phi nodes are placed at the iterated dominance frontiers of the blocks which assign a variable, as
+* described by Cytron et al. Only variables which are live at the start of a block get a phi there,
+* so a variable which went out of scope in one branch of an if statement never needs one
+*
+* Only instructions which assign the whole of a variable create a new version of it, so arrays, which
+* are only ever assigned an element at a time, keep their original name. So do variables which have
+* their address taken, since they can be changed through a pointer by an instruction which doesn't
+* name them
+*/
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use crate::parser::{Expression, ExpressionKind};
+
+use super::{BasicBlock, BlockId, Cfg, Instruction, Phi, Terminator, rename_variables};
+
+pub fn construct_ssa(cfg: &mut Cfg) {
+    let idom = cfg.immediate_dominators();
+    let address_taken = address_taken_variables(cfg);
+    place_phis(cfg, &idom, &address_taken);
+
+    let mut children = vec![Vec::new(); cfg.blocks.len()];
+    for (block, dominator) in idom.iter().enumerate() {
+        if let Some(x) = dominator {
+            children[*x].push(block);
+        }
+    }
+
+    let mut renamer = Renamer {
+        versions: HashMap::new(),
+        stacks: HashMap::new(),
+        address_taken,
+    };
+    renamer.rename_block(cfg, 0, &children);
+}
+
+fn address_taken_variables(cfg: &Cfg) -> HashSet<String> {
+    let mut address_taken = HashSet::new();
+    for block in &cfg.blocks {
+        for instruction in &block.instructions {
+            instruction.expr.collect_address_taken(&mut address_taken);
+            if let Some(x) = &instruction.place {
+                x.collect_address_taken(&mut address_taken);
+            }
+        }
+        if let Terminator::Branch { condition, .. }
+        | Terminator::Match {
+            scrutinee: condition,
+            ..
+        } = &block.terminator
+        {
+            condition.collect_address_taken(&mut address_taken);
+        }
+    }
+    address_taken
+}
+
+fn place_phis(cfg: &mut Cfg, idom: &[Option<BlockId>], address_taken: &HashSet<String>) {
+    let frontiers = cfg.dominance_frontiers(idom);
+    let live_in = live_variables(cfg);
+    let predecessors = cfg.predecessors();
+
+    let mut def_sites: BTreeMap<String, BTreeSet<BlockId>> = BTreeMap::new();
+    for (id, block) in cfg.blocks.iter().enumerate() {
+        let versioned = block
+            .instructions
+            .iter()
+            .filter(|x| x.place.is_none() && !address_taken.contains(&x.dest));
+        for instruction in versioned {
+            def_sites
+                .entry(instruction.dest.clone())
+                .or_default()
+                .insert(id);
+        }
+    }
+
+    for (var, mut defined) in def_sites {
+        let mut worklist: Vec<BlockId> = defined.iter().copied().collect();
+        let mut has_phi = BTreeSet::new();
+        while let Some(block) = worklist.pop() {
+            for &frontier in &frontiers[block] {
+                if has_phi.contains(&frontier) || !live_in[frontier].contains(&var) {
+                    continue;
+                }
+                has_phi.insert(frontier);
+                cfg.blocks[frontier].phis.push(Phi {
+                    var: var.clone(),
+                    dest: var.clone(),
+                    args: predecessors[frontier]
+                        .iter()
+                        .map(|x| (*x, var.clone()))
+                        .collect(),
+                });
+                if defined.insert(frontier) {
+                    worklist.push(frontier);
+                }
+            }
+        }
+    }
+}
+
+/*
+* Returns the variables live at the start of each block, i.e. those which may be read before they
+* are next assigned
+*/
+fn live_variables(cfg: &Cfg) -> Vec<HashSet<String>> {
+    let mut uses = vec![HashSet::new(); cfg.blocks.len()];
+    let mut defs = vec![HashSet::new(); cfg.blocks.len()];
+    for (id, block) in cfg.blocks.iter().enumerate() {
+        let mut read = HashSet::new();
+        for instruction in &block.instructions {
+            instruction.expr.collect_variables(&mut read);
+            if let Some(x) = &instruction.place {
+                x.collect_index_variables(&mut read);
+            }
+            uses[id].extend(read.drain().filter(|x| !defs[id].contains(x)));
+            if instruction.place.is_none() {
+                defs[id].insert(instruction.dest.clone());
+            }
+        }
+        if let Terminator::Branch { condition, .. }
+        | Terminator::Match {
+            scrutinee: condition,
+            ..
+        } = &block.terminator
+        {
+            condition.collect_variables(&mut read);
+            uses[id].extend(read.drain().filter(|x| !defs[id].contains(x)));
+        }
+    }
+
+    let mut live_in = uses.clone();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for id in (0..cfg.blocks.len()).rev() {
+            let live_out: HashSet<String> = cfg.blocks[id]
+                .terminator
+                .successors()
+                .iter()
+                .flat_map(|x| live_in[*x].iter().cloned())
+                .collect();
+            for var in live_out {
+                if !defs[id].contains(&var) && live_in[id].insert(var) {
+                    changed = true;
+                }
+            }
+        }
+    }
+    live_in
+}
+
+/*
+* Renames variables by walking the dominator tree, keeping a stack of the versions of each variable
+* so that every use refers to the version assigned by the closest dominating definition
+*/
+struct Renamer {
+    versions: HashMap<String, usize>,
+    stacks: HashMap<String, Vec<String>>,
+    address_taken: HashSet<String>,
+}
+
+impl Renamer {
+    fn current(&self, var: &str) -> String {
+        self.stacks
+            .get(var)
+            .and_then(|x| x.last())
+            .cloned()
+            .unwrap_or_else(|| var.to_string())
+    }
+
+    fn new_version(&mut self, cfg: &mut Cfg, var: &str) -> String {
+        let version = self.versions.entry(var.to_string()).or_insert(0);
+        let name = format!("{}.{}", var, version);
+        *version += 1;
+
+        if let Some(ty) = cfg.variables.get(var).cloned() {
+            cfg.variables.insert(name.clone(), ty);
+        }
+        self.stacks
+            .entry(var.to_string())
+            .or_default()
+            .push(name.clone());
+        name
+    }
+
+    fn rename_block(&mut self, cfg: &mut Cfg, block: BlockId, children: &[Vec<BlockId>]) {
+        let mut defined = Vec::new();
+
+        for idx in 0..cfg.blocks[block].phis.len() {
+            let var = cfg.blocks[block].phis[idx].var.clone();
+            cfg.blocks[block].phis[idx].dest = self.new_version(cfg, &var);
+            defined.push(var);
+        }
+
+        for idx in 0..cfg.blocks[block].instructions.len() {
+            let instruction = &mut cfg.blocks[block].instructions[idx];
+            rename_variables(&mut instruction.expr, &|x| self.current(x));
+            if let Some(place) = &mut instruction.place {
+                rename_variables(place, &|x| self.current(x));
+                continue;
+            }
+            if self.address_taken.contains(&instruction.dest) {
+                continue;
+            }
+            let var = instruction.dest.clone();
+            cfg.blocks[block].instructions[idx].dest = self.new_version(cfg, &var);
+            defined.push(var);
+        }
+
+        if let Terminator::Branch { condition, .. }
+        | Terminator::Match {
+            scrutinee: condition,
+            ..
+        } = &mut cfg.blocks[block].terminator
+        {
+            rename_variables(condition, &|x| self.current(x));
+        }
+
+        for successor in cfg.blocks[block].terminator.successors() {
+            for phi in &mut cfg.blocks[successor].phis {
+                let version = self.current(&phi.var);
+                for (predecessor, arg) in &mut phi.args {
+                    if *predecessor == block {
+                        *arg = version.clone();
+                    }
+                }
+            }
+        }
+
+        for child in &children[block] {
+            self.rename_block(cfg, *child, children);
+        }
+
+        for var in defined {
+            if let Some(stack) = self.stacks.get_mut(&var) {
+                stack.pop();
+            }
+        }
+    }
+}
+
+/*
+* Replaces every phi node with copies at the end of its predecessors, leaving a graph that only
+* contains instructions and branches. Edges from a block with several successors to a block with
+* several predecessors are split first, so that the copies only run on the edge they belong to
+*/
+pub fn destruct_ssa(cfg: &mut Cfg) {
+    split_critical_edges(cfg);
+    let predecessors = cfg.predecessors();
+
+    for (block, block_predecessors) in predecessors.iter().enumerate() {
+        let phis = std::mem::take(&mut cfg.blocks[block].phis);
+        if phis.is_empty() {
+            continue;
+        }
+        for &predecessor in block_predecessors {
+            let copies: Vec<(String, String)> = phis
+                .iter()
+                .filter_map(|phi| {
+                    phi.args
+                        .iter()
+                        .find(|(x, _)| *x == predecessor)
+                        .map(|(_, arg)| (phi.dest.clone(), arg.clone()))
+                })
+                .filter(|(dest, src)| dest != src)
+                .collect();
+            insert_copies(cfg, predecessor, copies);
+        }
+    }
+}
+
+fn split_critical_edges(cfg: &mut Cfg) {
+    let predecessors = cfg.predecessors();
+    for (block, block_predecessors) in predecessors.iter().enumerate() {
+        if cfg.blocks[block].phis.is_empty() || block_predecessors.len() < 2 {
+            continue;
+        }
+        for &predecessor in block_predecessors {
+            if cfg.blocks[predecessor].terminator.successors().len() < 2 {
+                continue;
+            }
+            let edge_block = cfg.blocks.len();
+            cfg.blocks.push(BasicBlock {
+                phis: Vec::new(),
+                instructions: Vec::new(),
+                terminator: Terminator::Jump(block),
+            });
+            cfg.blocks[predecessor]
+                .terminator
+                .retarget(block, edge_block);
+            for phi in &mut cfg.blocks[block].phis {
+                for (x, _) in &mut phi.args {
+                    if *x == predecessor {
+                        *x = edge_block;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/*
+* All of the phis in a block take their values at the same time, so when one of the copies would
+* overwrite a value that another copy still needs to read, every value is first copied to a
+* temporary
+*/
+fn insert_copies(cfg: &mut Cfg, block: BlockId, copies: Vec<(String, String)>) {
+    let is_overlapping = copies
+        .iter()
+        .any(|(dest, _)| copies.iter().any(|(_, src)| src == dest));
+
+    let mut instructions = Vec::new();
+    if is_overlapping {
+        for (dest, src) in &copies {
+            let temp = format!("{}.copy", dest);
+            if let Some(ty) = cfg.variables.get(dest).cloned() {
+                cfg.variables.insert(temp.clone(), ty);
+            }
+            instructions.push(copy_instruction(cfg, temp.clone(), src));
+        }
+        for (dest, _) in &copies {
+            let temp = format!("{}.copy", dest);
+            instructions.push(copy_instruction(cfg, dest.clone(), &temp));
+        }
+    } else {
+        for (dest, src) in &copies {
+            instructions.push(copy_instruction(cfg, dest.clone(), src));
+        }
+    }
+
+    cfg.blocks[block].instructions.extend(instructions);
+}
+
+fn copy_instruction(cfg: &Cfg, dest: String, src: &str) -> Instruction {
+    let mut expr = Expression::new(ExpressionKind::VarExpr(src.to_string()), Default::default());
+    expr.ty = cfg.variables.get(src).cloned();
+    Instruction {
+        dest,
+        place: None,
+        expr,
+    }
+}
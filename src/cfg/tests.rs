@@ -0,0 +1,258 @@
+use super::*;
+use crate::test_utils;
+
+fn build_text(contents: &str) -> Cfg {
+    build(&test_utils::fold_text(contents).0)
+}
+
+fn block_strings(cfg: &Cfg) -> Vec<String> {
+    cfg.blocks
+        .iter()
+        .map(|x| block_lines(x).join("\n"))
+        .collect()
+}
+
+const IF_ELSE_PROGRAM: &str =
+    "mut int x = 1;\nif x > 0 {\n  x = 2;\n} else {\n  int t = 3;\n  x = t;\n}\nint y = x;";
+const LOOP_PROGRAM: &str =
+    "mut int i = 0;\nmut int total = 0;\nwhile i < 10 {\n  total = total + i;\n  i = i + 1;\n}";
+
+#[test]
+fn test_build_straight_line_program() {
+    let cfg = build_text("int x = 1;\n{\n  int x = 2;\n  int y = x + 1;\n}\nint z = x;");
+    assert_eq!(
+        block_strings(&cfg),
+        vec!["x = 1\nx#1 = 2\ny = x#1 + 1\nz = x\nexit"]
+    );
+    assert_eq!(cfg.variables.get("x#1"), Some(&Type::I64));
+}
+
+#[test]
+fn test_build_if_else() {
+    let cfg = build_text(IF_ELSE_PROGRAM);
+    assert_eq!(
+        block_strings(&cfg),
+        vec![
+            "x = 1\nbranch x > 0, b1, b2",
+            "x = 2\njump b3",
+            "t = 3\nx = t\njump b3",
+            "y = x\nexit",
+        ]
+    );
+    assert_eq!(
+        cfg.predecessors(),
+        vec![vec![], vec![0], vec![0], vec![1, 2]]
+    );
+}
+
+#[test]
+fn test_build_while_loop() {
+    let cfg = build_text(LOOP_PROGRAM);
+    assert_eq!(
+        block_strings(&cfg),
+        vec![
+            "i = 0\ntotal = 0\njump b1",
+            "branch i < 10, b2, b3",
+            "total = total + i\ni = i + 1\njump b1",
+            "exit",
+        ]
+    );
+    assert_eq!(cfg.reverse_postorder(), vec![0, 1, 3, 2]);
+}
+
+#[test]
+fn test_should_remove_blocks_after_infinite_loop() {
+    let cfg = build_text("mut int x = 0;\nwhile true {\n  x = x + 1;\n}\nx = 5;");
+    assert_eq!(
+        block_strings(&cfg),
+        vec![
+            "x = 0\njump b1",
+            "branch true, b2, b3",
+            "x = x + 1\njump b1",
+            "x = 5\nexit"
+        ]
+    );
+
+    let cfg = build_text("mut int x = 0;\nwhile true {\n  if x > 5 {\n    x = 0;\n  }\n}");
+    assert!(
+        cfg.blocks
+            .iter()
+            .flat_map(|x| x.terminator.successors())
+            .all(|x| x < cfg.blocks.len())
+    );
+}
+
+#[test]
+fn test_dominators_and_frontiers() {
+    let cfg = build_text(IF_ELSE_PROGRAM);
+    let idom = cfg.immediate_dominators();
+    assert_eq!(idom, vec![None, Some(0), Some(0), Some(0)]);
+    let frontiers = cfg.dominance_frontiers(&idom);
+    let expected: Vec<BTreeSet<BlockId>> = vec![
+        BTreeSet::new(),
+        BTreeSet::from([3]),
+        BTreeSet::from([3]),
+        BTreeSet::new(),
+    ];
+    assert_eq!(frontiers, expected);
+
+    let cfg = build_text(LOOP_PROGRAM);
+    let idom = cfg.immediate_dominators();
+    assert_eq!(idom, vec![None, Some(0), Some(1), Some(1)]);
+    let frontiers = cfg.dominance_frontiers(&idom);
+    assert_eq!(frontiers[2], BTreeSet::from([1]));
+    assert_eq!(frontiers[1], BTreeSet::from([1]));
+}
+
+#[test]
+fn test_construct_ssa_for_if_else() {
+    let mut cfg = build_text(IF_ELSE_PROGRAM);
+    construct_ssa(&mut cfg);
+    assert_eq!(
+        block_strings(&cfg),
+        vec![
+            "x.0 = 1\nbranch x.0 > 0, b1, b2",
+            "x.1 = 2\njump b3",
+            "t.0 = 3\nx.2 = t.0\njump b3",
+            "x.3 = phi(b1: x.1, b2: x.2)\ny.0 = x.3\nexit",
+        ]
+    );
+    assert_eq!(cfg.variables.get("x.3"), Some(&Type::I64));
+}
+
+#[test]
+fn test_construct_ssa_for_loop() {
+    let mut cfg = build_text(LOOP_PROGRAM);
+    construct_ssa(&mut cfg);
+    assert_eq!(
+        block_strings(&cfg),
+        vec![
+            "i.0 = 0\ntotal.0 = 0\njump b1",
+            "i.1 = phi(b0: i.0, b2: i.2)\ntotal.1 = phi(b0: total.0, b2: total.2)\nbranch i.1 < 10, b2, b3",
+            "total.2 = total.1 + i.1\ni.2 = i.1 + 1\njump b1",
+            "exit",
+        ]
+    );
+}
+
+#[test]
+fn test_construct_ssa_keeps_names_of_variables_with_their_address_taken() {
+    let mut cfg =
+        build_text("mut int x = 1;\n*int p = &x;\nif x > 0 {\n  x = 2;\n}\n*p = 3;\nint y = x;");
+    construct_ssa(&mut cfg);
+    assert_eq!(
+        block_strings(&cfg),
+        vec![
+            "x = 1\np.0 = &x\nbranch x > 0, b1, b2",
+            "x = 2\njump b2",
+            "*p.0 = 3\ny.0 = x\nexit",
+        ]
+    );
+}
+
+#[test]
+fn test_construct_ssa_only_places_phis_for_live_variables() {
+    let mut cfg = build_text("if true {\n  int t = 1;\n} else {\n  int t = 2;\n}");
+    construct_ssa(&mut cfg);
+    assert!(cfg.blocks.iter().all(|x| x.phis.is_empty()));
+}
+
+#[test]
+fn test_destruct_ssa() {
+    let mut cfg = build_text("mut int x = 0;\nif x < 1 {\n  x = 1;\n}\nint y = x;");
+    construct_ssa(&mut cfg);
+    destruct_ssa(&mut cfg);
+    assert_eq!(
+        block_strings(&cfg),
+        vec![
+            "x.0 = 0\nbranch x.0 < 1, b1, b3",
+            "x.1 = 1\nx.2 = x.1\njump b2",
+            "y.0 = x.2\nexit",
+            "x.2 = x.0\njump b2",
+        ]
+    );
+}
+
+#[test]
+fn test_destruct_ssa_copies_through_temporaries_when_phis_overlap() {
+    let typed_var = |v_name: &str| {
+        let mut expr = Expression::new(
+            ExpressionKind::VarExpr(v_name.to_string()),
+            Default::default(),
+        );
+        expr.ty = Some(Type::I64);
+        expr
+    };
+    let mut cfg = Cfg {
+        blocks: vec![
+            BasicBlock {
+                phis: Vec::new(),
+                instructions: vec![
+                    Instruction {
+                        dest: String::from("a.0"),
+                        place: None,
+                        expr: typed_var("p"),
+                    },
+                    Instruction {
+                        dest: String::from("b.0"),
+                        place: None,
+                        expr: typed_var("q"),
+                    },
+                ],
+                terminator: Terminator::Jump(1),
+            },
+            BasicBlock {
+                phis: vec![
+                    Phi {
+                        var: String::from("a"),
+                        dest: String::from("a.1"),
+                        args: vec![(0, String::from("a.0")), (1, String::from("b.1"))],
+                    },
+                    Phi {
+                        var: String::from("b"),
+                        dest: String::from("b.1"),
+                        args: vec![(0, String::from("b.0")), (1, String::from("a.1"))],
+                    },
+                ],
+                instructions: Vec::new(),
+                terminator: Terminator::Jump(1),
+            },
+        ],
+        variables: BTreeMap::from([
+            (String::from("a.1"), Type::I64),
+            (String::from("b.1"), Type::I64),
+        ]),
+    };
+
+    destruct_ssa(&mut cfg);
+    assert_eq!(
+        block_strings(&cfg)[1],
+        "a.1.copy = b.1\nb.1.copy = a.1\na.1 = a.1.copy\nb.1 = b.1.copy\njump b1"
+    );
+}
+
+#[test]
+fn test_to_dot() {
+    let cfg = build_text("mut char c = '\"';\nif c == '\\\\' {\n  c = 'a';\n}");
+    assert_eq!(
+        cfg.to_dot(),
+        "digraph cfg {\n  node [shape=box, fontname=\"monospace\"];\n  b0 [label=\"b0\\lc = '\\\"'\\lbranch c == '\\\\\\\\', b1, b2\\l\"];\n  b0 -> b1 [label=\"true\"];\n  b0 -> b2 [label=\"false\"];\n  b1 [label=\"b1\\lc = 'a'\\ljump b2\\l\"];\n  b1 -> b2;\n  b2 [label=\"b2\\lexit\\l\"];\n}\n"
+    );
+}
+
+#[test]
+fn test_build_match() {
+    let cfg = build_text(
+        "enum Opt { Some(int); None; }\nOpt o = Opt::Some(5);\nmut int x = 1;\nmatch o {\n  Opt::Some(x) => { int z = x + 1; }\n  Opt::None => { x = 2; }\n}\nint y = x;",
+    );
+    assert_eq!(
+        block_strings(&cfg),
+        vec![
+            "o = Opt::Some(5)\nx = 1\nmatch o, Opt::Some(x#1) => b1, Opt::None => b2",
+            "z = x#1 + 1\njump b3",
+            "x = 2\njump b3",
+            "y = x\nexit",
+        ]
+    );
+    assert_eq!(cfg.variables.get("x#1"), Some(&Type::I64));
+}
@@ -0,0 +1,655 @@
+/*
+* Command line arguments are an optional subcommand, followed by options and the source file in any
+* order. Every option is described once in a table which is used both to parse the arguments and to
+* print the help text. Short options take their value attached (-O2) or as the next argument
+* (-t arm64), and long options after an equals sign (--emit=asm) or as the next argument. Anything
+* after -- is passed on to the program when it is run. A file of - reads the source from stdin, and
+* -o - writes the output of the last stage to stdout when it is text
+*
+* Without a file, build and check use every entry of the project whose mgs.toml is found in the
+* current directory or one of its parents, and run uses its first entry. The manifest's target,
+* optimisation level and output directory are used unless they are given on the command line
+*/
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    generator::GenOptions,
+    io_handler::{Emit, InputError, Output, STDIO_PATH},
+    lints::{Lint, LintLevel, LintOptions},
+    manifest,
+    optimiser::{OptLevel, OptimiserOptions, Pass},
+    target::Target,
+    toolchain::ToolchainOptions,
+};
+
+const BUILD_FOLDER: &str = "./build";
+pub const SOURCE_EXTENSION: &str = "mgs";
+const STDIN_NAME: &str = "<stdin>";
+const PROGRAM_NAME: &str = env!("CARGO_PKG_NAME");
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+const RUNTIME_CHECKS: [&str; 3] = ["overflow-checks", "bounds-checks", "null-checks"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Build,
+    Run,
+    Check,
+    New,
+}
+
+#[derive(Debug)]
+pub struct CommandParseError;
+
+impl Command {
+    const ALL: [Command; 4] = [Command::Build, Command::Run, Command::Check, Command::New];
+
+    fn description(&self) -> &'static str {
+        match self {
+            Command::Build => "Compile a source file to an executable (the default)",
+            Command::Run => "Compile a source file and run the executable",
+            Command::Check => "Check a source file for errors without generating any code",
+            Command::New => "Create a project with a manifest and starter code in a new directory",
+        }
+    }
+}
+
+impl std::fmt::Display for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Command::Build => write!(f, "build"),
+            Command::Run => write!(f, "run"),
+            Command::Check => write!(f, "check"),
+            Command::New => write!(f, "new"),
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = CommandParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Command::ALL
+            .into_iter()
+            .find(|x| x.to_string() == s)
+            .ok_or(CommandParseError)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OptionKind {
+    Help,
+    Version,
+    Target,
+    OptLevel,
+    Feature,
+    Warn,
+    Allow,
+    Deny,
+    Emit,
+    Output,
+    OutDir,
+    KeepIntermediates,
+    NoKeepIntermediates,
+    Assembler,
+    Linker,
+    Runner,
+    Config,
+    Name,
+    DumpCfg,
+}
+
+struct CliOption {
+    kind: OptionKind,
+    short: Option<char>,
+    long: Option<&'static str>,
+    value: Option<&'static str>,
+    help: &'static str,
+}
+
+const OPTIONS: [CliOption; 19] = [
+    CliOption {
+        kind: OptionKind::Help,
+        short: Some('h'),
+        long: Some("help"),
+        value: None,
+        help: "Print this help text",
+    },
+    CliOption {
+        kind: OptionKind::Version,
+        short: Some('V'),
+        long: Some("version"),
+        value: None,
+        help: "Print the compiler version",
+    },
+    CliOption {
+        kind: OptionKind::Target,
+        short: Some('t'),
+        long: Some("target"),
+        value: Some("TARGET"),
+        help: "Assembly target to generate code for",
+    },
+    CliOption {
+        kind: OptionKind::OptLevel,
+        short: Some('O'),
+        long: Some("opt-level"),
+        value: Some("LEVEL"),
+        help: "Optimisation level, 0 by default",
+    },
+    CliOption {
+        kind: OptionKind::Feature,
+        short: Some('f'),
+        long: None,
+        value: Some("[no-]FEATURE"),
+        help: "Enable or disable an optimisation pass or runtime check",
+    },
+    CliOption {
+        kind: OptionKind::Warn,
+        short: Some('W'),
+        long: Some("warn"),
+        value: Some("LINT"),
+        help: "Warn when a lint is triggered",
+    },
+    CliOption {
+        kind: OptionKind::Allow,
+        short: Some('A'),
+        long: Some("allow"),
+        value: Some("LINT"),
+        help: "Allow code which triggers a lint",
+    },
+    CliOption {
+        kind: OptionKind::Deny,
+        short: Some('D'),
+        long: Some("deny"),
+        value: Some("LINT"),
+        help: "Stop compilation when a lint is triggered",
+    },
+    CliOption {
+        kind: OptionKind::Emit,
+        short: None,
+        long: Some("emit"),
+        value: Some("KIND[,KIND...]"),
+        help: "Write the output of each stage and stop after the last one, exe by default",
+    },
+    CliOption {
+        kind: OptionKind::Output,
+        short: Some('o'),
+        long: Some("output"),
+        value: Some("PATH"),
+        help: "Path to write the output of the last stage to",
+    },
+    CliOption {
+        kind: OptionKind::OutDir,
+        short: None,
+        long: Some("out-dir"),
+        value: Some("DIR"),
+        help: "Directory to write artifacts to, ./build by default",
+    },
+    CliOption {
+        kind: OptionKind::KeepIntermediates,
+        short: None,
+        long: Some("keep-intermediates"),
+        value: None,
+        help: "Keep the assembly and object files made along the way (the default)",
+    },
+    CliOption {
+        kind: OptionKind::NoKeepIntermediates,
+        short: None,
+        long: Some("no-keep-intermediates"),
+        value: None,
+        help: "Delete the assembly and object files made along the way",
+    },
+    CliOption {
+        kind: OptionKind::Assembler,
+        short: None,
+        long: Some("assembler"),
+        value: Some("COMMAND"),
+        help: "Assembler to use for the target, overriding MGS_AS and the config file",
+    },
+    CliOption {
+        kind: OptionKind::Linker,
+        short: None,
+        long: Some("linker"),
+        value: Some("COMMAND"),
+        help: "Linker to use for the target, overriding MGS_LD and the config file",
+    },
+    CliOption {
+        kind: OptionKind::Runner,
+        short: None,
+        long: Some("runner"),
+        value: Some("COMMAND"),
+        help: "Command to run the program with, qemu-aarch64 when not on an aarch64 host",
+    },
+    CliOption {
+        kind: OptionKind::Config,
+        short: None,
+        long: Some("config"),
+        value: Some("PATH"),
+        help: "Config file choosing the toolchain, ~/.config/mgs/config.toml by default",
+    },
+    CliOption {
+        kind: OptionKind::Name,
+        short: None,
+        long: Some("name"),
+        value: Some("NAME"),
+        help: "Name of the source in diagnostics and artifacts, <stdin> when the file is -",
+    },
+    CliOption {
+        kind: OptionKind::DumpCfg,
+        short: None,
+        long: Some("dump-cfg"),
+        value: None,
+        help: "Write the control flow graph as Graphviz .dot files",
+    },
+];
+
+impl CliOption {
+    fn label(&self) -> String {
+        let names = match (self.short, self.long) {
+            (Some(short), Some(long)) => format!("-{}, --{}", short, long),
+            (Some(short), None) => format!("-{}", short),
+            (None, Some(long)) => format!("    --{}", long),
+            (None, None) => String::new(),
+        };
+        match self.value {
+            Some(value) => format!("{} <{}>", names, value),
+            None => names,
+        }
+    }
+
+    fn values(&self) -> Option<String> {
+        match self.kind {
+            OptionKind::Target => Some(Target::get_values_string().to_lowercase()),
+            OptionKind::OptLevel => Some(String::from("0,1,2")),
+            OptionKind::Feature => Some(format!(
+                "{},{}",
+                Pass::get_values_string(),
+                RUNTIME_CHECKS.join(",")
+            )),
+            OptionKind::Warn | OptionKind::Allow | OptionKind::Deny => {
+                Some(format!("all,{}", Lint::get_values_string()))
+            }
+            OptionKind::Emit => Some(Emit::get_values_string()),
+            _ => None,
+        }
+    }
+}
+
+/*
+* The sources are the file given on the command line or the entries of the project, for new the
+* only source is the directory to create the project in
+*/
+#[derive(Debug)]
+pub struct CmdArgs {
+    pub command: Command,
+    pub sources: Vec<PathBuf>,
+    pub name: Option<String>,
+    pub target: Target,
+    pub optimiser: OptimiserOptions,
+    pub lints: LintOptions,
+    pub codegen: GenOptions,
+    pub dump_cfg: bool,
+    pub emit: Vec<Emit>,
+    pub out_dir: PathBuf,
+    pub out_path: Option<PathBuf>,
+    pub keep_intermediates: bool,
+    pub toolchain: ToolchainOptions,
+    pub program_args: Vec<String>,
+}
+
+impl CmdArgs {
+    pub fn emits(&self, emit: Emit) -> bool {
+        self.emit.contains(&emit)
+    }
+
+    /*
+     * Compilation stops after the last stage whose output was asked for
+     */
+    pub fn runs_after(&self, emit: Emit) -> bool {
+        self.emit.iter().any(|x| *x > emit)
+    }
+
+    /*
+     * The name diagnostics refer to the source by
+     */
+    pub fn source_name(&self, file: &Path) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None if reads_stdin(file) => STDIN_NAME.to_string(),
+            None => file.display().to_string(),
+        }
+    }
+
+    pub fn output(&self, file: &Path) -> Result<Output, InputError> {
+        Ok(Output::new(
+            self.out_dir.clone(),
+            self.get_file_name(file)?,
+            self.out_path.clone(),
+            self.emit.clone(),
+            self.keep_intermediates,
+        ))
+    }
+
+    /*
+     * Artifacts are named after the source file without its directory or last extension, so
+     * src/main.mgs gives main and lib.test.mgs gives lib.test. Source read from stdin is named
+     * stdin unless it was given a name
+     */
+    pub fn get_file_name(&self, file: &Path) -> Result<String, InputError> {
+        let path = match &self.name {
+            Some(name) => PathBuf::from(name),
+            None if reads_stdin(file) => return Ok(String::from("stdin")),
+            None => file.to_path_buf(),
+        };
+        path.file_stem()
+            .and_then(|x| x.to_str())
+            .map(|x| x.to_string())
+            .ok_or(InputError::FileNameParseError(path.clone()))
+    }
+}
+
+pub fn reads_stdin(file: &Path) -> bool {
+    file.as_os_str() == STDIO_PATH
+}
+
+/*
+* A source without the .mgs extension is still compiled, but is probably not the file that was meant
+*/
+fn source_warning(file: &Path) -> Option<String> {
+    if reads_stdin(file) || file.extension().is_some_and(|x| x == SOURCE_EXTENSION) {
+        return None;
+    }
+    Some(format!(
+        "Warning: Expected a .{} source file, found {}",
+        SOURCE_EXTENSION,
+        file.display()
+    ))
+}
+
+pub fn process_cmd_args() -> Result<Option<CmdArgs>, InputError> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    read_to_cmd_args(&args, Path::new(""))
+}
+
+pub fn help_text() -> String {
+    let mut lines = vec![
+        format!(
+            "Usage: {} [COMMAND] [OPTIONS] [FILE|-] [-- PROGRAM_ARGS...]",
+            PROGRAM_NAME
+        ),
+        format!(
+            "Without a FILE the entries of the project in the nearest {} are used",
+            manifest::MANIFEST_FILE
+        ),
+        String::new(),
+        String::from("Commands:"),
+    ];
+    for command in Command::ALL.iter() {
+        lines.push(format!(
+            "  {:<8}{}",
+            command.to_string(),
+            command.description()
+        ));
+    }
+
+    lines.push(String::new());
+    lines.push(String::from("Options:"));
+    let width = OPTIONS.iter().map(|x| x.label().len()).max().unwrap_or(0) + 2;
+    for option in &OPTIONS {
+        let help = match option.values() {
+            Some(values) => format!("{} [{}]", option.help, values),
+            None => option.help.to_string(),
+        };
+        lines.push(format!(
+            "  {:<width$}{}",
+            option.label(),
+            help,
+            width = width
+        ));
+    }
+    lines.join("\n")
+}
+
+/*
+* Returns the option an argument names along with any value attached to it, or None when the
+* argument is not an option
+*/
+fn find_option(arg: &str) -> Result<Option<(&'static CliOption, Option<String>)>, InputError> {
+    if let Some(long) = arg.strip_prefix("--") {
+        let (name, value) = match long.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (long, None),
+        };
+        return OPTIONS
+            .iter()
+            .find(|x| x.long == Some(name))
+            .map(|x| Some((x, value)))
+            .ok_or(InputError::UnknownOption(arg.to_string()));
+    }
+
+    let mut chars = arg.chars();
+    let short = match (chars.next(), chars.next()) {
+        (Some('-'), Some(x)) => x,
+        _ => return Ok(None),
+    };
+    let rest = chars.as_str();
+    match OPTIONS.iter().find(|x| x.short == Some(short)) {
+        Some(option) if option.value.is_some() && !rest.is_empty() => {
+            Ok(Some((option, Some(rest.to_string()))))
+        }
+        Some(option) if rest.is_empty() => Ok(Some((option, None))),
+        _ => Err(InputError::UnknownOption(arg.to_string())),
+    }
+}
+
+/*
+* The directory is where the search for a project's manifest starts when no file is given
+*/
+fn read_to_cmd_args(args: &[String], dir: &Path) -> Result<Option<CmdArgs>, InputError> {
+    if args.is_empty() {
+        return Err(InputError::NotEnoughArgs);
+    }
+    let (command, args) = match Command::from_str(&args[0]) {
+        Ok(command) => (command, &args[1..]),
+        Err(_) => (Command::Build, args),
+    };
+
+    let mut file_name: Option<PathBuf> = None;
+    let mut name = None;
+    let mut target = None;
+    let mut opt_level = None;
+    let mut optimiser = OptimiserOptions::new(OptLevel::O0);
+    let mut lints = LintOptions::default();
+    let mut overflow_checks = None;
+    let mut bounds_checks = true;
+    let mut null_checks = true;
+    let mut dump_cfg = false;
+    let mut emit = vec![Emit::Exe];
+    let mut out_dir = None;
+    let mut out_path = None;
+    let mut keep_intermediates = true;
+    let mut toolchain = ToolchainOptions::default();
+    let mut program_args = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        if args[i] == "--" && command == Command::Run {
+            program_args = args[i + 1..].to_vec();
+            break;
+        }
+        let (option, attached) = match find_option(&args[i])? {
+            Some(x) => x,
+            None if file_name.is_none() => {
+                file_name = Some(PathBuf::from(&args[i]));
+                i += 1;
+                continue;
+            }
+            None => return Err(InputError::InvalidArg(args[i].clone())),
+        };
+        let value = match (option.value, attached) {
+            (None, None) => String::new(),
+            (None, Some(_)) => return Err(InputError::InvalidArg(args[i].clone())),
+            (Some(_), Some(value)) => value,
+            (Some(_), None) => {
+                i += 1;
+                args.get(i)
+                    .cloned()
+                    .ok_or(InputError::MissingValue(args[i - 1].clone()))?
+            }
+        };
+
+        match option.kind {
+            OptionKind::Help => {
+                println!("{}", help_text());
+                return Ok(None);
+            }
+            OptionKind::Version => {
+                println!("{} {}", PROGRAM_NAME, VERSION);
+                return Ok(None);
+            }
+            OptionKind::Target => {
+                target = Some(
+                    Target::from_str(&value)
+                        .map_err(|_| InputError::InvalidTarget(value.clone()))?,
+                );
+            }
+            OptionKind::OptLevel => {
+                opt_level = Some(
+                    OptLevel::from_str(&value)
+                        .map_err(|_| InputError::InvalidArg(value.clone()))?,
+                );
+            }
+            OptionKind::Feature => {
+                let (enabled, name) = match value.strip_prefix("no-") {
+                    Some(name) => (false, name),
+                    None => (true, value.as_str()),
+                };
+                match name {
+                    "overflow-checks" => overflow_checks = Some(enabled),
+                    "bounds-checks" => bounds_checks = enabled,
+                    "null-checks" => null_checks = enabled,
+                    _ => {
+                        let pass = Pass::from_str(name)
+                            .map_err(|_| InputError::InvalidPass(name.to_string()))?;
+                        if enabled {
+                            optimiser.enable(pass);
+                        } else {
+                            optimiser.disable(pass);
+                        }
+                    }
+                }
+            }
+            OptionKind::Warn => set_lint_level(&mut lints, &value, LintLevel::Warn)?,
+            OptionKind::Allow => set_lint_level(&mut lints, &value, LintLevel::Allow)?,
+            OptionKind::Deny => set_lint_level(&mut lints, &value, LintLevel::Deny)?,
+            OptionKind::Emit => {
+                emit = value
+                    .split(',')
+                    .map(|x| Emit::from_str(x).map_err(|_| InputError::InvalidEmit(x.to_string())))
+                    .collect::<Result<Vec<Emit>, InputError>>()?;
+            }
+            OptionKind::Output => out_path = Some(PathBuf::from(value)),
+            OptionKind::OutDir => out_dir = Some(PathBuf::from(value)),
+            OptionKind::KeepIntermediates => keep_intermediates = true,
+            OptionKind::NoKeepIntermediates => keep_intermediates = false,
+            OptionKind::Assembler => toolchain.assembler = Some(value),
+            OptionKind::Linker => toolchain.linker = Some(value),
+            OptionKind::Runner => toolchain.runner = Some(value),
+            OptionKind::Config => toolchain.config = Some(PathBuf::from(value)),
+            OptionKind::Name => name = Some(value),
+            OptionKind::DumpCfg => dump_cfg = true,
+        }
+
+        i += 1;
+    }
+
+    let sources = match file_name {
+        Some(file_name) => vec![file_name],
+        None if command == Command::New => return Err(InputError::NotEnoughArgs),
+        None => {
+            let path = manifest::find(dir).ok_or(InputError::ManifestNotFound)?;
+            let project = manifest::read(&path).map_err(InputError::ManifestFailure)?;
+            target = target.or(project.target);
+            opt_level = opt_level.or(project.opt_level);
+            out_dir = out_dir.or(Some(project.out_dir));
+            let mut entries = project.entries;
+            if command == Command::Run {
+                entries.truncate(1);
+            }
+            if let Some(path) = &out_path
+                && entries.len() > 1
+            {
+                return Err(InputError::OutputForEntries(project.name, path.clone()));
+            }
+            entries
+        }
+    };
+    if command == Command::Run && !emit.contains(&Emit::Exe) {
+        emit.push(Emit::Exe);
+    }
+    if let Some(path) = &out_path
+        && path.as_os_str() == STDIO_PATH
+        && let Some(last) = emit.iter().max()
+        && *last >= Emit::Obj
+    {
+        return Err(InputError::BinaryToStdout(*last));
+    }
+
+    if target.is_none() && command != Command::New {
+        eprintln!("No target provided, using default {}", Target::ARM64);
+    }
+    optimiser.level = opt_level.unwrap_or(OptLevel::O0);
+
+    /*
+     * Overflow checks are part of an unoptimised debug build unless asked for explicitly, whereas
+     * bounds and null checks guard against writing to memory which isn't meant to be written to so
+     * are kept at every level
+     */
+    let codegen = GenOptions {
+        overflow_checks: overflow_checks.unwrap_or(optimiser.level == OptLevel::O0),
+        bounds_checks,
+        null_checks,
+    };
+
+    if command != Command::New {
+        for warning in sources.iter().filter_map(|x| source_warning(x)) {
+            eprintln!("{}", warning);
+        }
+    }
+
+    Ok(Some(CmdArgs {
+        command,
+        sources,
+        name,
+        target: target.unwrap_or(Target::ARM64),
+        optimiser,
+        lints,
+        codegen,
+        dump_cfg,
+        emit,
+        out_dir: out_dir.unwrap_or(PathBuf::from(BUILD_FOLDER)),
+        out_path,
+        keep_intermediates,
+        toolchain,
+        program_args,
+    }))
+}
+
+fn set_lint_level(lints: &mut LintOptions, name: &str, level: LintLevel) -> Result<(), InputError> {
+    if name == "all" {
+        for lint in Lint::ALL {
+            lints.set_level(lint, level);
+        }
+        return Ok(());
+    }
+    let lint = Lint::from_str(name).map_err(|_| InputError::InvalidLint(name.to_string()))?;
+    lints.set_level(lint, level);
+    Ok(())
+}
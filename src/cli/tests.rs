@@ -0,0 +1,249 @@
+use super::*;
+
+fn parse(args: &[&str]) -> Result<Option<CmdArgs>, InputError> {
+    let args: Vec<String> = args.iter().map(|x| x.to_string()).collect();
+    read_to_cmd_args(&args, Path::new(""))
+}
+
+fn parse_err(args: &[&str]) -> String {
+    parse(args).unwrap_err().to_string()
+}
+
+#[test]
+fn test_build_is_the_default_command() {
+    let cmd_args = parse(&["main.mgs", "-O2"]).unwrap().unwrap();
+    assert_eq!(cmd_args.command, Command::Build);
+    assert_eq!(cmd_args.sources, vec![PathBuf::from("main.mgs")]);
+    assert_eq!(cmd_args.optimiser.level, OptLevel::O2);
+    assert_eq!(cmd_args.emit, vec![Emit::Exe]);
+    assert_eq!(cmd_args.out_dir, PathBuf::from(BUILD_FOLDER));
+
+    let cmd_args = parse(&["check", "main.mgs"]).unwrap().unwrap();
+    assert_eq!(cmd_args.command, Command::Check);
+}
+
+#[test]
+fn test_options_take_values_attached_or_as_the_next_argument() {
+    let cmd_args = parse(&[
+        "run",
+        "-O",
+        "1",
+        "--emit=asm,ir",
+        "-o",
+        "bin/main",
+        "src/main.mgs",
+        "--out-dir",
+        "out",
+        "-fno-overflow-checks",
+        "-f",
+        "cse",
+        "--target=arm64",
+        "--no-keep-intermediates",
+    ])
+    .unwrap()
+    .unwrap();
+    assert_eq!(cmd_args.command, Command::Run);
+    assert_eq!(cmd_args.optimiser.level, OptLevel::O1);
+    assert_eq!(cmd_args.emit, vec![Emit::Asm, Emit::Ir, Emit::Exe]);
+    assert_eq!(cmd_args.out_path, Some(PathBuf::from("bin/main")));
+    assert_eq!(cmd_args.out_dir, PathBuf::from("out"));
+    assert_eq!(cmd_args.sources, vec![PathBuf::from("src/main.mgs")]);
+    assert!(!cmd_args.codegen.overflow_checks);
+    assert!(
+        cmd_args
+            .optimiser
+            .passes()
+            .contains(&Pass::CommonSubexpressionElimination)
+    );
+    assert!(!cmd_args.keep_intermediates);
+
+    let cmd_args = parse(&["run", "main.mgs", "--", "-O2", "main.mgs"])
+        .unwrap()
+        .unwrap();
+    assert_eq!(cmd_args.program_args, vec!["-O2", "main.mgs"]);
+}
+
+#[test]
+fn test_compilation_stops_after_the_last_stage_emitted() {
+    let cmd_args = parse(&["main.mgs", "--emit=tokens,ast"]).unwrap().unwrap();
+    assert!(cmd_args.emits(Emit::Tokens));
+    assert!(!cmd_args.emits(Emit::Ir));
+    assert!(cmd_args.runs_after(Emit::Tokens));
+    assert!(!cmd_args.runs_after(Emit::Ast));
+    assert!(!cmd_args.runs_after(Emit::Ir));
+
+    let cmd_args = parse(&["main.mgs", "--emit=ir,tokens"]).unwrap().unwrap();
+    assert!(cmd_args.runs_after(Emit::Ast));
+    assert!(!cmd_args.runs_after(Emit::Ir));
+
+    let cmd_args = parse(&["main.mgs"]).unwrap().unwrap();
+    assert!(!cmd_args.emits(Emit::Ir));
+    assert!(cmd_args.runs_after(Emit::Obj));
+    assert!(!cmd_args.runs_after(Emit::Exe));
+}
+
+#[test]
+fn test_source_can_be_read_from_stdin_and_named() {
+    let cmd_args = parse(&["-", "--emit=asm", "-o", "-"]).unwrap().unwrap();
+    let file = &cmd_args.sources[0];
+    assert!(reads_stdin(file));
+    assert_eq!(cmd_args.source_name(file), "<stdin>");
+    assert_eq!(cmd_args.get_file_name(file).unwrap(), "stdin");
+    assert_eq!(cmd_args.out_path, Some(PathBuf::from("-")));
+
+    let cmd_args = parse(&["-", "--name", "src/tool.mgs"]).unwrap().unwrap();
+    assert_eq!(cmd_args.source_name(Path::new("-")), "src/tool.mgs");
+    assert_eq!(cmd_args.get_file_name(Path::new("-")).unwrap(), "tool");
+
+    let cmd_args = parse(&["src/main.mgs"]).unwrap().unwrap();
+    assert_eq!(cmd_args.source_name(&cmd_args.sources[0]), "src/main.mgs");
+}
+
+#[test]
+fn test_artifacts_are_named_after_the_file_stem() {
+    let cmd_args = parse(&["main.mgs"]).unwrap().unwrap();
+    let test_cases = [
+        ("./src/main.mgs", "main"),
+        ("../main.mgs", "main"),
+        (".hidden/main.mgs", "main"),
+        ("src/.config/lib.test.mgs", "lib.test"),
+        ("lib.test.mgs", "lib.test"),
+        ("src/main", "main"),
+        (".main", ".main"),
+    ];
+    for (file, expected) in test_cases {
+        assert_eq!(cmd_args.get_file_name(Path::new(file)).unwrap(), expected);
+    }
+    assert_eq!(
+        cmd_args
+            .get_file_name(Path::new(".."))
+            .unwrap_err()
+            .to_string(),
+        "Unable to find a file name in path: .."
+    );
+}
+
+#[test]
+fn test_sources_without_the_mgs_extension_are_warned_about() {
+    assert_eq!(source_warning(Path::new("./src/main.mgs")), None);
+    assert_eq!(source_warning(Path::new(".hidden/lib.test.mgs")), None);
+    assert_eq!(source_warning(Path::new(STDIO_PATH)), None);
+    assert_eq!(
+        source_warning(Path::new("src/main")).unwrap(),
+        "Warning: Expected a .mgs source file, found src/main"
+    );
+    assert_eq!(
+        source_warning(Path::new("main.mgs.txt")).unwrap(),
+        "Warning: Expected a .mgs source file, found main.mgs.txt"
+    );
+}
+
+#[test]
+fn test_project_is_used_without_a_file() {
+    let dir = std::env::temp_dir().join(format!("mgs_cli_project_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(
+        dir.join("mgs.toml"),
+        "[package]\nname = \"demo\"\nentries = [\"src/main.mgs\", \"src/tool.mgs\"]\n[build]\nopt-level = 2\nout-dir = \"out\"",
+    )
+    .unwrap();
+    let args = |args: &[&str]| {
+        let args: Vec<String> = args.iter().map(|x| x.to_string()).collect();
+        read_to_cmd_args(&args, &dir.join("src"))
+    };
+
+    let cmd_args = args(&["build"]).unwrap().unwrap();
+    let project = dir.join("src/..");
+    assert_eq!(
+        cmd_args.sources,
+        vec![project.join("src/main.mgs"), project.join("src/tool.mgs")]
+    );
+    assert_eq!(cmd_args.optimiser.level, OptLevel::O2);
+    assert_eq!(cmd_args.out_dir, project.join("out"));
+
+    let cmd_args = args(&["run", "-O1", "--out-dir", "bin"]).unwrap().unwrap();
+    assert_eq!(cmd_args.sources, vec![project.join("src/main.mgs")]);
+    assert_eq!(cmd_args.optimiser.level, OptLevel::O1);
+    assert_eq!(cmd_args.out_dir, PathBuf::from("bin"));
+
+    let e = args(&["build", "-o", "bin/main"]).unwrap_err();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(
+        e.to_string(),
+        "Unable to write the output of every entry of project demo to bin/main, use --out-dir instead"
+    );
+
+    let cmd_args = parse(&["new", "demo"]).unwrap().unwrap();
+    assert_eq!(cmd_args.command, Command::New);
+    assert_eq!(cmd_args.sources, vec![PathBuf::from("demo")]);
+}
+
+#[test]
+fn test_help_and_version_stop_without_a_file() {
+    assert!(parse(&["--help"]).unwrap().is_none());
+    assert!(parse(&["build", "-h"]).unwrap().is_none());
+    assert!(parse(&["-V"]).unwrap().is_none());
+
+    let help = help_text();
+    for option in &OPTIONS {
+        assert!(help.contains(&option.label()));
+    }
+    for command in Command::ALL {
+        assert!(help.contains(&format!("  {} ", command)));
+    }
+    assert!(help.contains("[arm64]"));
+}
+
+#[test]
+fn test_should_error_for_invalid_arguments() {
+    let test_cases = vec![
+        (
+            vec![],
+            "Not enough arguments provided, use -h flag to see expected usage",
+        ),
+        (vec!["main.mgs", "-t"], "Missing value for option -t"),
+        (
+            vec!["main.mgs", "--emit"],
+            "Missing value for option --emit",
+        ),
+        (
+            vec!["main.mgs", "--frobnicate"],
+            "Unknown option --frobnicate, use --help to see the available options",
+        ),
+        (
+            vec!["-x", "main.mgs"],
+            "Unknown option -x, use --help to see the available options",
+        ),
+        (
+            vec!["main.mgs", "--dump-cfg=yes"],
+            "Invalid argument --dump-cfg=yes, use -h flag to see expected usage",
+        ),
+        (
+            vec!["main.mgs", "other.mgs"],
+            "Invalid argument other.mgs, use -h flag to see expected usage",
+        ),
+        (
+            vec!["main.mgs", "-fno-inline"],
+            "Invalid optimisation pass inline, available values are: [const-prop,copy-prop,cse,unreachable-code,dead-store]",
+        ),
+        (
+            vec!["new"],
+            "Not enough arguments provided, use -h flag to see expected usage",
+        ),
+        (
+            vec!["main.mgs", "-o", "-"],
+            "Unable to write exe output to stdout, only the text stages can be written there",
+        ),
+        (
+            vec!["-", "--emit=ir,obj", "-o-"],
+            "Unable to write obj output to stdout, only the text stages can be written there",
+        ),
+        (
+            vec!["main.mgs", "--", "arg"],
+            "Unknown option --, use --help to see the available options",
+        ),
+    ];
+    for (args, expected) in test_cases {
+        assert_eq!(parse_err(&args), expected);
+    }
+}
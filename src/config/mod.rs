@@ -0,0 +1,294 @@
+/*
+* Configuration files are written in a small subset of TOML: tables headed by [name], followed by
+* key = value pairs where a value is a string, an integer, a boolean or an array of values, with
+* comments starting at #. Keys before the first table header belong to the root table, named ""
+*/
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Bool(bool),
+    Array(Vec<Value>),
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::String(x) => write!(f, "{:?}", x),
+            Value::Integer(x) => write!(f, "{}", x),
+            Value::Bool(x) => write!(f, "{}", x),
+            Value::Array(values) => {
+                let values: Vec<String> = values.iter().map(|x| x.to_string()).collect();
+                write!(f, "[{}]", values.join(", "))
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ConfigErrorKind {
+    UnexpectedChar(char),
+    UnexpectedEnd,
+    UnclosedString,
+    InvalidInteger(String),
+    DuplicateKey(String),
+    DuplicateTable(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ConfigError {
+    pub kind: ConfigErrorKind,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl std::error::Error for ConfigError {}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ConfigErrorKind::UnexpectedChar(x) => write!(f, "Unexpected character {:?}", x)?,
+            ConfigErrorKind::UnexpectedEnd => write!(f, "Unexpected end of file")?,
+            ConfigErrorKind::UnclosedString => write!(f, "String is missing its closing quote")?,
+            ConfigErrorKind::InvalidInteger(x) => write!(f, "Invalid integer {}", x)?,
+            ConfigErrorKind::DuplicateKey(x) => write!(f, "Key defined more than once: {}", x)?,
+            ConfigErrorKind::DuplicateTable(x) => write!(f, "Table defined more than once: {}", x)?,
+        }
+        write!(f, ", Line: {}, Col: {}", self.line, self.col)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Config {
+    tables: HashMap<String, HashMap<String, Value>>,
+}
+
+impl Config {
+    pub fn get(&self, table: &str, key: &str) -> Option<&Value> {
+        self.tables.get(table).and_then(|x| x.get(key))
+    }
+
+    pub fn get_str(&self, table: &str, key: &str) -> Option<&str> {
+        match self.get(table, key) {
+            Some(Value::String(x)) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+pub fn parse(contents: &str) -> Result<Config, ConfigError> {
+    let mut parser = Parser {
+        chars: contents.chars().collect(),
+        idx: 0,
+        line: 1,
+        col: 1,
+    };
+    let mut config = Config::default();
+    let mut table = String::new();
+    config.tables.insert(table.clone(), HashMap::new());
+
+    loop {
+        parser.skip_blank(true);
+        let Some(next) = parser.peek() else {
+            return Ok(config);
+        };
+        if next == '[' {
+            parser.advance();
+            parser.skip_blank(false);
+            let (line, col) = (parser.line, parser.col);
+            table = parser.parse_key()?;
+            parser.skip_blank(false);
+            parser.expect(']')?;
+            if config
+                .tables
+                .insert(table.clone(), HashMap::new())
+                .is_some()
+            {
+                return Err(ConfigError {
+                    kind: ConfigErrorKind::DuplicateTable(table),
+                    line,
+                    col,
+                });
+            }
+        } else {
+            let (line, col) = (parser.line, parser.col);
+            let key = parser.parse_key()?;
+            parser.skip_blank(false);
+            parser.expect('=')?;
+            parser.skip_blank(false);
+            let value = parser.parse_value()?;
+            let entries = config.tables.get_mut(&table).expect("Table was inserted");
+            if entries.insert(key.clone(), value).is_some() {
+                return Err(ConfigError {
+                    kind: ConfigErrorKind::DuplicateKey(key),
+                    line,
+                    col,
+                });
+            }
+        }
+        parser.skip_blank(false);
+        match parser.peek() {
+            None | Some('\n') => {}
+            Some(x) => return Err(parser.error(ConfigErrorKind::UnexpectedChar(x))),
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    idx: usize,
+    line: usize,
+    col: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.idx).copied()
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let next = self.peek()?;
+        self.idx += 1;
+        if next == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+        Some(next)
+    }
+
+    fn error(&self, kind: ConfigErrorKind) -> ConfigError {
+        ConfigError {
+            kind,
+            line: self.line,
+            col: self.col,
+        }
+    }
+
+    fn unexpected(&self) -> ConfigError {
+        match self.peek() {
+            Some(x) => self.error(ConfigErrorKind::UnexpectedChar(x)),
+            None => self.error(ConfigErrorKind::UnexpectedEnd),
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ConfigError> {
+        if self.peek() != Some(expected) {
+            return Err(self.unexpected());
+        }
+        self.advance();
+        Ok(())
+    }
+
+    /*
+     * Skips spaces and comments, along with line breaks when they are allowed. A comment runs up
+     * to the end of its line, leaving the line break itself
+     */
+    fn skip_blank(&mut self, newlines: bool) {
+        while let Some(next) = self.peek() {
+            match next {
+                ' ' | '\t' | '\r' => {}
+                '\n' if newlines => {}
+                '#' => {
+                    while self.peek().is_some_and(|x| x != '\n') {
+                        self.advance();
+                    }
+                    continue;
+                }
+                _ => return,
+            }
+            self.advance();
+        }
+    }
+
+    fn parse_key(&mut self) -> Result<String, ConfigError> {
+        let mut key = String::new();
+        while let Some(next) = self.peek() {
+            if !(next.is_ascii_alphanumeric() || matches!(next, '_' | '-' | '.')) {
+                break;
+            }
+            key.push(next);
+            self.advance();
+        }
+        if key.is_empty() {
+            return Err(self.unexpected());
+        }
+        Ok(key)
+    }
+
+    fn parse_value(&mut self) -> Result<Value, ConfigError> {
+        match self.peek() {
+            Some('"') => self.parse_string(),
+            Some('[') => self.parse_array(),
+            Some(x) if x.is_ascii_alphanumeric() || x == '-' => {
+                let (line, col) = (self.line, self.col);
+                let word = self.parse_key()?;
+                match word.as_str() {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    _ => word.parse().map(Value::Integer).map_err(|_| ConfigError {
+                        kind: ConfigErrorKind::InvalidInteger(word),
+                        line,
+                        col,
+                    }),
+                }
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<Value, ConfigError> {
+        let (line, col) = (self.line, self.col);
+        let unclosed = ConfigError {
+            kind: ConfigErrorKind::UnclosedString,
+            line,
+            col,
+        };
+        self.advance();
+        let mut value = String::new();
+        loop {
+            match self.advance() {
+                Some('"') => return Ok(Value::String(value)),
+                Some('\\') => match self.advance() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some(x @ ('"' | '\\')) => value.push(x),
+                    _ => return Err(unclosed),
+                },
+                Some('\n') | None => return Err(unclosed),
+                Some(x) => value.push(x),
+            }
+        }
+    }
+
+    /*
+     * Arrays can be split across lines and may end with a trailing comma
+     */
+    fn parse_array(&mut self) -> Result<Value, ConfigError> {
+        self.advance();
+        let mut values = Vec::new();
+        loop {
+            self.skip_blank(true);
+            if self.peek() == Some(']') {
+                self.advance();
+                return Ok(Value::Array(values));
+            }
+            values.push(self.parse_value()?);
+            self.skip_blank(true);
+            match self.peek() {
+                Some(',') => {
+                    self.advance();
+                }
+                Some(']') => {}
+                _ => return Err(self.unexpected()),
+            }
+        }
+    }
+}
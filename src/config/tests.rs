@@ -0,0 +1,47 @@
+use super::*;
+
+#[test]
+fn test_parse_tables_and_values() {
+    let config = parse(
+        "# comment\nname = \"demo\" # trailing\n\n[package]\nversion = 2\nstrict = false\nentries = [\n  \"src/main.mgs\",\n  \"src/tool.mgs\",\n]\n[arm64]\nas = \"clang \\\"quoted\\\"\"",
+    )
+    .unwrap();
+    assert_eq!(config.get_str("", "name"), Some("demo"));
+    assert_eq!(config.get("package", "version"), Some(&Value::Integer(2)));
+    assert_eq!(config.get("package", "strict"), Some(&Value::Bool(false)));
+    assert_eq!(
+        config.get("package", "entries").unwrap().to_string(),
+        "[\"src/main.mgs\", \"src/tool.mgs\"]"
+    );
+    assert_eq!(config.get_str("arm64", "as"), Some("clang \"quoted\""));
+    assert_eq!(config.get_str("arm64", "ld"), None);
+}
+
+#[test]
+fn test_should_error_for_invalid_config() {
+    let test_cases = vec![
+        ("name = ", "Unexpected end of file, Line: 1, Col: 8"),
+        (
+            "name = \"demo",
+            "String is missing its closing quote, Line: 1, Col: 8",
+        ),
+        (
+            "name \"demo\"",
+            "Unexpected character '\"', Line: 1, Col: 6",
+        ),
+        ("a = 1 b = 2", "Unexpected character 'b', Line: 1, Col: 7"),
+        ("a = 1x", "Invalid integer 1x, Line: 1, Col: 5"),
+        (
+            "a = 1\na = 2",
+            "Key defined more than once: a, Line: 2, Col: 1",
+        ),
+        (
+            "[t]\n[t]",
+            "Table defined more than once: t, Line: 2, Col: 2",
+        ),
+        ("a = [1, 2", "Unexpected end of file, Line: 1, Col: 10"),
+    ];
+    for (input, expected) in test_cases {
+        assert_eq!(parse(input).unwrap_err().to_string(), expected);
+    }
+}
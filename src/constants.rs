@@ -1,8 +1,8 @@
 use regex::Regex;
 use std::sync::LazyLock;
 
-const VARIABLE_PATTERN: &'static str = r"^[_a-zA-Z](?:[\w\-]*[a-zA-Z0-9])?$";
-const VALUE_PATTERN: &'static str = r#"^(?:\d+(?:\.\d+)?|".*")$"#;
+const VARIABLE_PATTERN: &str = r"^[_a-zA-Z](?:[\w\-]*[a-zA-Z0-9])?$";
+const VALUE_PATTERN: &str = r#"^(?:\d+(?:\.\d+)?|".*"|'(?:[^'\\]|\\.)'|true|false)$"#;
 
 pub static VARIABLE_REGEX: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(VARIABLE_PATTERN).unwrap());
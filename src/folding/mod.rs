@@ -0,0 +1,450 @@
+/*
+* Constant folding runs after semantic analysis, so every expression already has its type. Any
+* operation whose operands are all literals is evaluated at compile time and replaced by a literal of
+* the same type, and every use of a constant is replaced by the value of that constant, which means
+* constants never need to be stored by the generated program
+*
+* Folded values follow the same rules as the generated code would at runtime, except that checked
+* integer arithmetic which doesn't fit in its type, or an integer division by zero, is reported as an
+* error rather than trapping when the program runs. Likewise an array index which is known once
+* folded must be within the bounds of the array
+*/
+
+#[cfg(test)]
+mod tests;
+
+use std::{cmp::Ordering, collections::HashMap};
+
+use crate::{
+    parser::{
+        Expression, ExpressionKind, Literal, MatchArm, Operator, OverflowMode, Program, Statement,
+        Type,
+    },
+    source::{Located, SourceMap},
+    token::TextLocation,
+};
+
+#[derive(Debug)]
+pub enum FoldError {
+    Overflow {
+        expr: String,
+        ty: Type,
+        location: TextLocation,
+    },
+    DivisionByZero {
+        expr: String,
+        location: TextLocation,
+    },
+    IndexOutOfBounds {
+        index: i128,
+        len: usize,
+        location: TextLocation,
+    },
+}
+
+impl std::error::Error for FoldError {}
+
+impl Located for FoldError {
+    fn location_mut(&mut self) -> Option<&mut TextLocation> {
+        match self {
+            FoldError::Overflow { location, .. }
+            | FoldError::DivisionByZero { location, .. }
+            | FoldError::IndexOutOfBounds { location, .. } => Some(location),
+        }
+    }
+}
+
+impl std::fmt::Display for FoldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FoldError::Overflow { expr, ty, location } => write!(
+                f,
+                "Result of constant expression {} overflows type {}, {}",
+                expr, ty, location
+            ),
+            FoldError::DivisionByZero { expr, location } => {
+                write!(
+                    f,
+                    "Division by zero in constant expression {}, {}",
+                    expr, location
+                )
+            }
+            FoldError::IndexOutOfBounds {
+                index,
+                len,
+                location,
+            } => write!(
+                f,
+                "Index {} is out of bounds for array of length {}, {}",
+                index, len, location
+            ),
+        }
+    }
+}
+
+/*
+* Each scope maps a name to the value of the constant it refers to, variables are also recorded (with
+* no value) so that a variable which shadows a constant isn't replaced
+*/
+struct Folder<'a> {
+    source_map: &'a SourceMap,
+    scopes: Vec<HashMap<String, Option<Literal>>>,
+}
+
+pub fn fold_program(program: &mut Program, source_map: &SourceMap) -> Result<(), FoldError> {
+    let mut folder = Folder {
+        source_map,
+        scopes: vec![HashMap::new()],
+    };
+    folder.fold_statements(&mut program.statements)
+}
+
+/*
+* Folds an expression outside of the context of a program, used by optimisations which replace
+* variables with values that are known at compile time
+*/
+pub fn fold_expression(expr: &mut Expression, source_map: &SourceMap) -> Result<(), FoldError> {
+    let mut folder = Folder {
+        source_map,
+        scopes: vec![HashMap::new()],
+    };
+    folder.fold_expression(expr)
+}
+
+impl Folder<'_> {
+    fn fold_statements(&mut self, statements: &mut [Statement]) -> Result<(), FoldError> {
+        for statement in statements {
+            self.fold_statement(statement)?;
+        }
+        Ok(())
+    }
+
+    fn fold_statement(&mut self, statement: &mut Statement) -> Result<(), FoldError> {
+        match statement {
+            Statement::DeclarationStatement { v_name, expr, .. } => {
+                if let Some(x) = expr {
+                    self.fold_expression(x)?;
+                }
+                self.declare(v_name, None);
+            }
+            Statement::ConstStatement { v_name, expr, .. } => {
+                self.fold_expression(expr)?;
+                self.declare(v_name, literal_value(expr));
+            }
+            Statement::AssignmentStatement { target, expr, .. } => {
+                self.fold_place(target)?;
+                self.fold_expression(expr)?;
+            }
+            Statement::BlockStatement { statements, .. } => {
+                self.scopes.push(HashMap::new());
+                let result = self.fold_statements(statements);
+                self.scopes.pop();
+                result?;
+            }
+            Statement::IfStatement {
+                condition,
+                then_block,
+                else_block,
+                ..
+            } => {
+                self.fold_expression(condition)?;
+                self.fold_statement(then_block)?;
+                if let Some(x) = else_block {
+                    self.fold_statement(x)?;
+                }
+            }
+            Statement::WhileStatement {
+                condition, body, ..
+            } => {
+                self.fold_expression(condition)?;
+                self.fold_statement(body)?;
+            }
+            Statement::MatchStatement {
+                scrutinee, arms, ..
+            } => {
+                self.fold_expression(scrutinee)?;
+                for arm in arms {
+                    self.fold_arm(arm, |x, body| x.fold_statement(body))?;
+                }
+            }
+            Statement::StructStatement { .. } | Statement::EnumStatement { .. } => {}
+        }
+        Ok(())
+    }
+
+    fn declare(&mut self, v_name: &str, value: Option<Literal>) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(v_name.to_string(), value);
+        }
+    }
+
+    /*
+     * The variables bound by the pattern of an arm shadow any constants with the same name
+     */
+    fn fold_arm<T>(
+        &mut self,
+        arm: &mut MatchArm<T>,
+        fold_body: impl FnOnce(&mut Self, &mut T) -> Result<(), FoldError>,
+    ) -> Result<(), FoldError> {
+        self.scopes.push(HashMap::new());
+        for (_, v_name, _) in arm.pattern.bindings() {
+            self.declare(v_name, None);
+        }
+        let result = fold_body(self, &mut arm.body);
+        self.scopes.pop();
+        result
+    }
+
+    fn lookup(&self, v_name: &str) -> Option<Literal> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(v_name))
+            .cloned()
+            .flatten()
+    }
+
+    fn fold_expression(&mut self, expr: &mut Expression) -> Result<(), FoldError> {
+        match &mut expr.kind {
+            ExpressionKind::ArithmeticExpr(x, _, y) | ExpressionKind::BooleanExpr(x, _, y) => {
+                self.fold_expression(x)?;
+                self.fold_expression(y)?;
+            }
+            ExpressionKind::CastExpr(x, _) | ExpressionKind::Deref(x) => self.fold_expression(x)?,
+            ExpressionKind::IndexExpr(..) | ExpressionKind::FieldExpr(..) => {
+                self.fold_place(expr)?
+            }
+            ExpressionKind::AddressOf(x) => self.fold_place(x)?,
+            ExpressionKind::StructLiteral(_, fields) => {
+                for (_, value) in fields {
+                    self.fold_expression(value)?;
+                }
+            }
+            ExpressionKind::VariantLiteral(_, _, values) => {
+                for value in values {
+                    self.fold_expression(value)?;
+                }
+            }
+            ExpressionKind::MatchExpr(scrutinee, arms) => {
+                self.fold_expression(scrutinee)?;
+                for arm in arms {
+                    self.fold_arm(arm, |x, body| x.fold_expression(body))?;
+                }
+            }
+            ExpressionKind::ValExpr(_) | ExpressionKind::VarExpr(_) => {}
+        }
+
+        if let Some(value) = self.evaluate(expr)? {
+            expr.kind = ExpressionKind::ValExpr(value.to_string());
+        }
+        Ok(())
+    }
+
+    /*
+     * Folds the indices of a place expression and the pointer it is reached through, the variable
+     * it refers to is left as it is
+     */
+    fn fold_place(&mut self, place: &mut Expression) -> Result<(), FoldError> {
+        if let ExpressionKind::Deref(x) = &mut place.kind {
+            self.fold_expression(x)?;
+        } else if let ExpressionKind::FieldExpr(x, _) = &mut place.kind {
+            if let Some(Type::Pointer(_)) = x.ty {
+                self.fold_expression(x)?;
+            } else {
+                self.fold_place(x)?;
+            }
+        } else if let ExpressionKind::IndexExpr(x, index) = &mut place.kind {
+            self.fold_place(x)?;
+            self.fold_expression(index)?;
+            if let (Some(Type::Array(_, len)), Some(Literal::Int(value))) =
+                (&x.ty, literal_value(index))
+                && (value < 0 || value >= *len as i128)
+            {
+                return Err(FoldError::IndexOutOfBounds {
+                    index: value,
+                    len: *len,
+                    location: self.source_map.location(index.span.start),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /*
+     * Returns the value of the expression if it can be computed from its (already folded) operands
+     */
+    fn evaluate(&self, expr: &Expression) -> Result<Option<Literal>, FoldError> {
+        let ty = match &expr.ty {
+            Some(x) => x.clone(),
+            None => return Ok(None),
+        };
+
+        match &expr.kind {
+            ExpressionKind::ValExpr(_)
+            | ExpressionKind::IndexExpr(..)
+            | ExpressionKind::FieldExpr(..)
+            | ExpressionKind::StructLiteral(..)
+            | ExpressionKind::VariantLiteral(..)
+            | ExpressionKind::MatchExpr(..)
+            | ExpressionKind::AddressOf(_)
+            | ExpressionKind::Deref(_) => Ok(None),
+            ExpressionKind::VarExpr(v_name) => Ok(self.lookup(v_name)),
+            ExpressionKind::ArithmeticExpr(x, op, y) => {
+                match (literal_value(x), literal_value(y)) {
+                    (Some(a), Some(b)) => self.evaluate_arithmetic(expr, a, *op, b, ty),
+                    _ => Ok(None),
+                }
+            }
+            ExpressionKind::BooleanExpr(x, op, y) => Ok(literal_value(x)
+                .zip(literal_value(y))
+                .and_then(|(a, b)| evaluate_comparison(a, *op, b))),
+            ExpressionKind::CastExpr(x, _) => {
+                Ok(literal_value(x).and_then(|a| evaluate_cast(a, ty)))
+            }
+        }
+    }
+
+    fn evaluate_arithmetic(
+        &self,
+        expr: &Expression,
+        a: Literal,
+        op: Operator,
+        b: Literal,
+        ty: Type,
+    ) -> Result<Option<Literal>, FoldError> {
+        match (a, b) {
+            (Literal::Int(a), Literal::Int(b)) => {
+                let (min, max) = match ty.int_range() {
+                    Some(x) => x,
+                    None => return Ok(None),
+                };
+                let result = match op.base_operator() {
+                    Operator::Add => a.checked_add(b),
+                    Operator::Sub => a.checked_sub(b),
+                    Operator::Mul => a.checked_mul(b),
+                    Operator::Div if b == 0 => {
+                        return Err(FoldError::DivisionByZero {
+                            expr: expr.to_string(),
+                            location: self.source_map.location(expr.span.start),
+                        });
+                    }
+                    Operator::Div => a.checked_div(b),
+                    _ => return Ok(None),
+                };
+
+                /*
+                 * Only multiplying two large 64 bit values can overflow an i128
+                 */
+                let value = match (op.overflow_mode(), result) {
+                    (_, Some(x)) if x >= min && x <= max => x,
+                    (OverflowMode::Wrapping, x) => {
+                        wrap_int(x.unwrap_or_else(|| a.wrapping_mul(b)), ty)
+                    }
+                    (OverflowMode::Saturating, x) => x
+                        .unwrap_or(if (a < 0) == (b < 0) { max } else { min })
+                        .clamp(min, max),
+                    (OverflowMode::Checked, _) => {
+                        return Err(FoldError::Overflow {
+                            expr: expr.to_string(),
+                            ty,
+                            location: self.source_map.location(expr.span.start),
+                        });
+                    }
+                };
+                Ok(Some(Literal::Int(value)))
+            }
+            (Literal::Float(a), Literal::Float(b)) => {
+                let result = match op {
+                    Operator::Add => a + b,
+                    Operator::Sub => a - b,
+                    Operator::Mul => a * b,
+                    Operator::Div => a / b,
+                    _ => return Ok(None),
+                };
+                Ok(Some(Literal::Float(round_float(result, ty))))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/*
+* The value of a literal expression, using the type given to it by the semantic pass, e.g. an integer
+* literal used as an f64 is treated as a float
+*/
+fn literal_value(expr: &Expression) -> Option<Literal> {
+    let value = match &expr.kind {
+        ExpressionKind::ValExpr(x) => Literal::parse(x)?,
+        _ => return None,
+    };
+    match (value, expr.ty.as_ref()?) {
+        (Literal::Int(x), ty) if ty.is_float() => Some(Literal::Float(int_to_float(x, ty.clone()))),
+        (Literal::Float(x), ty) => Some(Literal::Float(round_float(x, ty.clone()))),
+        (value, _) => Some(value),
+    }
+}
+
+fn evaluate_comparison(a: Literal, op: Operator, b: Literal) -> Option<Literal> {
+    let ordering = match (a, b) {
+        (Literal::Int(a), Literal::Int(b)) => a.partial_cmp(&b),
+        (Literal::Float(a), Literal::Float(b)) => a.partial_cmp(&b),
+        (Literal::Char(a), Literal::Char(b)) => a.partial_cmp(&b),
+        (Literal::Bool(a), Literal::Bool(b)) => a.partial_cmp(&b),
+        _ => return None,
+    };
+    let result = match op {
+        Operator::LessThan => ordering == Some(Ordering::Less),
+        Operator::GreaterThan => ordering == Some(Ordering::Greater),
+        Operator::LessThanEq => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        Operator::GreaterThanEq => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        Operator::Equal => ordering == Some(Ordering::Equal),
+        Operator::NotEqual => ordering != Some(Ordering::Equal),
+        _ => return None,
+    };
+    Some(Literal::Bool(result))
+}
+
+/*
+* Integer casts truncate or extend the value the same way the generated code does. Floats are
+* converted to integers by first converting to a 64 bit integer, which saturates, and then
+* truncating to the target type
+*/
+fn evaluate_cast(value: Literal, to: Type) -> Option<Literal> {
+    let result = match value {
+        Literal::Int(x) if to.is_float() => Literal::Float(int_to_float(x, to)),
+        Literal::Int(x) if to == Type::Char => Literal::Char(char::from(x as u8)),
+        Literal::Int(x) => Literal::Int(wrap_int(x, to)),
+        Literal::Float(x) if to.is_float() => Literal::Float(round_float(x, to)),
+        Literal::Float(x) if to.is_signed() => Literal::Int(wrap_int(x as i64 as i128, to)),
+        Literal::Float(x) => Literal::Int(wrap_int(x as u64 as i128, to)),
+        Literal::Bool(x) => Literal::Int(wrap_int(x as i128, to)),
+        Literal::Char(x) if to == Type::Char => Literal::Char(x),
+        Literal::Char(x) => Literal::Int(wrap_int(x as i128, to)),
+        Literal::Str(_) | Literal::Null => return None,
+    };
+    Some(result)
+}
+
+fn wrap_int(value: i128, ty: Type) -> i128 {
+    let bits = ty.size().expect("Only primitive values should be folded") as u32 * 8;
+    let truncated = value & ((1i128 << bits) - 1);
+    if ty.is_signed() && truncated >= 1i128 << (bits - 1) {
+        truncated - (1i128 << bits)
+    } else {
+        truncated
+    }
+}
+
+fn int_to_float(value: i128, ty: Type) -> f64 {
+    match ty {
+        Type::F32 => value as f32 as f64,
+        _ => value as f64,
+    }
+}
+
+fn round_float(value: f64, ty: Type) -> f64 {
+    match ty {
+        Type::F32 => value as f32 as f64,
+        _ => value,
+    }
+}
@@ -0,0 +1,151 @@
+use super::*;
+use crate::test_utils;
+
+fn fold_text(contents: &str) -> Result<Program, FoldError> {
+    let (mut program, source_map) = test_utils::analyse_text(contents);
+    fold_program(&mut program, &source_map)?;
+    Ok(program)
+}
+
+fn folded_expression(program: &Program, idx: usize) -> String {
+    match &program.statements[idx] {
+        Statement::DeclarationStatement {
+            expr: Some(expr), ..
+        }
+        | Statement::ConstStatement { expr, .. } => expr.to_string(),
+        x => panic!("Unexpected statement: {}", x),
+    }
+}
+
+#[test]
+fn test_should_fold_literal_subexpressions() {
+    let program =
+        fold_text("int x = 10;\nint y = 9 - 5 + x;\nint z = 2 * 3 + 4;\nint w = x + 9 - 5;")
+            .unwrap();
+    assert_eq!(folded_expression(&program, 1), "4 + x");
+    assert_eq!(folded_expression(&program, 2), "10");
+    assert_eq!(folded_expression(&program, 3), "x + 9 - 5");
+}
+
+#[test]
+fn test_should_fold_operators_of_the_same_precedence_from_the_left() {
+    let program = fold_text(
+        "const int A = 16 / 4 / 2;\nconst int B = 16 / 4 * 2;\nint c = 10 - 3 - 2;\nint d = 10 - 3 + 2;\nu8 x = 20;\nu8 e = x / 2 * 2;\nu8 f = x - 5 + 9;",
+    )
+    .unwrap();
+    assert_eq!(folded_expression(&program, 0), "2");
+    assert_eq!(folded_expression(&program, 1), "8");
+    assert_eq!(folded_expression(&program, 2), "5");
+    assert_eq!(folded_expression(&program, 3), "9");
+    assert_eq!(folded_expression(&program, 5), "x / 2 * 2");
+    assert_eq!(folded_expression(&program, 6), "x - 5 + 9");
+}
+
+#[test]
+fn test_should_fold_to_negative_values() {
+    let program = fold_text("int x = 1 - 5;\ni8 y = 0 - 100;").unwrap();
+    assert_eq!(folded_expression(&program, 0), "-4");
+    assert_eq!(folded_expression(&program, 1), "-100");
+}
+
+#[test]
+fn test_should_replace_constants_with_their_values() {
+    let program = fold_text("const u8 A = 3;\nconst u8 B = A * 2;\nu8 c = B + A;").unwrap();
+    assert_eq!(folded_expression(&program, 1), "6");
+    assert_eq!(folded_expression(&program, 2), "9");
+}
+
+#[test]
+fn test_should_not_replace_variable_shadowing_constant() {
+    let program = fold_text("const int A = 1;\n{\n  int A = 2;\n  int b = A;\n}").unwrap();
+    match &program.statements[1] {
+        Statement::BlockStatement { statements, .. } => match &statements[1] {
+            Statement::DeclarationStatement {
+                expr: Some(expr), ..
+            } => assert_eq!(expr.to_string(), "A"),
+            x => panic!("Unexpected statement: {}", x),
+        },
+        x => panic!("Unexpected statement: {}", x),
+    }
+}
+
+#[test]
+fn test_should_fold_comparisons_and_casts() {
+    let program = fold_text(
+        "bool a = 300 as u8 < 50;\ni8 b = 2.9 as i8;\nf32 c = 0.1 + 0.2;\nchar d = 10 as u8 as char;\nbool e = 'a' != 'a';",
+    )
+    .unwrap();
+    assert_eq!(folded_expression(&program, 0), "true");
+    assert_eq!(folded_expression(&program, 1), "2");
+    assert_eq!(
+        folded_expression(&program, 2),
+        format!("{:?}", (0.1f32 + 0.2f32) as f64)
+    );
+    assert_eq!(folded_expression(&program, 3), "'\\n'");
+    assert_eq!(folded_expression(&program, 4), "false");
+}
+
+#[test]
+fn test_should_error_for_overflow() {
+    let e = fold_text("u8 x = 1;\nu8 y = x + 200 * 2;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Result of constant expression 200 * 2 overflows type u8, Line: 2, Col: 12")
+    );
+    let e = fold_text("u8 x = 1 - 2;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Result of constant expression 1 - 2 overflows type u8, Line: 1, Col: 8")
+    );
+}
+
+#[test]
+fn test_should_error_for_division_by_zero() {
+    let e = fold_text("const int A = 0;\nint x = 10 / A;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Division by zero in constant expression 10 / 0, Line: 2, Col: 9")
+    );
+}
+
+#[test]
+fn test_should_fold_wrapping_and_saturating_arithmetic() {
+    let program = fold_text(
+        "const i8 N = 0 - 100;\nu8 a = 250 +% 10;\nu8 b = 250 +| 10;\ni8 c = N -| 100;\ni8 d = N -% 100;\nu64 e = 18446744073709551615 *| 2;\ni64 f = 9223372036854775807 *% 2;",
+    )
+    .unwrap();
+    assert_eq!(folded_expression(&program, 1), "4");
+    assert_eq!(folded_expression(&program, 2), "255");
+    assert_eq!(folded_expression(&program, 3), "-128");
+    assert_eq!(folded_expression(&program, 4), "56");
+    assert_eq!(folded_expression(&program, 5), "18446744073709551615");
+    assert_eq!(folded_expression(&program, 6), "-2");
+}
+
+#[test]
+fn test_should_error_for_constant_index_out_of_bounds() {
+    let program = fold_text("const int N = 2;\nmut i64[3] a;\na[N] = 1;").unwrap();
+    match &program.statements[2] {
+        Statement::AssignmentStatement { target, .. } => assert_eq!(target.to_string(), "a[2]"),
+        x => panic!("Unexpected statement: {}", x),
+    }
+
+    let e = fold_text("mut i64[3] a;\ni64 x = a[1 + 2];").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Index 3 is out of bounds for array of length 3, Line: 2, Col: 11")
+    );
+}
+
+#[test]
+fn test_match_bindings_shadow_constants() {
+    let program = fold_text(
+        "enum Opt { Some(int); None; }\nconst int x = 2;\nOpt o = Opt::Some(x + 1);\nint y = match o { Opt::Some(x) => x * 2, _ => x * 2 };",
+    )
+    .unwrap();
+    assert_eq!(folded_expression(&program, 2), "Opt::Some(3)");
+    assert_eq!(
+        folded_expression(&program, 3),
+        "match o { Opt::Some(x) => x * 2, _ => 4 }"
+    );
+}
@@ -1,25 +1,53 @@
 #[cfg(test)]
 mod tests;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
-    parser::{Expression, Program, Statement},
+    layout::{self, FieldLayout, Layouts},
+    parser::{
+        Expression, ExpressionKind, Literal, MatchArm, Operator, OverflowMode, PatternKind,
+        Program, Statement, Type,
+    },
+    source::{Located, SourceMap, Span},
     target::Target,
+    token::TextLocation,
 };
 
 const PROG_PRELUDE: &str = ".section .text\n.global _start\n_start:\n  mov x29, sp\n";
 const PROG_POSTLUDE: &str = "  mov x0, #0\n  mov x8, #93\n  svc #0\n";
-const STACK_VAR_OFFSET: usize = 16;
+const STACK_ALIGNMENT: usize = 16;
+const MAX_LOAD_OFFSET: usize = 256;
+const MAX_ADD_IMMEDIATE: usize = 4095;
+const MAX_UNROLLED_WORDS: usize = 8;
+const MIN_JUMP_TABLE_VARIANTS: usize = 4;
+const MAX_MOV_IMMEDIATE: i128 = 65535;
+const MIN_MOV_IMMEDIATE: i128 = -65536;
+const TRAP_EXIT_CODE: usize = 101;
+const MAX_REGISTER: usize = 15;
+const EXPRESSION_REGISTERS: usize = 4;
+
+#[derive(Debug, Default)]
+pub struct GenOptions {
+    pub overflow_checks: bool,
+    pub bounds_checks: bool,
+    pub null_checks: bool,
+}
 
 #[derive(Debug)]
 pub enum GenInternalError {
     UndefinedVariable(String),
+    MissingType(String),
+    InvalidLiteral(String),
+    InvalidOperator(Operator),
+    InvalidPlace(String),
+    InvalidMatch(String),
 }
 
 #[derive(Debug)]
 pub enum GenError {
     UnexpectedInternalError(GenInternalError),
+    TooManyRegisters(TextLocation),
 }
 
 impl std::error::Error for GenInternalError {}
@@ -31,6 +59,15 @@ impl std::fmt::Display for GenInternalError {
             GenInternalError::UndefinedVariable(x) => {
                 write!(f, "Undefined variable: {}", x)
             }
+            GenInternalError::MissingType(x) => {
+                write!(f, "No type information for expression: {}", x)
+            }
+            GenInternalError::InvalidLiteral(x) => write!(f, "Invalid literal: {}", x),
+            GenInternalError::InvalidOperator(x) => write!(f, "Invalid operator: {}", x),
+            GenInternalError::InvalidPlace(x) => write!(f, "Unable to find address of: {}", x),
+            GenInternalError::InvalidMatch(x) => {
+                write!(f, "Unable to match on value of type: {}", x)
+            }
         }
     }
 }
@@ -45,6 +82,20 @@ impl std::fmt::Display for GenError {
                     x
                 )
             }
+            GenError::TooManyRegisters(x) => write!(
+                f,
+                "Expression needs more registers than are available, {}",
+                x
+            ),
+        }
+    }
+}
+
+impl Located for GenError {
+    fn location_mut(&mut self) -> Option<&mut TextLocation> {
+        match self {
+            GenError::UnexpectedInternalError(_) => None,
+            GenError::TooManyRegisters(x) => Some(x),
         }
     }
 }
@@ -53,113 +104,1455 @@ impl GenError {
     fn from_undefined_var(v_name: String) -> Self {
         GenError::UnexpectedInternalError(GenInternalError::UndefinedVariable(v_name))
     }
+
+    fn from_internal(e: GenInternalError) -> Self {
+        GenError::UnexpectedInternalError(e)
+    }
 }
 
-struct GenState {
-    var_locs: HashMap<String, usize>,
+/*
+* Variables are stored on the stack addressed relative to the frame pointer (x29) and are found at
+* their offset below it. Each block introduces a new scope with a frame holding the variables
+* declared directly within it, which is reserved when the block starts and released when it ends.
+* Variables are placed in the frame in the order they are declared, each aligned according to its
+* layout, and the frame itself is rounded up to keep the stack pointer 16 byte aligned
+*/
+struct Scope {
+    variables: HashMap<String, (usize, Type)>,
+    next_offset: usize,
+    frame_size: usize,
 }
 
-impl GenState {
-    fn new() -> GenState {
-        return GenState {
-            var_locs: HashMap::new(),
+struct GenState<'a> {
+    scopes: Vec<Scope>,
+    stack_size: usize,
+    layouts: Layouts,
+    float_constants: Vec<(u64, Type)>,
+    num_labels: usize,
+    options: &'a GenOptions,
+    source_map: &'a SourceMap,
+    traps: Vec<String>,
+}
+
+impl<'a> GenState<'a> {
+    fn new(layouts: Layouts, options: &'a GenOptions, source_map: &'a SourceMap) -> Self {
+        GenState {
+            scopes: Vec::new(),
+            stack_size: 0,
+            layouts,
+            float_constants: Vec::new(),
+            num_labels: 0,
+            options,
+            source_map,
+            traps: Vec::new(),
+        }
+    }
+
+    fn next_label_id(&mut self) -> usize {
+        self.num_labels += 1;
+        self.num_labels - 1
+    }
+
+    /*
+     * Finds the offset of the next variable in the current frame, it is only visible once declared
+     * so that its initial value can still refer to a variable it shadows
+     */
+    fn allocate_var(&mut self, ty: &Type) -> usize {
+        let layout = self.layouts.of(ty);
+        let scope = self
+            .scopes
+            .last_mut()
+            .expect("Variables should only be declared within a scope");
+        scope.next_offset = layout::align_up(scope.next_offset + layout.size, layout.align);
+        scope.next_offset
+    }
+
+    fn declare_var(&mut self, v_name: String, offset: usize, ty: Type) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.variables.insert(v_name, (offset, ty));
+        }
+    }
+
+    fn get_var(&self, v_name: &str) -> Result<(usize, &Type), GenError> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.variables.get(v_name))
+            .map(|(offset, ty)| (*offset, ty))
+            .ok_or(GenError::from_undefined_var(v_name.to_string()))
+    }
+
+    /*
+     * Returns the size of the frame for the variables declared by the statements
+     */
+    fn enter_scope(&mut self, statements: &[Statement]) -> usize {
+        let frame_start = self.stack_size;
+        let mut frame_end = frame_start;
+        for statement in statements {
+            if let Statement::DeclarationStatement { ty, .. } = statement {
+                let layout = self.layouts.of(ty);
+                frame_end = layout::align_up(frame_end + layout.size, layout.align);
+            }
+        }
+        let frame_size = layout::align_up(frame_end, STACK_ALIGNMENT) - frame_start;
+        self.stack_size += frame_size;
+        self.scopes.push(Scope {
+            variables: HashMap::new(),
+            next_offset: frame_start,
+            frame_size,
+        });
+        frame_size
+    }
+
+    /*
+     * Reserves a frame holding a single value which isn't a variable, returns the offset of the
+     * value and the size of the frame
+     */
+    fn enter_temp_scope(&mut self, ty: &Type) -> (usize, usize) {
+        let layout = self.layouts.of(ty);
+        let offset = layout::align_up(self.stack_size + layout.size, layout.align);
+        let frame_size = layout::align_up(offset, STACK_ALIGNMENT) - self.stack_size;
+        self.stack_size += frame_size;
+        self.scopes.push(Scope {
+            variables: HashMap::new(),
+            next_offset: offset,
+            frame_size,
+        });
+        (offset, frame_size)
+    }
+
+    fn exit_scope(&mut self) -> usize {
+        let released = self.scopes.pop().map_or(0, |x| x.frame_size);
+        self.stack_size -= released;
+        released
+    }
+
+    /*
+     * Floating point values can't be used as immediates, so each distinct constant is placed in a
+     * literal pool at the end of the program and loaded from there by its label
+     */
+    fn get_float_constant_label(&mut self, value: f64, ty: &Type) -> String {
+        let bits = match ty {
+            Type::F32 => (value as f32).to_bits() as u64,
+            _ => value.to_bits(),
+        };
+        let idx = match self
+            .float_constants
+            .iter()
+            .position(|(x_bits, x_ty)| *x_bits == bits && x_ty == ty)
+        {
+            Some(idx) => idx,
+            None => {
+                self.float_constants.push((bits, ty.clone()));
+                self.float_constants.len() - 1
+            }
+        };
+        get_float_label(idx)
+    }
+
+    /*
+     * A failed runtime check branches to a trap which prints a message with the location of the
+     * check and exits, each distinct message has its own trap
+     */
+    fn get_trap_label(&mut self, message: &str, span: Span) -> String {
+        let message = format!("{}, {}", message, self.source_map.location(span.start));
+        let idx = match self.traps.iter().position(|x| *x == message) {
+            Some(idx) => idx,
+            None => {
+                self.traps.push(message);
+                self.traps.len() - 1
+            }
         };
+        format!(".Ltrap{}", idx)
+    }
+
+    fn generate_traps(&self) -> String {
+        if self.traps.is_empty() {
+            return String::new();
+        }
+        let mut output = String::new();
+        for (idx, message) in self.traps.iter().enumerate() {
+            output.push_str(&format!(
+                ".Ltrap{}:\n  adr x1, .Ltrapmsg{}\n  mov x2, #{}\n  b .Lpanic\n",
+                idx,
+                idx,
+                message.len() + 1
+            ));
+        }
+        output.push_str(&format!(
+            ".Lpanic:\n  mov x0, #2\n  mov x8, #64\n  svc #0\n  mov x0, #{}\n  mov x8, #93\n  svc #0\n",
+            TRAP_EXIT_CODE
+        ));
+        for (idx, message) in self.traps.iter().enumerate() {
+            output.push_str(&format!(".Ltrapmsg{}:\n  .ascii \"{}\\n\"\n", idx, message));
+        }
+        output
+    }
+
+    fn generate_float_constants(&self) -> String {
+        let mut output = String::new();
+        for (idx, (bits, ty)) in self.float_constants.iter().enumerate() {
+            let (alignment, directive) = match ty {
+                Type::F32 => (2, ".word"),
+                _ => (3, ".quad"),
+            };
+            output.push_str(&format!(
+                ".align {}\n{}:\n  {} 0x{:x}\n",
+                alignment,
+                get_float_label(idx),
+                directive,
+                bits
+            ));
+        }
+        output
     }
 }
 
-pub fn generate(target: &Target, program: Program) -> Result<String, GenError> {
+pub fn generate(
+    target: &Target,
+    program: Program,
+    options: &GenOptions,
+    source_map: &SourceMap,
+) -> Result<String, GenError> {
     match target {
-        Target::ARM64 => generate_arm(program),
+        Target::ARM64 => generate_arm(program, options, source_map),
     }
 }
 
-fn generate_arm(program: Program) -> Result<String, GenError> {
+fn generate_arm(
+    program: Program,
+    options: &GenOptions,
+    source_map: &SourceMap,
+) -> Result<String, GenError> {
     let mut output = String::new();
     output.push_str(PROG_PRELUDE);
 
-    output.push_str(&process_statements(program.statements)?);
+    let mut state = GenState::new(Layouts::new(&program), options, source_map);
+    let frame_size = state.enter_scope(&program.statements);
+    if frame_size > 0 {
+        output.push_str(&adjust_stack_pointer("sub", frame_size));
+    }
+    output.push_str(&process_statements(&mut state, program.statements)?);
 
     output.push_str(PROG_POSTLUDE);
+    output.push_str(&state.generate_traps());
+    output.push_str(&state.generate_float_constants());
     Ok(output)
 }
 
-fn process_statements(statements: Vec<Statement>) -> Result<String, GenError> {
+fn process_statements(
+    state: &mut GenState,
+    statements: Vec<Statement>,
+) -> Result<String, GenError> {
     let mut output = String::new();
-    let mut state = GenState::new();
-
     for statement in statements {
-        let processed = match statement {
-            Statement::DeclarationStatement { v_name, expr } => {
-                process_declaration_statement(&mut state, v_name, expr)?
-            }
-            Statement::AssignmentStatement { v_name, expr } => {
-                process_assignment_statement(&state, v_name, expr)?
-            }
-        };
-        output.push_str(&processed);
+        output.push_str(&process_statement(state, statement)?);
     }
-
     Ok(output)
 }
 
+fn process_statement(state: &mut GenState, statement: Statement) -> Result<String, GenError> {
+    match statement {
+        Statement::DeclarationStatement {
+            v_name, ty, expr, ..
+        } => process_declaration_statement(state, v_name, ty, expr),
+        Statement::ConstStatement { .. } => Ok(String::new()),
+        Statement::AssignmentStatement { target, expr, .. } => {
+            process_assignment_statement(state, target, expr)
+        }
+        Statement::BlockStatement { statements, .. } => process_block_statement(state, statements),
+        Statement::IfStatement {
+            condition,
+            then_block,
+            else_block,
+            ..
+        } => process_if_statement(state, condition, *then_block, else_block.map(|x| *x)),
+        Statement::WhileStatement {
+            condition, body, ..
+        } => process_while_statement(state, condition, *body),
+        Statement::MatchStatement {
+            scrutinee, arms, ..
+        } => {
+            let output = generate_match(state, &scrutinee, arms, 0, |state, body| {
+                Ok(process_statement(state, body)?.trim_end().to_string())
+            })?;
+            Ok(join_statement_lines(vec![output]))
+        }
+        Statement::StructStatement { .. } | Statement::EnumStatement { .. } => Ok(String::new()),
+    }
+}
+
+/*
+* A variable declared without a value is left as it is, unless it is an aggregate which starts out
+* zeroed
+*/
 fn process_declaration_statement(
     state: &mut GenState,
     v_name: String,
-    expr: Expression,
+    ty: Type,
+    expr: Option<Expression>,
 ) -> Result<String, GenError> {
-    state.var_locs.insert(v_name, state.var_locs.len() + 1);
-    Ok(format!(
-        "{}\n  str x0, [sp, #-{}]!\n",
-        generate_expression(state, &expr, 0)?,
-        STACK_VAR_OFFSET
-    ))
+    let offset = state.allocate_var(&ty);
+    let size = state.layouts.of(&ty).size;
+    let lines = match expr {
+        Some(x) if ty.is_aggregate() => vec![
+            generate_frame_address(0, offset),
+            generate_aggregate_store(state, &x, 0)?,
+        ],
+        Some(x) => vec![
+            generate_expression(state, &x, 0)?,
+            generate_frame_store(&ty, offset),
+        ],
+        None if ty.is_aggregate() && size > 0 => vec![
+            generate_frame_address(0, offset),
+            generate_copy(state, 0, None, size, 1),
+        ],
+        None => Vec::new(),
+    };
+    state.declare_var(v_name, offset, ty);
+    Ok(join_statement_lines(lines))
 }
 
 fn process_assignment_statement(
-    state: &GenState,
-    v_name: String,
+    state: &mut GenState,
+    target: Expression,
     expr: Expression,
 ) -> Result<String, GenError> {
-    let location = state
-        .var_locs
-        .get(&v_name)
-        .ok_or(GenError::from_undefined_var(v_name.clone()))?;
-    let offset = location * STACK_VAR_OFFSET;
+    let ty = get_expression_type(&target)?;
+    if ty.is_aggregate() {
+        return process_aggregate_assignment(state, target, expr);
+    }
+
+    let mut lines = vec![generate_expression(state, &expr, 0)?];
+    match get_frame_offset(state, &target)? {
+        Some(offset) => lines.push(generate_frame_store(ty, offset)),
+        None => {
+            let (command, reg_prefix) = get_store_command(ty);
+            lines.push(generate_address(state, &target, 1)?);
+            lines.push(format!("  {} {}0, [x1]", command, reg_prefix));
+        }
+    }
+    Ok(join_statement_lines(lines))
+}
+
+/*
+* A literal or match which may read the memory it is assigned to is built in a temporary frame and
+* then copied, so that no field is overwritten before it is read. Any value which reads through a
+* pointer, or is stored through one, is assumed to do so
+*/
+fn process_aggregate_assignment(
+    state: &mut GenState,
+    target: Expression,
+    expr: Expression,
+) -> Result<String, GenError> {
+    let mut used = HashSet::new();
+    expr.collect_variables(&mut used);
+    let is_aliased = matches!(
+        expr.kind,
+        ExpressionKind::StructLiteral(..)
+            | ExpressionKind::VariantLiteral(..)
+            | ExpressionKind::MatchExpr(..)
+    ) && (target.root_variable().is_none_or(|x| used.contains(x))
+        || expr.dereferences());
+    if !is_aliased {
+        return Ok(join_statement_lines(vec![
+            generate_address(state, &target, 0)?,
+            generate_aggregate_store(state, &expr, 0)?,
+        ]));
+    }
+
+    let ty = get_expression_type(&target)?;
+    let size = state.layouts.of(ty).size;
+    let (offset, frame_size) = state.enter_temp_scope(ty);
+    let lines = vec![
+        adjust_stack_pointer("sub", frame_size)
+            .trim_end()
+            .to_string(),
+        generate_frame_address(0, offset),
+        generate_aggregate_store(state, &expr, 0)?,
+        generate_address(state, &target, 1)?,
+        generate_copy(state, 1, Some(0), size, 2),
+    ];
+    state.exit_scope();
+    Ok(join_statement_lines(
+        lines
+            .into_iter()
+            .chain([adjust_stack_pointer("add", frame_size)
+                .trim_end()
+                .to_string()])
+            .collect(),
+    ))
+}
+
+/*
+* Stores an aggregate value at the address in the given register, leaving the address register
+* unchanged. A struct literal is stored one field at a time with any array fields it leaves out set
+* to zero, and a variant literal stores its tag followed by the values it carries. The arms of a
+* match store their values directly. Any other aggregate value is a place which is copied. Registers
+* after the address are free to use
+*/
+fn generate_aggregate_store(
+    state: &mut GenState,
+    expr: &Expression,
+    address: usize,
+) -> Result<String, GenError> {
+    check_registers(state, address, expr.span)?;
+    let ty = get_expression_type(expr)?;
+    match (&expr.kind, ty) {
+        (ExpressionKind::StructLiteral(_, values), Type::Struct(name)) => {
+            let fields = state.layouts.fields(name).to_vec();
+            let values = fields
+                .iter()
+                .map(|field| {
+                    values
+                        .iter()
+                        .find(|(x, _)| *x == field.name)
+                        .map(|(_, x)| x)
+                })
+                .collect();
+            generate_field_stores(state, &fields, values, address)
+        }
+        (ExpressionKind::VariantLiteral(name, variant, values), _) => {
+            let variant = state.layouts.variant(name, variant).clone();
+            Ok(join_lines(vec![
+                load_int(address + 1, variant.tag as i128),
+                format!("  str w{}, [x{}]", address + 1, address),
+                generate_field_stores(
+                    state,
+                    &variant.payload,
+                    values.iter().map(Some).collect(),
+                    address,
+                )?,
+            ]))
+        }
+        (ExpressionKind::MatchExpr(scrutinee, arms), _) => {
+            let arms = arms
+                .iter()
+                .map(|x| MatchArm {
+                    pattern: x.pattern.clone(),
+                    body: &x.body,
+                })
+                .collect();
+            generate_match(state, scrutinee, arms, address + 1, |state, body| {
+                generate_aggregate_store(state, body, address)
+            })
+        }
+        (ExpressionKind::StructLiteral(..), x) => Err(GenError::from_internal(
+            GenInternalError::InvalidPlace(x.to_string()),
+        )),
+        _ => {
+            let size = state.layouts.of(ty).size;
+            Ok(join_lines(vec![
+                generate_address(state, expr, address + 1)?,
+                generate_copy(state, address, Some(address + 1), size, address + 2),
+            ]))
+        }
+    }
+}
+
+/*
+* Stores the value given for each field at its offset from the address in the given register, a
+* field without a value is set to zero
+*/
+fn generate_field_stores(
+    state: &mut GenState,
+    fields: &[FieldLayout],
+    values: Vec<Option<&Expression>>,
+    address: usize,
+) -> Result<String, GenError> {
+    let mut lines = Vec::new();
+    for (field, value) in fields.iter().zip(values) {
+        let size = state.layouts.of(&field.ty).size;
+        match value {
+            Some(x) if field.ty.is_aggregate() => {
+                lines.push(generate_offset_address(address + 1, address, field.offset));
+                lines.push(generate_aggregate_store(state, x, address + 1)?);
+            }
+            Some(x) => {
+                let (command, reg_prefix) = get_store_command(&field.ty);
+                lines.push(generate_expression(state, x, address + 1)?);
+                if field.offset <= MAX_ADD_IMMEDIATE {
+                    lines.push(format!(
+                        "  {} {}{}, [x{}, #{}]",
+                        command,
+                        reg_prefix,
+                        address + 1,
+                        address,
+                        field.offset
+                    ));
+                } else {
+                    lines.push(generate_offset_address(address + 2, address, field.offset));
+                    lines.push(format!(
+                        "  {} {}{}, [x{}]",
+                        command,
+                        reg_prefix,
+                        address + 1,
+                        address + 2
+                    ));
+                }
+            }
+            None if size > 0 => {
+                lines.push(generate_offset_address(address + 1, address, field.offset));
+                lines.push(generate_copy(state, address + 1, None, size, address + 2));
+            }
+            None => {}
+        }
+    }
+    Ok(join_lines(lines))
+}
+
+/*
+* Copies a number of bytes from the address in the source register to the address in the
+* destination register, or sets them to zero if there is no source. Whole words are copied first,
+* in a loop if there are many of them which moves both addresses past the words, then the remaining
+* bytes are copied with narrower loads and stores. The scratch register and the one after it are
+* used to hold the value copied and the number of words left
+*/
+fn generate_copy(
+    state: &mut GenState,
+    dst: usize,
+    src: Option<usize>,
+    size: usize,
+    scratch: usize,
+) -> String {
+    let mut lines = Vec::new();
+    let num_words = size / 8;
+    let mut widths = Vec::new();
+    if num_words > MAX_UNROLLED_WORDS {
+        let label = format!(".Lcopy{}", state.next_label_id());
+        lines.push(load_int(scratch + 1, num_words as i128));
+        lines.push(format!("{}:", label));
+        let value = match src {
+            Some(src) => {
+                lines.push(format!("  ldr x{}, [x{}], #8", scratch, src));
+                format!("x{}", scratch)
+            }
+            None => "xzr".to_string(),
+        };
+        lines.push(format!("  str {}, [x{}], #8", value, dst));
+        lines.push(format!("  subs x{}, x{}, #1", scratch + 1, scratch + 1));
+        lines.push(format!("  b.ne {}", label));
+    } else {
+        widths = vec![8; num_words];
+    }
+    widths.extend([4, 2, 1].into_iter().filter(|x| (size % 8) & x != 0));
+
+    let mut position = 0;
+    for width in widths {
+        let (load, store, prefix) = match width {
+            8 => ("ldr", "str", "x"),
+            4 => ("ldr", "str", "w"),
+            2 => ("ldrh", "strh", "w"),
+            _ => ("ldrb", "strb", "w"),
+        };
+        let value = match src {
+            Some(src) => {
+                lines.push(format!(
+                    "  {} {}{}, [x{}, #{}]",
+                    load, prefix, scratch, src, position
+                ));
+                format!("{}{}", prefix, scratch)
+            }
+            None => format!("{}zr", prefix),
+        };
+        lines.push(format!("  {} {}, [x{}, #{}]", store, value, dst, position));
+        position += width;
+    }
+    join_lines(lines)
+}
+
+/*
+* A match copies the value matched on into a temporary frame, so that the values bound by its arms
+* refer to the copy and can't be changed by the arm itself. The tag of the variant is loaded into
+* the given register and used to find the first arm covering it. An enum with many variants jumps
+* through a table of branches indexed by the tag, otherwise the tag is compared against each arm in
+* turn and the last arm is reached when none of the others match. Registers after the given one are
+* free to use
+*/
+fn generate_match<T>(
+    state: &mut GenState,
+    scrutinee: &Expression,
+    arms: Vec<MatchArm<T>>,
+    reg: usize,
+    mut generate_body: impl FnMut(&mut GenState, T) -> Result<String, GenError>,
+) -> Result<String, GenError> {
+    let ty = get_expression_type(scrutinee)?.clone();
+    let variants = match &ty {
+        Type::Enum(name) => state.layouts.variants(name).to_vec(),
+        x => {
+            return Err(GenError::from_internal(GenInternalError::InvalidMatch(
+                x.to_string(),
+            )));
+        }
+    };
+    let label_id = state.next_label_id();
+    let arm_label = |idx: usize| format!(".Larm{}_{}", label_id, idx);
+    let end_label = format!(".Lendmatch{}", label_id);
+
+    let (offset, frame_size) = state.enter_temp_scope(&ty);
+    let mut lines = vec![
+        adjust_stack_pointer("sub", frame_size)
+            .trim_end()
+            .to_string(),
+        generate_frame_address(reg, offset),
+        generate_aggregate_store(state, scrutinee, reg)?,
+        format!("  ldr w{}, [x{}]", reg, reg),
+    ];
+
+    let mut arms: Vec<(usize, MatchArm<T>)> = arms.into_iter().enumerate().collect();
+    if variants.len() >= MIN_JUMP_TABLE_VARIANTS {
+        let table_label = format!(".Ljumptable{}", label_id);
+        lines.push(format!("  adr x{}, {}", reg + 1, table_label));
+        lines.push(format!(
+            "  add x{}, x{}, x{}, lsl #2",
+            reg + 1,
+            reg + 1,
+            reg
+        ));
+        lines.push(format!("  br x{}", reg + 1));
+        lines.push(format!("{}:", table_label));
+        for variant in &variants {
+            let idx = arms
+                .iter()
+                .position(|(_, arm)| match &arm.pattern.kind {
+                    PatternKind::Variant(_, x, _) => *x == variant.name,
+                    PatternKind::Wildcard => true,
+                })
+                .ok_or(GenError::from_internal(GenInternalError::InvalidMatch(
+                    ty.to_string(),
+                )))?;
+            lines.push(format!("  b {}", arm_label(idx)));
+        }
+    } else if let Some(last) = arms.pop() {
+        for (idx, arm) in &arms {
+            if let PatternKind::Variant(enum_name, variant, _) = &arm.pattern.kind {
+                let tag = state.layouts.variant(enum_name, variant).tag;
+                lines.push(format!("  cmp w{}, #{}", reg, tag));
+                lines.push(format!("  b.eq {}", arm_label(*idx)));
+            }
+        }
+        arms.insert(0, last);
+    }
+
+    let num_arms = arms.len();
+    for (position, (idx, arm)) in arms.into_iter().enumerate() {
+        lines.push(format!("{}:", arm_label(idx)));
+        state.enter_scope(&[]);
+        if let PatternKind::Variant(enum_name, variant, _) = &arm.pattern.kind {
+            let payload = state.layouts.variant(enum_name, variant).payload.clone();
+            for (field_idx, v_name, _) in arm.pattern.bindings() {
+                let field = &payload[field_idx];
+                state.declare_var(v_name.to_string(), offset - field.offset, field.ty.clone());
+            }
+        }
+        let body = generate_body(state, arm.body);
+        state.exit_scope();
+        lines.push(body?);
+        if position + 1 < num_arms {
+            lines.push(format!("  b {}", end_label));
+        }
+    }
+
+    state.exit_scope();
+    lines.push(format!("{}:", end_label));
+    lines.push(
+        adjust_stack_pointer("add", frame_size)
+            .trim_end()
+            .to_string(),
+    );
+    Ok(join_lines(lines))
+}
+
+fn process_block_statement(
+    state: &mut GenState,
+    statements: Vec<Statement>,
+) -> Result<String, GenError> {
+    let mut output = String::new();
+    let frame_size = state.enter_scope(&statements);
+    if frame_size > 0 {
+        output.push_str(&adjust_stack_pointer("sub", frame_size));
+    }
+    output.push_str(&process_statements(state, statements)?);
+    let released = state.exit_scope();
+    if released > 0 {
+        output.push_str(&adjust_stack_pointer("add", released));
+    }
+    Ok(output)
+}
+
+/*
+* Conditions are booleans held as 0 or 1, so branching only needs to check for 0
+*/
+fn process_if_statement(
+    state: &mut GenState,
+    condition: Expression,
+    then_block: Statement,
+    else_block: Option<Statement>,
+) -> Result<String, GenError> {
+    let label_id = state.next_label_id();
+    let end_label = format!(".Lendif{}", label_id);
+    let mut output = generate_expression(state, &condition, 0)?;
+
+    match else_block {
+        Some(else_block) => {
+            let else_label = format!(".Lelse{}", label_id);
+            output.push_str(&format!("\n  cbz x0, {}\n", else_label));
+            output.push_str(&process_statement(state, then_block)?);
+            output.push_str(&format!("  b {}\n{}:\n", end_label, else_label));
+            output.push_str(&process_statement(state, else_block)?);
+        }
+        None => {
+            output.push_str(&format!("\n  cbz x0, {}\n", end_label));
+            output.push_str(&process_statement(state, then_block)?);
+        }
+    }
+
+    output.push_str(&format!("{}:\n", end_label));
+    Ok(output)
+}
+
+fn process_while_statement(
+    state: &mut GenState,
+    condition: Expression,
+    body: Statement,
+) -> Result<String, GenError> {
+    let label_id = state.next_label_id();
+    let loop_label = format!(".Lwhile{}", label_id);
+    let end_label = format!(".Lendwhile{}", label_id);
+
     Ok(format!(
-        "{}\n  str x0, [x29, #-{}]\n",
-        generate_expression(state, &expr, 0)?,
-        offset
+        "{}:\n{}\n  cbz x0, {}\n{}  b {}\n{}:\n",
+        loop_label,
+        generate_expression(state, &condition, 0)?,
+        end_label,
+        process_statement(state, body)?,
+        loop_label,
+        end_label
     ))
 }
 
+/*
+* Integer values are always held in registers extended to the full 64 bits according to their type,
+* so values are sign or zero extended when loaded and after any operation which may leave bits set
+* outside of the width of the type. Floating point values use the SIMD/FP registers with the same
+* register number, d registers for f64 and s registers for f32. Pointers are held as 64 bit
+* addresses. The left operand of an operator is evaluated into the target register and the right
+* one into the register after it, so a chain of operations grouped from the left keeps reusing the
+* same registers
+*/
 fn generate_expression(
-    state: &GenState,
+    state: &mut GenState,
     expr: &Expression,
     target_reg: usize,
 ) -> Result<String, GenError> {
-    match expr {
-        Expression::ValExpr(x) => Ok(format!("  mov x{}, #{}", target_reg, x)),
-        Expression::VarExpr(x) => {
-            let location = state
-                .var_locs
-                .get(x)
-                .ok_or(GenError::from_undefined_var(x.clone()))?;
-            let offset = location * STACK_VAR_OFFSET;
-            Ok(format!("  ldr x{}, [x29, #-{}]", target_reg, offset))
-        }
-        Expression::ArithmeticExpr(x, op, y) => {
-            let x_expr = generate_expression(state, &x, target_reg + 1)?;
-            let y_expr = generate_expression(state, &y, target_reg + 2)?;
+    check_registers(state, target_reg, expr.span)?;
+    let ty = get_expression_type(expr)?;
+
+    match &expr.kind {
+        ExpressionKind::ValExpr(x) => generate_literal(state, x, ty, target_reg),
+        ExpressionKind::VarExpr(_)
+        | ExpressionKind::IndexExpr(..)
+        | ExpressionKind::FieldExpr(..)
+        | ExpressionKind::Deref(_)
+            if !ty.is_aggregate() =>
+        {
+            let (command, reg_prefix) = get_load_command(ty);
+            if let Some(offset) = get_frame_offset(state, expr)?
+                && offset <= MAX_LOAD_OFFSET
+            {
+                return Ok(format!(
+                    "  {} {}{}, [x29, #-{}]",
+                    command, reg_prefix, target_reg, offset
+                ));
+            }
             Ok(format!(
-                "{}\n{}\n  {} x{}, x{}, x{}",
+                "{}\n  {} {}{}, [x{}]",
+                generate_address(state, expr, target_reg)?,
+                command,
+                reg_prefix,
+                target_reg,
+                target_reg
+            ))
+        }
+        ExpressionKind::ArithmeticExpr(x, op, y) => {
+            let x_expr = generate_expression(state, x, target_reg)?;
+            let y_expr = generate_expression(state, y, target_reg + 1)?;
+            let operation = if let Type::Pointer(pointee) = get_expression_type(x)? {
+                generate_pointer_arithmetic(state, *op, ty, pointee, target_reg)
+            } else if ty.is_float() {
+                format!(
+                    "  {} {}, {}, {}",
+                    op.to_arm_command(ty),
+                    get_register(ty, target_reg),
+                    get_register(ty, target_reg),
+                    get_register(ty, target_reg + 1)
+                )
+            } else {
+                generate_integer_arithmetic(state, *op, ty, target_reg, expr.span)
+            };
+            Ok(format!("{}\n{}\n{}", x_expr, y_expr, operation))
+        }
+        ExpressionKind::BooleanExpr(x, op, y) => {
+            let operand_ty = get_expression_type(x)?;
+            let condition = op
+                .to_arm_condition(operand_ty)
+                .ok_or(GenError::from_internal(GenInternalError::InvalidOperator(
+                    *op,
+                )))?;
+            let x_expr = generate_expression(state, x, target_reg)?;
+            let y_expr = generate_expression(state, y, target_reg + 1)?;
+            Ok(format!(
+                "{}\n{}\n  {} {}, {}\n  cset x{}, {}",
                 x_expr,
                 y_expr,
-                op.to_arm_command(),
+                op.to_arm_command(operand_ty),
+                get_register(operand_ty, target_reg),
+                get_register(operand_ty, target_reg + 1),
                 target_reg,
-                target_reg + 1,
-                target_reg + 2
+                condition
             ))
         }
-        Expression::BooleanExpr(_x, _op, _y) => Ok(format!("Boolean expression not implemented")),
+        ExpressionKind::CastExpr(x, _) => generate_cast(state, x, ty, target_reg),
+        ExpressionKind::AddressOf(x) => generate_address(state, x, target_reg),
+        ExpressionKind::MatchExpr(scrutinee, arms) if !ty.is_aggregate() => {
+            let arms = arms
+                .iter()
+                .map(|x| MatchArm {
+                    pattern: x.pattern.clone(),
+                    body: &x.body,
+                })
+                .collect();
+            generate_match(state, scrutinee, arms, target_reg, |state, body| {
+                generate_expression(state, body, target_reg)
+            })
+        }
+        ExpressionKind::VarExpr(_)
+        | ExpressionKind::IndexExpr(..)
+        | ExpressionKind::FieldExpr(..)
+        | ExpressionKind::Deref(_)
+        | ExpressionKind::StructLiteral(..)
+        | ExpressionKind::VariantLiteral(..)
+        | ExpressionKind::MatchExpr(..) => Err(GenError::from_internal(
+            GenInternalError::InvalidPlace(expr.to_string()),
+        )),
+    }
+}
+
+/*
+* Places the address of a variable, array element, field or dereferenced pointer in the target
+* register. An index is evaluated into the register after it and scaled by the element size. Bounds
+* checks compare the index as unsigned so that negative indices are also caught, and are left out for
+* literal indices which are known to be in bounds. A field accessed through a pointer is at an offset
+* from the pointer itself
+*/
+fn generate_address(
+    state: &mut GenState,
+    place: &Expression,
+    target_reg: usize,
+) -> Result<String, GenError> {
+    if let Some(offset) = get_frame_offset(state, place)? {
+        return Ok(generate_frame_address(target_reg, offset));
+    }
+    match &place.kind {
+        ExpressionKind::IndexExpr(base, index) => {
+            let (elem_size, len) = get_element_layout(state, base)?;
+            let (address, index_reg, scratch) = (target_reg, target_reg + 1, target_reg + 2);
+            let mut lines = vec![
+                generate_address(state, base, address)?,
+                generate_expression(state, index, index_reg)?,
+            ];
+
+            if state.options.bounds_checks && get_literal_index(index, len).is_none() {
+                let trap = state.get_trap_label(
+                    &format!("Index out of bounds for length {}", len),
+                    index.span,
+                );
+                if len <= MAX_ADD_IMMEDIATE {
+                    lines.push(format!("  cmp x{}, #{}", index_reg, len));
+                } else {
+                    lines.push(load_int(scratch, len as i128));
+                    lines.push(format!("  cmp x{}, x{}", index_reg, scratch));
+                }
+                lines.push(format!("  b.hs {}", trap));
+            }
+
+            if elem_size.is_power_of_two() {
+                lines.push(format!(
+                    "  add x{}, x{}, x{}, lsl #{}",
+                    address,
+                    address,
+                    index_reg,
+                    elem_size.trailing_zeros()
+                ));
+            } else {
+                lines.push(load_int(scratch, elem_size as i128));
+                lines.push(format!(
+                    "  madd x{}, x{}, x{}, x{}",
+                    address, index_reg, scratch, address
+                ));
+            }
+            Ok(lines.join("\n"))
+        }
+        ExpressionKind::FieldExpr(base, field) => {
+            let offset = get_field_offset(state, base, field)?;
+            let base_address = match get_expression_type(base)? {
+                Type::Pointer(_) => generate_pointer(state, base, target_reg)?,
+                _ => generate_address(state, base, target_reg)?,
+            };
+            Ok(join_lines(vec![
+                base_address,
+                generate_offset_address(target_reg, target_reg, offset),
+            ]))
+        }
+        ExpressionKind::Deref(pointer) => generate_pointer(state, pointer, target_reg),
+        _ => Err(GenError::from_internal(GenInternalError::InvalidPlace(
+            place.to_string(),
+        ))),
+    }
+}
+
+/*
+* Evaluates a pointer which is about to be dereferenced into the target register, trapping if it is
+* null when null checks are enabled
+*/
+fn generate_pointer(
+    state: &mut GenState,
+    pointer: &Expression,
+    target_reg: usize,
+) -> Result<String, GenError> {
+    let mut lines = vec![generate_expression(state, pointer, target_reg)?];
+    if state.options.null_checks {
+        let trap = state.get_trap_label("Null pointer dereference", pointer.span);
+        lines.push(format!("  cbz x{}, {}", target_reg, trap));
+    }
+    Ok(lines.join("\n"))
+}
+
+/*
+* The offset below the frame pointer of a place which is known at compile time, i.e. a variable, a
+* field of one, or an element of one at a literal index which is in bounds
+*/
+fn get_frame_offset(state: &GenState, place: &Expression) -> Result<Option<usize>, GenError> {
+    match &place.kind {
+        ExpressionKind::VarExpr(v_name) => Ok(Some(state.get_var(v_name)?.0)),
+        ExpressionKind::IndexExpr(base, index) => {
+            let (elem_size, len) = get_element_layout(state, base)?;
+            match get_literal_index(index, len) {
+                Some(x) => Ok(get_frame_offset(state, base)?.map(|offset| offset - x * elem_size)),
+                None => Ok(None),
+            }
+        }
+        ExpressionKind::FieldExpr(base, _) if matches!(base.ty, Some(Type::Pointer(_))) => Ok(None),
+        ExpressionKind::FieldExpr(base, field) => {
+            let field_offset = get_field_offset(state, base, field)?;
+            Ok(get_frame_offset(state, base)?.map(|offset| offset - field_offset))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn get_literal_index(index: &Expression, len: usize) -> Option<usize> {
+    match &index.kind {
+        ExpressionKind::ValExpr(x) => match Literal::parse(x) {
+            Some(Literal::Int(x)) if (0..len as i128).contains(&x) => Some(x as usize),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn get_element_layout(state: &GenState, array: &Expression) -> Result<(usize, usize), GenError> {
+    match get_expression_type(array)? {
+        Type::Array(elem, len) => Ok((state.layouts.of(elem).size, *len)),
+        x => Err(GenError::from_internal(GenInternalError::InvalidPlace(
+            x.to_string(),
+        ))),
+    }
+}
+
+fn get_field_offset(state: &GenState, base: &Expression, field: &str) -> Result<usize, GenError> {
+    let ty = get_expression_type(base)?;
+    match ty.struct_name() {
+        Some(name) => Ok(state.layouts.field(name, field).offset),
+        None => Err(GenError::from_internal(GenInternalError::InvalidPlace(
+            ty.to_string(),
+        ))),
+    }
+}
+
+fn generate_frame_address(target_reg: usize, offset: usize) -> String {
+    if offset <= MAX_ADD_IMMEDIATE {
+        return format!("  sub x{}, x29, #{}", target_reg, offset);
+    }
+    format!(
+        "{}\n  sub x{}, x29, x{}",
+        load_int(target_reg, offset as i128),
+        target_reg,
+        target_reg
+    )
+}
+
+/*
+* Stores the value in the first register to a variable, using the register after it to hold the
+* address when the offset is too large for the store itself
+*/
+fn generate_frame_store(ty: &Type, offset: usize) -> String {
+    let (command, reg_prefix) = get_store_command(ty);
+    if offset <= MAX_LOAD_OFFSET {
+        return format!("  {} {}0, [x29, #-{}]", command, reg_prefix, offset);
+    }
+    format!(
+        "{}\n  {} {}0, [x1]",
+        generate_frame_address(1, offset),
+        command,
+        reg_prefix
+    )
+}
+
+/*
+* Adds an offset to the address in the base register, an offset which is too large for an immediate
+* is loaded into the target register first so the two must be different in that case
+*/
+fn generate_offset_address(target_reg: usize, base_reg: usize, offset: usize) -> String {
+    match offset {
+        0 if target_reg == base_reg => String::new(),
+        0 => format!("  mov x{}, x{}", target_reg, base_reg),
+        x if x <= MAX_ADD_IMMEDIATE => {
+            format!("  add x{}, x{}, #{}", target_reg, base_reg, x)
+        }
+        x => format!(
+            "{}\n  add x{}, x{}, x{}",
+            load_int(target_reg, x as i128),
+            target_reg,
+            base_reg,
+            target_reg
+        ),
+    }
+}
+
+/*
+* The operands are in the target register and the one after it, and the two after those are free to
+* use as scratch space. Both operands are read before the result is written over the first. Checked arithmetic on 64 bit types uses the flags set by adds/subs, or the
+* high half of the product for mul. Smaller types are held extended to 64 bits so the operation can't
+* overflow the register, instead the result is compared with itself extended from the width of its
+* type. Saturating arithmetic uses the same tests to select the minimum or maximum value instead
+*/
+fn generate_integer_arithmetic(
+    state: &mut GenState,
+    op: Operator,
+    ty: &Type,
+    target_reg: usize,
+    span: Span,
+) -> String {
+    let (result, lhs, rhs, scratch, sign) = (
+        format!("x{}", target_reg),
+        format!("x{}", target_reg),
+        format!("x{}", target_reg + 1),
+        format!("x{}", target_reg + 2),
+        format!("x{}", target_reg + 3),
+    );
+    let base_op = op.base_operator();
+    let command = base_op.to_arm_command(ty);
+    let flags_command = match base_op {
+        Operator::Add => "adds",
+        _ => "subs",
+    };
+    let is_wide = ty.size() == Some(8);
+    let mut lines = Vec::new();
+
+    if base_op == Operator::Div && state.options.overflow_checks {
+        let trap = state.get_trap_label("Division by zero", span);
+        lines.push(format!("  cbz {}, {}", rhs, trap));
+    }
+
+    match op.overflow_mode() {
+        OverflowMode::Checked if state.options.overflow_checks => {
+            let trap = state.get_trap_label("Arithmetic overflow", span);
+            match base_op {
+                Operator::Add | Operator::Sub if is_wide => {
+                    let condition = match (base_op, ty.is_signed()) {
+                        (_, true) => "vs",
+                        (Operator::Add, false) => "cs",
+                        _ => "lo",
+                    };
+                    lines.push(format!("  {} {}, {}, {}", flags_command, result, lhs, rhs));
+                    lines.push(format!("  b.{} {}", condition, trap));
+                }
+                Operator::Mul if is_wide => {
+                    if ty.is_signed() {
+                        lines.push(format!("  smulh {}, {}, {}", scratch, lhs, rhs));
+                        lines.push(format!("  mul {}, {}, {}", result, lhs, rhs));
+                        lines.push(format!("  cmp {}, {}, asr #63", scratch, result));
+                        lines.push(format!("  b.ne {}", trap));
+                    } else {
+                        lines.push(format!("  umulh {}, {}, {}", scratch, lhs, rhs));
+                        lines.push(format!("  mul {}, {}, {}", result, lhs, rhs));
+                        lines.push(format!("  cbnz {}, {}", scratch, trap));
+                    }
+                }
+                Operator::Div if is_wide => {
+                    if ty.is_signed() {
+                        lines.push(format!("  cmn {}, #1", rhs));
+                        lines.push(format!("  ccmp {}, #1, #0, eq", lhs));
+                        lines.push(format!("  b.vs {}", trap));
+                    }
+                    lines.push(format!("  {} {}, {}, {}", command, result, lhs, rhs));
+                }
+                _ => {
+                    lines.push(format!("  {} {}, {}, {}", command, result, lhs, rhs));
+                    if base_op != Operator::Div || ty.is_signed() {
+                        lines.push(format!(
+                            "  cmp {}, w{}, {}",
+                            result,
+                            target_reg,
+                            get_extend_operand(ty)
+                        ));
+                        lines.push(format!("  b.ne {}", trap));
+                    }
+                }
+            }
+        }
+        OverflowMode::Saturating => {
+            let (min, max) = ty.int_range().unwrap_or((0, 0));
+            match (base_op, ty.is_signed()) {
+                (Operator::Add | Operator::Sub, true) if is_wide => {
+                    lines.push(format!("  asr {}, {}, #63", scratch, lhs));
+                    lines.push(format!("  eor {}, {}, #0x{:x}", scratch, scratch, max));
+                    lines.push(format!("  {} {}, {}, {}", flags_command, result, lhs, rhs));
+                    lines.push(format!("  csel {}, {}, {}, vs", result, scratch, result));
+                }
+                (Operator::Mul, true) if is_wide => {
+                    lines.push(format!("  eor {}, {}, {}", sign, lhs, rhs));
+                    lines.push(format!("  asr {}, {}, #63", sign, sign));
+                    lines.push(format!("  eor {}, {}, #0x{:x}", sign, sign, max));
+                    lines.push(format!("  smulh {}, {}, {}", scratch, lhs, rhs));
+                    lines.push(format!("  mul {}, {}, {}", result, lhs, rhs));
+                    lines.push(format!("  cmp {}, {}, asr #63", scratch, result));
+                    lines.push(format!("  csel {}, {}, {}, ne", result, sign, result));
+                }
+                (Operator::Add, false) if is_wide => {
+                    lines.push(format!("  adds {}, {}, {}", result, lhs, rhs));
+                    lines.push(format!("  csinv {}, {}, xzr, cc", result, result));
+                }
+                (Operator::Sub, false) => {
+                    lines.push(format!("  subs {}, {}, {}", result, lhs, rhs));
+                    lines.push(format!("  csel {}, {}, xzr, cs", result, result));
+                }
+                (_, false) if is_wide => {
+                    lines.push(format!("  umulh {}, {}, {}", scratch, lhs, rhs));
+                    lines.push(format!("  mul {}, {}, {}", result, lhs, rhs));
+                    lines.push(format!("  cmp {}, #0", scratch));
+                    lines.push(format!("  csinv {}, {}, xzr, eq", result, result));
+                }
+                (_, is_signed) => {
+                    lines.push(format!("  {} {}, {}, {}", command, result, lhs, rhs));
+                    lines.push(load_int(target_reg + 2, max));
+                    lines.push(format!("  cmp {}, {}", result, scratch));
+                    let above = if is_signed { "gt" } else { "hi" };
+                    lines.push(format!(
+                        "  csel {}, {}, {}, {}",
+                        result, scratch, result, above
+                    ));
+                    if is_signed {
+                        lines.push(load_int(target_reg + 2, min));
+                        lines.push(format!("  cmp {}, {}", result, scratch));
+                        lines.push(format!("  csel {}, {}, {}, lt", result, scratch, result));
+                    }
+                }
+            }
+        }
+        OverflowMode::Checked | OverflowMode::Wrapping => {
+            lines.push(format!("  {} {}, {}, {}", command, result, lhs, rhs));
+            if let Some(extend) = get_extend_command(ty, target_reg) {
+                lines.push(extend);
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+/*
+* Adding an integer to a pointer moves it by that many values of the type it points to, and the
+* difference between two pointers is the number of values between them. The operands are in the
+* target register and the one after it, and the one after those is free to use as scratch space
+*/
+fn generate_pointer_arithmetic(
+    state: &GenState,
+    op: Operator,
+    ty: &Type,
+    pointee: &Type,
+    target_reg: usize,
+) -> String {
+    let (result, lhs, rhs, scratch) = (
+        format!("x{}", target_reg),
+        format!("x{}", target_reg),
+        format!("x{}", target_reg + 1),
+        format!("x{}", target_reg + 2),
+    );
+    let size = state.layouts.of(pointee).size.max(1);
+    let command = op.base_operator().to_arm_command(ty);
+
+    if let Type::Pointer(_) = ty {
+        if size.is_power_of_two() {
+            return format!(
+                "  {} {}, {}, {}, lsl #{}",
+                command,
+                result,
+                lhs,
+                rhs,
+                size.trailing_zeros()
+            );
+        }
+        let scaled = match op.base_operator() {
+            Operator::Add => "madd",
+            _ => "msub",
+        };
+        return format!(
+            "{}\n  {} {}, {}, {}, {}",
+            load_int(target_reg + 2, size as i128),
+            scaled,
+            result,
+            rhs,
+            scratch,
+            lhs
+        );
+    }
+
+    let difference = format!("  sub {}, {}, {}", result, lhs, rhs);
+    if size.is_power_of_two() {
+        return format!(
+            "{}\n  asr {}, {}, #{}",
+            difference,
+            result,
+            result,
+            size.trailing_zeros()
+        );
+    }
+    format!(
+        "{}\n{}\n  sdiv {}, {}, {}",
+        difference,
+        load_int(target_reg + 2, size as i128),
+        result,
+        result,
+        scratch
+    )
+}
+
+fn generate_cast(
+    state: &mut GenState,
+    expr: &Expression,
+    to_ty: &Type,
+    target_reg: usize,
+) -> Result<String, GenError> {
+    let from_ty = get_expression_type(expr)?;
+    if !from_ty.is_float() && !to_ty.is_float() {
+        let mut output = generate_expression(state, expr, target_reg)?;
+        if let Some(extend) = get_extend_command(to_ty, target_reg) {
+            output.push_str(&format!("\n{}", extend));
+        }
+        return Ok(output);
+    }
+
+    let command = match (from_ty.is_float(), to_ty.is_float()) {
+        (true, true) if from_ty == to_ty => "fmov",
+        (true, true) => "fcvt",
+        (false, true) if from_ty.is_signed() => "scvtf",
+        (false, true) => "ucvtf",
+        (true, false) if to_ty.is_signed() => "fcvtzs",
+        _ => "fcvtzu",
+    };
+    let mut output = format!(
+        "{}\n  {} {}, {}",
+        generate_expression(state, expr, target_reg + 1)?,
+        command,
+        get_register(to_ty, target_reg),
+        get_register(from_ty, target_reg + 1)
+    );
+    if let Some(extend) = get_extend_command(to_ty, target_reg) {
+        output.push_str(&format!("\n{}", extend));
+    }
+    Ok(output)
+}
+
+fn generate_literal(
+    state: &mut GenState,
+    value: &str,
+    ty: &Type,
+    target_reg: usize,
+) -> Result<String, GenError> {
+    let literal = Literal::parse(value);
+    if ty.is_float() {
+        let float_value = match literal {
+            Some(Literal::Float(x)) => x,
+            Some(Literal::Int(x)) => x as f64,
+            _ => {
+                return Err(GenError::from_internal(GenInternalError::InvalidLiteral(
+                    value.to_string(),
+                )));
+            }
+        };
+        let label = state.get_float_constant_label(float_value, ty);
+        return Ok(format!("  ldr {}, {}", get_register(ty, target_reg), label));
+    }
+
+    let int_value = match literal {
+        Some(Literal::Int(x)) => x,
+        Some(Literal::Bool(x)) => x as i128,
+        Some(Literal::Char(x)) => x as i128,
+        Some(Literal::Null) => 0,
+        _ => {
+            return Err(GenError::from_internal(GenInternalError::InvalidLiteral(
+                value.to_string(),
+            )));
+        }
+    };
+
+    Ok(load_int(target_reg, int_value))
+}
+
+/*
+* Negative values are only produced by constant folding, mov can encode small negative values (as
+* movn) and anything else is loaded as its 64 bit two's complement representation
+*/
+fn load_int(reg: usize, value: i128) -> String {
+    if (MIN_MOV_IMMEDIATE..=MAX_MOV_IMMEDIATE).contains(&value) {
+        format!("  mov x{}, #{}", reg, value)
+    } else {
+        format!("  ldr x{}, ={}", reg, value as u64)
+    }
+}
+
+/*
+* Values are only held in x0 to x15, x16 and x17 are left free for helpers like
+* adjust_stack_pointer and x18 is reserved by the platform. An expression may use the registers after
+* its target up to the last of the ones it is given, so one nested so deeply that they would run out
+* is rejected
+*/
+fn check_registers(state: &GenState, target_reg: usize, span: Span) -> Result<(), GenError> {
+    if target_reg + EXPRESSION_REGISTERS - 1 <= MAX_REGISTER {
+        return Ok(());
+    }
+    Err(GenError::TooManyRegisters(
+        state.source_map.location(span.start),
+    ))
+}
+
+fn get_expression_type(expr: &Expression) -> Result<&Type, GenError> {
+    expr.ty
+        .as_ref()
+        .ok_or(GenError::from_internal(GenInternalError::MissingType(
+            expr.to_string(),
+        )))
+}
+
+/*
+* Joins the lines of generated code, skipping any helper which had nothing to generate
+*/
+fn join_lines(lines: Vec<String>) -> String {
+    lines
+        .into_iter()
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn join_statement_lines(lines: Vec<String>) -> String {
+    let output = join_lines(lines);
+    if output.is_empty() {
+        return output;
+    }
+    output + "\n"
+}
+
+/*
+* Moves the stack pointer by a number of bytes which may be too large for an immediate, using x16
+* to hold it in that case
+*/
+fn adjust_stack_pointer(command: &str, size: usize) -> String {
+    if size <= MAX_ADD_IMMEDIATE {
+        return format!("  {} sp, sp, #{}\n", command, size);
+    }
+    format!(
+        "{}\n  {} sp, sp, x16\n",
+        load_int(16, size as i128),
+        command
+    )
+}
+
+fn get_float_label(idx: usize) -> String {
+    format!(".LF{}", idx)
+}
+
+fn get_register(ty: &Type, reg: usize) -> String {
+    match ty {
+        Type::F64 => format!("d{}", reg),
+        Type::F32 => format!("s{}", reg),
+        _ => format!("x{}", reg),
+    }
+}
+
+fn get_store_command(ty: &Type) -> (&'static str, &'static str) {
+    match ty {
+        Type::I64
+        | Type::U64
+        | Type::Pointer(_)
+        | Type::Array(..)
+        | Type::Struct(_)
+        | Type::Enum(_) => ("str", "x"),
+        Type::I32 | Type::U32 | Type::Char => ("str", "w"),
+        Type::I16 | Type::U16 => ("strh", "w"),
+        Type::I8 | Type::U8 | Type::Bool => ("strb", "w"),
+        Type::F64 => ("str", "d"),
+        Type::F32 => ("str", "s"),
+    }
+}
+
+fn get_load_command(ty: &Type) -> (&'static str, &'static str) {
+    match ty {
+        Type::I64
+        | Type::U64
+        | Type::Pointer(_)
+        | Type::Array(..)
+        | Type::Struct(_)
+        | Type::Enum(_) => ("ldr", "x"),
+        Type::I32 => ("ldrsw", "x"),
+        Type::U32 | Type::Char => ("ldr", "w"),
+        Type::I16 => ("ldrsh", "x"),
+        Type::U16 => ("ldrh", "w"),
+        Type::I8 => ("ldrsb", "x"),
+        Type::U8 | Type::Bool => ("ldrb", "w"),
+        Type::F64 => ("ldr", "d"),
+        Type::F32 => ("ldr", "s"),
+    }
+}
+
+fn get_extend_operand(ty: &Type) -> &'static str {
+    match ty {
+        Type::I8 => "sxtb",
+        Type::I16 => "sxth",
+        Type::I32 => "sxtw",
+        Type::U8 => "uxtb",
+        Type::U16 => "uxth",
+        _ => "uxtw",
+    }
+}
+
+fn get_extend_command(ty: &Type, reg: usize) -> Option<String> {
+    match ty {
+        Type::I8 => Some(format!("  sxtb x{}, w{}", reg, reg)),
+        Type::I16 => Some(format!("  sxth x{}, w{}", reg, reg)),
+        Type::I32 => Some(format!("  sxtw x{}, w{}", reg, reg)),
+        Type::U8 => Some(format!("  uxtb w{}, w{}", reg, reg)),
+        Type::U16 => Some(format!("  uxth w{}, w{}", reg, reg)),
+        Type::U32 | Type::Char => Some(format!("  mov w{}, w{}", reg, reg)),
+        Type::I64
+        | Type::U64
+        | Type::Bool
+        | Type::F32
+        | Type::F64
+        | Type::Pointer(_)
+        | Type::Array(..)
+        | Type::Struct(_)
+        | Type::Enum(_) => None,
     }
 }
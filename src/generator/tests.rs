@@ -1,13 +1,20 @@
-use crate::parser::Operator;
+use crate::parser::{EnumVariant, MatchArm, Operator, Pattern, PatternKind, StructField, Type};
 
 use super::*;
+use crate::source::{SourceMap, Span};
 
 const PRELUDE: &str = ".section .text\n.global _start\n_start:\n  mov x29, sp\n";
 const POSTLUDE: &str = "  mov x0, #0\n  mov x8, #93\n  svc #0\n";
 
 #[test]
 fn should_generate_empty_assembly_program() {
-    let output = generate(&Target::ARM64, Program { statements: vec![] }).unwrap();
+    let output = generate(
+        &Target::ARM64,
+        Program { statements: vec![] },
+        &GenOptions::default(),
+        &SourceMap::new(""),
+    )
+    .unwrap();
     starts_with_prelude(&output);
     ends_with_postlude(&output)
 }
@@ -19,14 +26,23 @@ fn should_generate_declaration_statement() {
         Program {
             statements: vec![Statement::DeclarationStatement {
                 v_name: String::from("x"),
-                expr: Expression::ValExpr(String::from("10")),
+                v_span: Span::default(),
+                ty: Type::I64,
+                mutable: false,
+                expr: Some(val_expr("10")),
+                span: Span::default(),
             }],
         },
+        &GenOptions::default(),
+        &SourceMap::new(""),
     )
     .unwrap();
 
     starts_with_prelude(&output);
-    contains_body(&output, "  mov x0, #10\n  str x0, [sp, #-16]!\n");
+    contains_body(
+        &output,
+        "  sub sp, sp, #16\n  mov x0, #10\n  str x0, [x29, #-8]\n",
+    );
     ends_with_postlude(&output)
 }
 
@@ -38,21 +54,31 @@ fn should_generate_multiple_declarations() {
             statements: vec![
                 Statement::DeclarationStatement {
                     v_name: String::from("x"),
-                    expr: Expression::ValExpr(String::from("10")),
+                    v_span: Span::default(),
+                    ty: Type::I64,
+                    mutable: false,
+                    expr: Some(val_expr("10")),
+                    span: Span::default(),
                 },
                 Statement::DeclarationStatement {
                     v_name: String::from("y"),
-                    expr: Expression::ValExpr(String::from("32")),
+                    v_span: Span::default(),
+                    ty: Type::I64,
+                    mutable: false,
+                    expr: Some(val_expr("32")),
+                    span: Span::default(),
                 },
             ],
         },
+        &GenOptions::default(),
+        &SourceMap::new(""),
     )
     .unwrap();
 
     starts_with_prelude(&output);
     contains_body(
         &output,
-        "  mov x0, #10\n  str x0, [sp, #-16]!\n  mov x0, #32\n  str x0, [sp, #-16]!\n",
+        "  sub sp, sp, #16\n  mov x0, #10\n  str x0, [x29, #-8]\n  mov x0, #32\n  str x0, [x29, #-16]\n",
     );
     ends_with_postlude(&output)
 }
@@ -65,21 +91,28 @@ fn should_generate_assignment_statement() {
             statements: vec![
                 Statement::DeclarationStatement {
                     v_name: String::from("x"),
-                    expr: Expression::ValExpr(String::from("10")),
+                    v_span: Span::default(),
+                    ty: Type::I64,
+                    mutable: false,
+                    expr: Some(val_expr("10")),
+                    span: Span::default(),
                 },
                 Statement::AssignmentStatement {
-                    v_name: String::from("x"),
-                    expr: Expression::ValExpr(String::from("32")),
+                    target: var_expr("x"),
+                    expr: val_expr("32"),
+                    span: Span::default(),
                 },
             ],
         },
+        &GenOptions::default(),
+        &SourceMap::new(""),
     )
     .unwrap();
 
     starts_with_prelude(&output);
     contains_body(
         &output,
-        "  mov x0, #10\n  str x0, [sp, #-16]!\n  mov x0, #32\n  str x0, [x29, #-16]\n",
+        "  sub sp, sp, #16\n  mov x0, #10\n  str x0, [x29, #-8]\n  mov x0, #32\n  str x0, [x29, #-8]\n",
     );
     ends_with_postlude(&output)
 }
@@ -92,21 +125,31 @@ fn should_support_variable_expressions() {
             statements: vec![
                 Statement::DeclarationStatement {
                     v_name: String::from("x"),
-                    expr: Expression::ValExpr(String::from("10")),
+                    v_span: Span::default(),
+                    ty: Type::I64,
+                    mutable: false,
+                    expr: Some(val_expr("10")),
+                    span: Span::default(),
                 },
                 Statement::DeclarationStatement {
                     v_name: String::from("y"),
-                    expr: Expression::VarExpr(String::from("x")),
+                    v_span: Span::default(),
+                    ty: Type::I64,
+                    mutable: false,
+                    expr: Some(var_expr("x")),
+                    span: Span::default(),
                 },
             ],
         },
+        &GenOptions::default(),
+        &SourceMap::new(""),
     )
     .unwrap();
 
     starts_with_prelude(&output);
     contains_body(
         &output,
-        "  mov x0, #10\n  str x0, [sp, #-16]!\n  ldr x0, [x29, #-16]\n  str x0, [sp, #-16]!\n",
+        "  sub sp, sp, #16\n  mov x0, #10\n  str x0, [x29, #-8]\n  ldr x0, [x29, #-8]\n  str x0, [x29, #-16]\n",
     );
     ends_with_postlude(&output)
 }
@@ -118,20 +161,26 @@ fn should_support_simple_arithmetic_expressions() {
         Program {
             statements: vec![Statement::DeclarationStatement {
                 v_name: String::from("x"),
-                expr: Expression::ArithmeticExpr(
-                    Box::from(Expression::ValExpr(String::from("10"))),
+                v_span: Span::default(),
+                ty: Type::I64,
+                mutable: false,
+                expr: Some(arithmetic_expr(
+                    val_expr("10"),
                     Operator::Add,
-                    Box::from(Expression::ValExpr(String::from("7"))),
-                ),
+                    val_expr("7"),
+                )),
+                span: Span::default(),
             }],
         },
+        &GenOptions::default(),
+        &SourceMap::new(""),
     )
     .unwrap();
 
     starts_with_prelude(&output);
     contains_body(
         &output,
-        "  mov x1, #10\n  mov x2, #7\n  add x0, x1, x2\n  str x0, [sp, #-16]!\n",
+        "  sub sp, sp, #16\n  mov x0, #10\n  mov x1, #7\n  add x0, x0, x1\n  str x0, [x29, #-8]\n",
     );
     ends_with_postlude(&output)
 }
@@ -143,24 +192,904 @@ fn should_support_chained_arithmetic_expressions() {
         Program {
             statements: vec![Statement::DeclarationStatement {
                 v_name: String::from("x"),
-                expr: Expression::ArithmeticExpr(
-                    Box::from(Expression::ValExpr(String::from("10"))),
+                v_span: Span::default(),
+                ty: Type::I64,
+                mutable: false,
+                expr: Some(arithmetic_expr(
+                    val_expr("10"),
+                    Operator::Add,
+                    arithmetic_expr(val_expr("20"), Operator::Sub, val_expr("12")),
+                )),
+                span: Span::default(),
+            }],
+        },
+        &GenOptions::default(),
+        &SourceMap::new(""),
+    )
+    .unwrap();
+
+    starts_with_prelude(&output);
+    contains_body(
+        &output,
+        "  sub sp, sp, #16\n  mov x0, #10\n  mov x1, #20\n  mov x2, #12\n  sub x1, x1, x2\n  add x0, x0, x1\n  str x0, [x29, #-8]\n",
+    );
+    ends_with_postlude(&output)
+}
+
+#[test]
+fn should_reuse_registers_for_long_chains() {
+    let chain = (0..40).fold(val_expr("1"), |x, _| {
+        arithmetic_expr(x, Operator::Add, val_expr("1"))
+    });
+    let output = generate(
+        &Target::ARM64,
+        Program {
+            statements: vec![declaration("x", Type::I64, chain)],
+        },
+        &GenOptions::default(),
+        &SourceMap::new(""),
+    )
+    .unwrap();
+
+    contains_body(
+        &output,
+        &format!(
+            "  sub sp, sp, #16\n  mov x0, #1\n{}  str x0, [x29, #-8]\n",
+            "  mov x1, #1\n  add x0, x0, x1\n".repeat(40)
+        ),
+    );
+}
+
+#[test]
+fn should_error_for_expression_needing_too_many_registers() {
+    let nested = (0..13).fold(val_expr("1"), |x, _| {
+        arithmetic_expr(val_expr("1"), Operator::Add, x)
+    });
+    let output = generate(
+        &Target::ARM64,
+        Program {
+            statements: vec![declaration("x", Type::I64, nested)],
+        },
+        &GenOptions::default(),
+        &SourceMap::new(""),
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        output.to_string(),
+        "Expression needs more registers than are available, Line: 1, Col: 1"
+    );
+}
+
+#[test]
+fn should_reserve_space_for_declaration_without_value() {
+    let output = generate(
+        &Target::ARM64,
+        Program {
+            statements: vec![
+                Statement::DeclarationStatement {
+                    v_name: String::from("x"),
+                    v_span: Span::default(),
+                    ty: Type::I64,
+                    mutable: false,
+                    expr: None,
+                    span: Span::default(),
+                },
+                declaration("y", Type::I64, val_expr("10")),
+                Statement::AssignmentStatement {
+                    target: var_expr("x"),
+                    expr: var_expr("y"),
+                    span: Span::default(),
+                },
+            ],
+        },
+        &GenOptions::default(),
+        &SourceMap::new(""),
+    )
+    .unwrap();
+
+    starts_with_prelude(&output);
+    contains_body(
+        &output,
+        "  sub sp, sp, #16\n  mov x0, #10\n  str x0, [x29, #-16]\n  ldr x0, [x29, #-16]\n  str x0, [x29, #-8]\n",
+    );
+    ends_with_postlude(&output)
+}
+
+#[test]
+fn should_release_block_variables_at_end_of_block() {
+    let output = generate(
+        &Target::ARM64,
+        Program {
+            statements: vec![
+                Statement::DeclarationStatement {
+                    v_name: String::from("x"),
+                    v_span: Span::default(),
+                    ty: Type::I64,
+                    mutable: false,
+                    expr: Some(val_expr("10")),
+                    span: Span::default(),
+                },
+                Statement::BlockStatement {
+                    statements: vec![
+                        Statement::DeclarationStatement {
+                            v_name: String::from("x"),
+                            v_span: Span::default(),
+                            ty: Type::I64,
+                            mutable: false,
+                            expr: Some(var_expr("x")),
+                            span: Span::default(),
+                        },
+                        Statement::AssignmentStatement {
+                            target: var_expr("x"),
+                            expr: val_expr("3"),
+                            span: Span::default(),
+                        },
+                    ],
+                    span: Span::default(),
+                },
+                Statement::AssignmentStatement {
+                    target: var_expr("x"),
+                    expr: val_expr("4"),
+                    span: Span::default(),
+                },
+            ],
+        },
+        &GenOptions::default(),
+        &SourceMap::new(""),
+    )
+    .unwrap();
+
+    starts_with_prelude(&output);
+    contains_body(
+        &output,
+        "  sub sp, sp, #16\n  mov x0, #10\n  str x0, [x29, #-8]\n  sub sp, sp, #16\n  ldr x0, [x29, #-8]\n  str x0, [x29, #-24]\n  mov x0, #3\n  str x0, [x29, #-24]\n  add sp, sp, #16\n  mov x0, #4\n  str x0, [x29, #-8]\n",
+    );
+    ends_with_postlude(&output)
+}
+
+#[test]
+fn should_use_sized_loads_and_stores() {
+    let output = generate(
+        &Target::ARM64,
+        Program {
+            statements: vec![
+                declaration("a", Type::I8, typed_val_expr("10", Type::I8)),
+                declaration("b", Type::U16, typed_val_expr("300", Type::U16)),
+                declaration("c", Type::I32, typed_val_expr("70000", Type::I32)),
+                declaration("d", Type::Char, typed_val_expr("'a'", Type::Char)),
+                declaration("e", Type::Bool, typed_val_expr("true", Type::Bool)),
+                declaration("f", Type::I8, typed_var_expr("a", Type::I8)),
+                declaration("g", Type::U16, typed_var_expr("b", Type::U16)),
+                declaration("h", Type::I32, typed_var_expr("c", Type::I32)),
+            ],
+        },
+        &GenOptions::default(),
+        &SourceMap::new(""),
+    )
+    .unwrap();
+
+    starts_with_prelude(&output);
+    contains_body(
+        &output,
+        "  sub sp, sp, #32\n  mov x0, #10\n  strb w0, [x29, #-1]\n  mov x0, #300\n  strh w0, [x29, #-4]\n  ldr x0, =70000\n  str w0, [x29, #-8]\n  mov x0, #97\n  str w0, [x29, #-12]\n  mov x0, #1\n  strb w0, [x29, #-13]\n  ldrsb x0, [x29, #-1]\n  strb w0, [x29, #-14]\n  ldrh w0, [x29, #-4]\n  strh w0, [x29, #-16]\n  ldrsw x0, [x29, #-8]\n  str w0, [x29, #-20]\n",
+    );
+    ends_with_postlude(&output)
+}
+
+#[test]
+fn should_extend_narrow_arithmetic_results() {
+    let output = generate(
+        &Target::ARM64,
+        Program {
+            statements: vec![declaration(
+                "x",
+                Type::U8,
+                arithmetic_expr(
+                    typed_val_expr("200", Type::U8),
+                    Operator::Add,
+                    typed_val_expr("100", Type::U8),
+                ),
+            )],
+        },
+        &GenOptions::default(),
+        &SourceMap::new(""),
+    )
+    .unwrap();
+
+    starts_with_prelude(&output);
+    contains_body(
+        &output,
+        "  sub sp, sp, #16\n  mov x0, #200\n  mov x1, #100\n  add x0, x0, x1\n  uxtb w0, w0\n  strb w0, [x29, #-1]\n",
+    );
+    ends_with_postlude(&output)
+}
+
+#[test]
+fn should_generate_comparisons_using_signedness() {
+    let output = generate(
+        &Target::ARM64,
+        Program {
+            statements: vec![
+                declaration(
+                    "x",
+                    Type::Bool,
+                    typed_expr(
+                        ExpressionKind::BooleanExpr(
+                            Box::from(val_expr("1")),
+                            Operator::LessThan,
+                            Box::from(val_expr("2")),
+                        ),
+                        Type::Bool,
+                    ),
+                ),
+                declaration(
+                    "y",
+                    Type::Bool,
+                    typed_expr(
+                        ExpressionKind::BooleanExpr(
+                            Box::from(typed_val_expr("1", Type::U32)),
+                            Operator::GreaterThanEq,
+                            Box::from(typed_val_expr("2", Type::U32)),
+                        ),
+                        Type::Bool,
+                    ),
+                ),
+            ],
+        },
+        &GenOptions::default(),
+        &SourceMap::new(""),
+    )
+    .unwrap();
+
+    starts_with_prelude(&output);
+    contains_body(
+        &output,
+        "  sub sp, sp, #16\n  mov x0, #1\n  mov x1, #2\n  cmp x0, x1\n  cset x0, lt\n  strb w0, [x29, #-1]\n  mov x0, #1\n  mov x1, #2\n  cmp x0, x1\n  cset x0, hs\n  strb w0, [x29, #-2]\n",
+    );
+    ends_with_postlude(&output)
+}
+
+#[test]
+fn should_generate_casts_between_integer_types() {
+    let output = generate(
+        &Target::ARM64,
+        Program {
+            statements: vec![
+                declaration("x", Type::I64, val_expr("300")),
+                declaration(
+                    "y",
+                    Type::I8,
+                    typed_expr(
+                        ExpressionKind::CastExpr(Box::from(var_expr("x")), Type::I8),
+                        Type::I8,
+                    ),
+                ),
+            ],
+        },
+        &GenOptions::default(),
+        &SourceMap::new(""),
+    )
+    .unwrap();
+
+    starts_with_prelude(&output);
+    contains_body(
+        &output,
+        "  sub sp, sp, #16\n  mov x0, #300\n  str x0, [x29, #-8]\n  ldr x0, [x29, #-8]\n  sxtb x0, w0\n  strb w0, [x29, #-9]\n",
+    );
+    ends_with_postlude(&output)
+}
+
+#[test]
+fn should_generate_float_declarations_from_literal_pool() {
+    let output = generate(
+        &Target::ARM64,
+        Program {
+            statements: vec![
+                declaration("x", Type::F64, typed_val_expr("1.5", Type::F64)),
+                declaration("y", Type::F32, typed_val_expr("2", Type::F32)),
+                declaration("z", Type::F64, typed_val_expr("1.5", Type::F64)),
+                declaration("w", Type::F64, typed_var_expr("x", Type::F64)),
+            ],
+        },
+        &GenOptions::default(),
+        &SourceMap::new(""),
+    )
+    .unwrap();
+
+    starts_with_prelude(&output);
+    assert!(output.ends_with(
+        "  svc #0\n.align 3\n.LF0:\n  .quad 0x3ff8000000000000\n.align 2\n.LF1:\n  .word 0x40000000\n"
+    ));
+    assert!(output.contains(
+        "  ldr d0, .LF0\n  str d0, [x29, #-8]\n  ldr s0, .LF1\n  str s0, [x29, #-12]\n  ldr d0, .LF0\n  str d0, [x29, #-24]\n  ldr d0, [x29, #-8]\n  str d0, [x29, #-32]\n"
+    ));
+}
+
+#[test]
+fn should_generate_float_arithmetic() {
+    let test_cases = [
+        (Operator::Add, "fadd"),
+        (Operator::Sub, "fsub"),
+        (Operator::Mul, "fmul"),
+        (Operator::Div, "fdiv"),
+    ];
+
+    for (op, command) in test_cases {
+        let output = generate(
+            &Target::ARM64,
+            Program {
+                statements: vec![declaration(
+                    "x",
+                    Type::F64,
+                    arithmetic_expr(
+                        typed_val_expr("1.5", Type::F64),
+                        op,
+                        typed_val_expr("2.5", Type::F64),
+                    ),
+                )],
+            },
+            &GenOptions::default(),
+            &SourceMap::new(""),
+        )
+        .unwrap();
+
+        assert!(output.contains(&format!(
+            "  ldr d0, .LF0\n  ldr d1, .LF1\n  {} d0, d0, d1\n  str d0, [x29, #-8]\n",
+            command
+        )));
+    }
+}
+
+#[test]
+fn should_generate_integer_multiplication_and_division() {
+    let test_cases = [
+        (Operator::Mul, Type::I64, "mul"),
+        (Operator::Div, Type::I64, "sdiv"),
+        (Operator::Div, Type::U64, "udiv"),
+    ];
+
+    for (op, ty, command) in test_cases {
+        let output = generate(
+            &Target::ARM64,
+            Program {
+                statements: vec![declaration(
+                    "x",
+                    ty.clone(),
+                    arithmetic_expr(typed_val_expr("6", ty.clone()), op, typed_val_expr("3", ty)),
+                )],
+            },
+            &GenOptions::default(),
+            &SourceMap::new(""),
+        )
+        .unwrap();
+
+        contains_body(
+            &output,
+            &format!(
+                "  sub sp, sp, #16\n  mov x0, #6\n  mov x1, #3\n  {} x0, x0, x1\n  str x0, [x29, #-8]\n",
+                command
+            ),
+        );
+    }
+}
+
+#[test]
+fn should_generate_float_comparisons() {
+    let test_cases = [
+        (Operator::LessThan, "mi"),
+        (Operator::LessThanEq, "ls"),
+        (Operator::GreaterThan, "gt"),
+        (Operator::GreaterThanEq, "ge"),
+        (Operator::Equal, "eq"),
+        (Operator::NotEqual, "ne"),
+    ];
+
+    for (op, condition) in test_cases {
+        let output = generate(
+            &Target::ARM64,
+            Program {
+                statements: vec![declaration(
+                    "x",
+                    Type::Bool,
+                    typed_expr(
+                        ExpressionKind::BooleanExpr(
+                            Box::from(typed_val_expr("1.5", Type::F64)),
+                            op,
+                            Box::from(typed_val_expr("2.5", Type::F64)),
+                        ),
+                        Type::Bool,
+                    ),
+                )],
+            },
+            &GenOptions::default(),
+            &SourceMap::new(""),
+        )
+        .unwrap();
+
+        assert!(output.contains(&format!(
+            "  ldr d0, .LF0\n  ldr d1, .LF1\n  fcmp d0, d1\n  cset x0, {}\n  strb w0, [x29, #-1]\n",
+            condition
+        )));
+    }
+}
+
+#[test]
+fn should_generate_conversions_between_ints_and_floats() {
+    let test_cases = [
+        (Type::I64, Type::F64, "  scvtf d0, x1"),
+        (Type::U32, Type::F32, "  ucvtf s0, x1"),
+        (Type::F64, Type::I64, "  fcvtzs x0, d1"),
+        (Type::F32, Type::U8, "  fcvtzu x0, s1\n  uxtb w0, w0"),
+        (Type::F32, Type::F64, "  fcvt d0, s1"),
+        (Type::F64, Type::F32, "  fcvt s0, d1"),
+    ];
+
+    for (from_ty, to_ty, expected) in test_cases {
+        let output = generate(
+            &Target::ARM64,
+            Program {
+                statements: vec![
+                    declaration("x", from_ty.clone(), typed_val_expr("1", from_ty.clone())),
+                    declaration(
+                        "y",
+                        to_ty.clone(),
+                        typed_expr(
+                            ExpressionKind::CastExpr(
+                                Box::from(typed_var_expr("x", from_ty.clone())),
+                                to_ty.clone(),
+                            ),
+                            to_ty,
+                        ),
+                    ),
+                ],
+            },
+            &GenOptions::default(),
+            &SourceMap::new(""),
+        )
+        .unwrap();
+
+        let (load, reg) = get_load_command(&from_ty);
+        let offset = from_ty.size().unwrap();
+        assert!(output.contains(&format!(
+            "  {} {}1, [x29, #-{}]\n{}\n",
+            load, reg, offset, expected
+        )));
+    }
+}
+
+#[test]
+fn should_generate_folded_negative_literals_without_storing_constants() {
+    let output = generate(
+        &Target::ARM64,
+        Program {
+            statements: vec![
+                Statement::ConstStatement {
+                    v_name: String::from("A"),
+                    v_span: Span::default(),
+                    ty: Type::I64,
+                    expr: val_expr("-4"),
+                    span: Span::default(),
+                },
+                declaration("x", Type::I64, val_expr("-4")),
+                declaration("y", Type::I64, val_expr("-100000")),
+            ],
+        },
+        &GenOptions::default(),
+        &SourceMap::new(""),
+    )
+    .unwrap();
+
+    starts_with_prelude(&output);
+    contains_body(
+        &output,
+        "  sub sp, sp, #16\n  mov x0, #-4\n  str x0, [x29, #-8]\n  ldr x0, =18446744073709451616\n  str x0, [x29, #-16]\n",
+    );
+    ends_with_postlude(&output)
+}
+
+#[test]
+fn should_generate_if_and_while_statements() {
+    let condition = || {
+        typed_expr(
+            ExpressionKind::BooleanExpr(
+                Box::new(var_expr("x")),
+                Operator::LessThan,
+                Box::new(val_expr("5")),
+            ),
+            Type::Bool,
+        )
+    };
+    let assignment = |value| {
+        Box::new(Statement::BlockStatement {
+            statements: vec![Statement::AssignmentStatement {
+                target: var_expr("x"),
+                expr: val_expr(value),
+                span: Span::default(),
+            }],
+            span: Span::default(),
+        })
+    };
+    let output = generate(
+        &Target::ARM64,
+        Program {
+            statements: vec![
+                declaration("x", Type::I64, val_expr("1")),
+                Statement::WhileStatement {
+                    condition: condition(),
+                    body: Box::new(Statement::IfStatement {
+                        condition: condition(),
+                        then_block: assignment("2"),
+                        else_block: Some(assignment("3")),
+                        span: Span::default(),
+                    }),
+                    span: Span::default(),
+                },
+                Statement::IfStatement {
+                    condition: condition(),
+                    then_block: assignment("4"),
+                    else_block: None,
+                    span: Span::default(),
+                },
+            ],
+        },
+        &GenOptions::default(),
+        &SourceMap::new(""),
+    )
+    .unwrap();
+
+    let compare = "  ldr x0, [x29, #-8]\n  mov x1, #5\n  cmp x0, x1\n  cset x0, lt\n";
+    starts_with_prelude(&output);
+    contains_body(
+        &output,
+        &format!(
+            "  sub sp, sp, #16\n  mov x0, #1\n  str x0, [x29, #-8]\n.Lwhile0:\n{0}  cbz x0, .Lendwhile0\n{0}  cbz x0, .Lelse1\n  mov x0, #2\n  str x0, [x29, #-8]\n  b .Lendif1\n.Lelse1:\n  mov x0, #3\n  str x0, [x29, #-8]\n.Lendif1:\n  b .Lwhile0\n.Lendwhile0:\n{0}  cbz x0, .Lendif2\n  mov x0, #4\n  str x0, [x29, #-8]\n.Lendif2:\n",
+            compare
+        ),
+    );
+    ends_with_postlude(&output)
+}
+
+#[test]
+fn should_trap_on_overflow_when_checks_are_enabled() {
+    let output = generate(
+        &Target::ARM64,
+        Program {
+            statements: vec![declaration(
+                "x",
+                Type::I64,
+                arithmetic_expr(
+                    typed_val_expr("6", Type::I64),
                     Operator::Add,
-                    Box::from(Expression::ArithmeticExpr(
-                        Box::from(Expression::ValExpr(String::from("20"))),
-                        Operator::Sub,
-                        Box::from(Expression::ValExpr(String::from("12"))),
-                    )),
+                    typed_val_expr("3", Type::I64),
                 ),
+            )],
+        },
+        &GenOptions {
+            overflow_checks: true,
+            bounds_checks: false,
+            null_checks: false,
+        },
+        &SourceMap::new("i64 x = 6 + 3;"),
+    )
+    .unwrap();
+
+    starts_with_prelude(&output);
+    assert!(output.contains(
+        "  mov x0, #6\n  mov x1, #3\n  adds x0, x0, x1\n  b.vs .Ltrap0\n  str x0, [x29, #-8]\n"
+    ));
+    assert!(output.ends_with(
+        "  svc #0\n.Ltrap0:\n  adr x1, .Ltrapmsg0\n  mov x2, #37\n  b .Lpanic\n.Lpanic:\n  mov x0, #2\n  mov x8, #64\n  svc #0\n  mov x0, #101\n  mov x8, #93\n  svc #0\n.Ltrapmsg0:\n  .ascii \"Arithmetic overflow, Line: 1, Col: 1\\n\"\n"
+    ));
+}
+
+#[test]
+fn should_check_narrow_results_against_their_type() {
+    let test_cases = [
+        (
+            Operator::Add,
+            Type::I32,
+            "  add x0, x0, x1\n  cmp x0, w0, sxtw\n  b.ne .Ltrap0\n  str w0, [x29, #-4]\n",
+        ),
+        (
+            Operator::Div,
+            Type::U8,
+            "  cbz x1, .Ltrap0\n  udiv x0, x0, x1\n  strb w0, [x29, #-1]\n",
+        ),
+        (
+            Operator::WrappingAdd,
+            Type::U8,
+            "  add x0, x0, x1\n  uxtb w0, w0\n  strb w0, [x29, #-1]\n",
+        ),
+        (
+            Operator::SaturatingAdd,
+            Type::U64,
+            "  adds x0, x0, x1\n  csinv x0, x0, xzr, cc\n  str x0, [x29, #-8]\n",
+        ),
+    ];
+
+    for (op, ty, expected) in test_cases {
+        let output = generate(
+            &Target::ARM64,
+            Program {
+                statements: vec![declaration(
+                    "x",
+                    ty.clone(),
+                    arithmetic_expr(typed_val_expr("6", ty.clone()), op, typed_val_expr("3", ty)),
+                )],
+            },
+            &GenOptions {
+                overflow_checks: true,
+                bounds_checks: false,
+                null_checks: false,
+            },
+            &SourceMap::new(""),
+        )
+        .unwrap();
+
+        assert!(
+            output.contains(&format!("  mov x0, #6\n  mov x1, #3\n{}", expected)),
+            "{}",
+            output
+        );
+    }
+}
+
+#[test]
+fn should_generate_array_declarations_and_element_accesses() {
+    let array_ty = Type::Array(Box::new(Type::I64), 3);
+    let element = |index| {
+        typed_expr(
+            ExpressionKind::IndexExpr(
+                Box::new(typed_var_expr("a", array_ty.clone())),
+                Box::new(index),
+            ),
+            Type::I64,
+        )
+    };
+    let output = generate(
+        &Target::ARM64,
+        Program {
+            statements: vec![
+                Statement::DeclarationStatement {
+                    v_name: String::from("a"),
+                    v_span: Span::default(),
+                    ty: array_ty.clone(),
+                    mutable: true,
+                    expr: None,
+                    span: Span::default(),
+                },
+                declaration("i", Type::I64, val_expr("1")),
+                Statement::AssignmentStatement {
+                    target: element(var_expr("i")),
+                    expr: val_expr("5"),
+                    span: Span::default(),
+                },
+                declaration("x", Type::I64, element(val_expr("2"))),
+            ],
+        },
+        &GenOptions {
+            overflow_checks: false,
+            bounds_checks: true,
+            null_checks: false,
+        },
+        &SourceMap::new("a[i] = 5;"),
+    )
+    .unwrap();
+
+    starts_with_prelude(&output);
+    assert!(output.contains(
+        "  sub sp, sp, #48\n  sub x0, x29, #24\n  str xzr, [x0, #0]\n  str xzr, [x0, #8]\n  str xzr, [x0, #16]\n  mov x0, #1\n  str x0, [x29, #-32]\n"
+    ));
+    assert!(output.contains(
+        "  mov x0, #5\n  sub x1, x29, #24\n  ldr x2, [x29, #-32]\n  cmp x2, #3\n  b.hs .Ltrap0\n  add x1, x1, x2, lsl #3\n  str x0, [x1]\n"
+    ));
+    assert!(output.contains("  ldr x0, [x29, #-8]\n  str x0, [x29, #-40]\n"));
+    assert!(output.contains("  .ascii \"Index out of bounds for length 3, Line: 1, Col: 1\\n\"\n"));
+}
+
+#[test]
+fn should_use_registers_for_large_stack_offsets() {
+    let output = generate(
+        &Target::ARM64,
+        Program {
+            statements: vec![Statement::BlockStatement {
+                statements: vec![
+                    Statement::DeclarationStatement {
+                        v_name: String::from("a"),
+                        v_span: Span::default(),
+                        ty: Type::Array(Box::new(Type::U8), 5000),
+                        mutable: true,
+                        expr: None,
+                        span: Span::default(),
+                    },
+                    declaration("x", Type::I64, val_expr("1")),
+                    Statement::AssignmentStatement {
+                        target: var_expr("x"),
+                        expr: val_expr("2"),
+                        span: Span::default(),
+                    },
+                ],
+                span: Span::default(),
             }],
         },
+        &GenOptions::default(),
+        &SourceMap::new(""),
+    )
+    .unwrap();
+
+    starts_with_prelude(&output);
+    contains_body(
+        &output,
+        "  mov x16, #5008\n  sub sp, sp, x16\n  mov x0, #5000\n  sub x0, x29, x0\n  mov x2, #625\n.Lcopy0:\n  str xzr, [x0], #8\n  subs x2, x2, #1\n  b.ne .Lcopy0\n  mov x0, #1\n  mov x1, #5008\n  sub x1, x29, x1\n  str x0, [x1]\n  mov x0, #2\n  mov x1, #5008\n  sub x1, x29, x1\n  str x0, [x1]\n  mov x16, #5008\n  add sp, sp, x16\n",
+    );
+    ends_with_postlude(&output)
+}
+
+#[test]
+fn should_lay_out_struct_fields_and_copy_structs() {
+    let struct_ty = Type::Struct(String::from("P"));
+    let field_ty = |name: &str| StructField {
+        name: String::from(name),
+        ty: if name == "x" { Type::I32 } else { Type::I64 },
+        span: Span::default(),
+    };
+    let literal = typed_expr(
+        ExpressionKind::StructLiteral(
+            String::from("P"),
+            vec![
+                (String::from("y"), val_expr("2")),
+                (String::from("x"), typed_val_expr("1", Type::I32)),
+            ],
+        ),
+        struct_ty.clone(),
+    );
+    let field = typed_expr(
+        ExpressionKind::FieldExpr(
+            Box::new(typed_var_expr("q", struct_ty.clone())),
+            String::from("x"),
+        ),
+        Type::I32,
+    );
+    let output = generate(
+        &Target::ARM64,
+        Program {
+            statements: vec![
+                Statement::StructStatement {
+                    name: String::from("P"),
+                    fields: vec![field_ty("x"), field_ty("y")],
+                    span: Span::default(),
+                },
+                declaration("p", struct_ty.clone(), literal),
+                declaration("q", struct_ty.clone(), typed_var_expr("p", struct_ty)),
+                declaration("z", Type::I32, field),
+            ],
+        },
+        &GenOptions::default(),
+        &SourceMap::new(""),
     )
     .unwrap();
 
     starts_with_prelude(&output);
     contains_body(
         &output,
-        "  mov x1, #10\n  mov x3, #20\n  mov x4, #12\n  sub x2, x3, x4\n  add x0, x1, x2\n  str x0, [sp, #-16]!\n",
+        "  sub sp, sp, #48\n  sub x0, x29, #16\n  mov x1, #1\n  str w1, [x0, #0]\n  mov x1, #2\n  str x1, [x0, #8]\n  sub x0, x29, #32\n  sub x1, x29, #16\n  ldr x2, [x1, #0]\n  str x2, [x0, #0]\n  ldr x2, [x1, #8]\n  str x2, [x0, #8]\n  ldrsw x0, [x29, #-32]\n  str w0, [x29, #-36]\n",
+    );
+    ends_with_postlude(&output)
+}
+
+#[test]
+fn should_generate_addresses_and_dereference_pointers() {
+    let pointer_ty = Type::Pointer(Box::new(Type::I64));
+    let address = typed_expr(
+        ExpressionKind::AddressOf(Box::new(var_expr("a"))),
+        pointer_ty.clone(),
+    );
+    let offset = arithmetic_expr(
+        typed_var_expr("p", pointer_ty.clone()),
+        Operator::Add,
+        val_expr("2"),
+    );
+    let deref = typed_expr(
+        ExpressionKind::Deref(Box::new(typed_var_expr("q", pointer_ty.clone()))),
+        Type::I64,
+    );
+    let output = generate(
+        &Target::ARM64,
+        Program {
+            statements: vec![
+                declaration("a", Type::I64, val_expr("1")),
+                declaration("p", pointer_ty.clone(), address),
+                declaration("q", pointer_ty, offset),
+                declaration("b", Type::I64, deref),
+            ],
+        },
+        &GenOptions {
+            null_checks: true,
+            ..GenOptions::default()
+        },
+        &SourceMap::new(""),
+    )
+    .unwrap();
+
+    starts_with_prelude(&output);
+    assert!(output.contains(
+        "  sub x0, x29, #8\n  str x0, [x29, #-16]\n  ldr x0, [x29, #-16]\n  mov x1, #2\n  add x0, x0, x1, lsl #3\n  str x0, [x29, #-24]\n"
+    ));
+    assert!(output.contains(
+        "  ldr x0, [x29, #-24]\n  cbz x0, .Ltrap0\n  ldr x0, [x0]\n  str x0, [x29, #-32]\n"
+    ));
+    assert!(output.contains("  .ascii \"Null pointer dereference, Line: 1, Col: 1\\n\"\n"));
+}
+
+#[test]
+fn should_branch_on_enum_tags_in_match() {
+    let enum_ty = Type::Enum(String::from("Opt"));
+    let definition = Statement::EnumStatement {
+        name: String::from("Opt"),
+        variants: vec![
+            EnumVariant {
+                name: String::from("Some"),
+                payload: vec![Type::I64],
+                span: Span::default(),
+            },
+            EnumVariant {
+                name: String::from("None"),
+                payload: vec![],
+                span: Span::default(),
+            },
+        ],
+        span: Span::default(),
+    };
+    let literal = typed_expr(
+        ExpressionKind::VariantLiteral(
+            String::from("Opt"),
+            String::from("Some"),
+            vec![val_expr("5")],
+        ),
+        enum_ty.clone(),
+    );
+    let arm = |kind: PatternKind, body: Expression| MatchArm {
+        pattern: Pattern {
+            kind,
+            span: Span::default(),
+        },
+        body,
+    };
+    let matched = typed_expr(
+        ExpressionKind::MatchExpr(
+            Box::new(typed_var_expr("o", enum_ty.clone())),
+            vec![
+                arm(
+                    PatternKind::Variant(
+                        String::from("Opt"),
+                        String::from("Some"),
+                        vec![(String::from("x"), Span::default())],
+                    ),
+                    var_expr("x"),
+                ),
+                arm(PatternKind::Wildcard, val_expr("0")),
+            ],
+        ),
+        Type::I64,
+    );
+    let output = generate(
+        &Target::ARM64,
+        Program {
+            statements: vec![
+                definition,
+                declaration("o", enum_ty, literal),
+                declaration("n", Type::I64, matched),
+            ],
+        },
+        &GenOptions::default(),
+        &SourceMap::new(""),
+    )
+    .unwrap();
+    starts_with_prelude(&output);
+    contains_body(
+        &output,
+        "  sub sp, sp, #32\n  sub x0, x29, #16\n  mov x1, #0\n  str w1, [x0]\n  mov x1, #5\n  str x1, [x0, #8]\n  sub sp, sp, #16\n  sub x0, x29, #48\n  sub x1, x29, #16\n  ldr x2, [x1, #0]\n  str x2, [x0, #0]\n  ldr x2, [x1, #8]\n  str x2, [x0, #8]\n  ldr w0, [x0]\n  cmp w0, #0\n  b.eq .Larm0_0\n.Larm0_1:\n  mov x0, #0\n  b .Lendmatch0\n.Larm0_0:\n  ldr x0, [x29, #-40]\n.Lendmatch0:\n  add sp, sp, #16\n  str x0, [x29, #-24]\n",
     );
     ends_with_postlude(&output)
 }
@@ -171,10 +1100,13 @@ fn should_return_err_when_assigning_to_undefined_var() {
         &Target::ARM64,
         Program {
             statements: vec![Statement::AssignmentStatement {
-                v_name: String::from("x"),
-                expr: Expression::ValExpr(String::from("32")),
+                target: var_expr("x"),
+                expr: val_expr("32"),
+                span: Span::default(),
             }],
         },
+        &GenOptions::default(),
+        &SourceMap::new(""),
     )
     .unwrap_err();
 
@@ -198,3 +1130,44 @@ fn contains_body(output: &str, expected_body: &str) {
         expected_body
     );
 }
+
+fn typed_expr(kind: ExpressionKind, ty: Type) -> Expression {
+    let mut expr = Expression::new(kind, Span::default());
+    expr.ty = Some(ty);
+    expr
+}
+
+fn val_expr(value: &str) -> Expression {
+    typed_val_expr(value, Type::I64)
+}
+
+fn typed_val_expr(value: &str, ty: Type) -> Expression {
+    typed_expr(ExpressionKind::ValExpr(String::from(value)), ty)
+}
+
+fn var_expr(v_name: &str) -> Expression {
+    typed_var_expr(v_name, Type::I64)
+}
+
+fn typed_var_expr(v_name: &str, ty: Type) -> Expression {
+    typed_expr(ExpressionKind::VarExpr(String::from(v_name)), ty)
+}
+
+fn arithmetic_expr(lhs: Expression, op: Operator, rhs: Expression) -> Expression {
+    let ty = lhs.ty.clone().unwrap();
+    typed_expr(
+        ExpressionKind::ArithmeticExpr(Box::from(lhs), op, Box::from(rhs)),
+        ty,
+    )
+}
+
+fn declaration(v_name: &str, ty: Type, expr: Expression) -> Statement {
+    Statement::DeclarationStatement {
+        v_name: String::from(v_name),
+        v_span: Span::default(),
+        ty,
+        mutable: false,
+        expr: Some(expr),
+        span: Span::default(),
+    }
+}
@@ -0,0 +1,437 @@
+#[cfg(test)]
+mod tests;
+
+use core::fmt;
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    os::unix::process::ExitStatusExt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    lints::Lint,
+    manifest::{MANIFEST_FILE, ManifestError},
+    optimiser::Pass,
+    target::Target,
+    toolchain::{Tool, Toolchain, ToolchainError},
+};
+
+/*
+* Given as the source file or output path to read from stdin or write to stdout instead
+*/
+pub const STDIO_PATH: &str = "-";
+
+#[derive(Debug)]
+pub enum InputError {
+    NotEnoughArgs,
+    InvalidArg(String),
+    UnknownOption(String),
+    MissingValue(String),
+    FileNameParseError(PathBuf),
+    FileNotFound(PathBuf),
+    NotAFile(PathBuf),
+    FileReadFailure(PathBuf, io::Error),
+    StdinReadFailure(io::Error),
+    InvalidTarget(String),
+    InvalidPass(String),
+    InvalidLint(String),
+    InvalidEmit(String),
+    BinaryToStdout(Emit),
+    OutputForEntries(String, PathBuf),
+    ManifestNotFound,
+    ManifestFailure(ManifestError),
+    ContentWriteFailure(Box<dyn std::error::Error>),
+    ExecutableGenerationFailure { command: String, reason: String },
+    ToolchainFailure(ToolchainError),
+    ExecutionFailure(PathBuf, io::Error),
+}
+
+impl std::error::Error for InputError {}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputError::NotEnoughArgs => write!(
+                f,
+                "Not enough arguments provided, use -h flag to see expected usage",
+            ),
+            InputError::InvalidArg(x) => write!(
+                f,
+                "Invalid argument {}, use -h flag to see expected usage",
+                x
+            ),
+            InputError::UnknownOption(x) => write!(
+                f,
+                "Unknown option {}, use --help to see the available options",
+                x
+            ),
+            InputError::MissingValue(x) => write!(f, "Missing value for option {}", x),
+            InputError::FileNotFound(x) => write!(f, "Unable to find file path: {}", x.display()),
+            InputError::NotAFile(x) => {
+                write!(f, "Expected a source file but found: {}", x.display())
+            }
+            InputError::FileReadFailure(x, e) => {
+                write!(f, "Unable to read file {}, caused by: {}", x.display(), e)
+            }
+            InputError::StdinReadFailure(e) => {
+                write!(f, "Unable to read source from stdin, caused by: {}", e)
+            }
+            InputError::FileNameParseError(x) => {
+                write!(f, "Unable to find a file name in path: {}", x.display())
+            }
+            InputError::InvalidTarget(x) => write!(
+                f,
+                "Invalid target argument {}, available values are: [{}]",
+                x,
+                Target::get_values_string()
+            ),
+            InputError::InvalidPass(x) => write!(
+                f,
+                "Invalid optimisation pass {}, available values are: [{}]",
+                x,
+                Pass::get_values_string()
+            ),
+            InputError::InvalidLint(x) => write!(
+                f,
+                "Invalid lint {}, available values are: [all,{}]",
+                x,
+                Lint::get_values_string()
+            ),
+            InputError::InvalidEmit(x) => write!(
+                f,
+                "Invalid emit kind {}, available values are: [{}]",
+                x,
+                Emit::get_values_string()
+            ),
+            InputError::BinaryToStdout(x) => write!(
+                f,
+                "Unable to write {} output to stdout, only the text stages can be written there",
+                x
+            ),
+            InputError::OutputForEntries(project, x) => write!(
+                f,
+                "Unable to write the output of every entry of project {} to {}, use --out-dir instead",
+                project,
+                x.display()
+            ),
+            InputError::ManifestNotFound => write!(
+                f,
+                "No source file given and no {} found in the current directory or its parents, use -h flag to see expected usage",
+                MANIFEST_FILE
+            ),
+            InputError::ManifestFailure(e) => write!(f, "{}", e),
+            InputError::ContentWriteFailure(e) => {
+                write!(f, "Failed to write output to file, caused by: {}", e)
+            }
+            InputError::ExecutableGenerationFailure { command, reason } => {
+                write!(
+                    f,
+                    "Failed to generate executable file, {} failed with {}",
+                    command, reason
+                )
+            }
+            InputError::ToolchainFailure(e) => write!(f, "{}", e),
+            InputError::ExecutionFailure(x, e) => {
+                write!(f, "Unable to run {}, caused by: {}", x.display(), e)
+            }
+        }
+    }
+}
+
+/*
+* The stages of compilation whose output can be written to the build folder, in the order they run
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Emit {
+    Tokens,
+    Ast,
+    Ir,
+    Asm,
+    Obj,
+    Exe,
+}
+
+#[derive(Debug)]
+pub struct EmitParseError;
+
+impl Emit {
+    const ALL: [Emit; 6] = [
+        Emit::Tokens,
+        Emit::Ast,
+        Emit::Ir,
+        Emit::Asm,
+        Emit::Obj,
+        Emit::Exe,
+    ];
+
+    pub fn get_values_string() -> String {
+        Emit::ALL
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Emit::Tokens => ".tokens",
+            Emit::Ast => ".ast",
+            Emit::Ir => ".ir",
+            Emit::Asm => ".s",
+            Emit::Obj => ".o",
+            Emit::Exe => "",
+        }
+    }
+}
+
+impl fmt::Display for Emit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Emit::Tokens => write!(f, "tokens"),
+            Emit::Ast => write!(f, "ast"),
+            Emit::Ir => write!(f, "ir"),
+            Emit::Asm => write!(f, "asm"),
+            Emit::Obj => write!(f, "obj"),
+            Emit::Exe => write!(f, "exe"),
+        }
+    }
+}
+
+impl FromStr for Emit {
+    type Err = EmitParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Emit::ALL
+            .into_iter()
+            .find(|x| x.to_string() == s)
+            .ok_or(EmitParseError)
+    }
+}
+
+/*
+* Where each artifact is written, every artifact goes in the output directory named after the source
+* file except for the output of the last stage, which can be given its own path
+*/
+pub struct Output {
+    dir: PathBuf,
+    name: String,
+    path: Option<PathBuf>,
+    emit: Vec<Emit>,
+    keep_intermediates: bool,
+}
+
+impl Output {
+    pub fn new(
+        dir: PathBuf,
+        name: String,
+        path: Option<PathBuf>,
+        emit: Vec<Emit>,
+        keep_intermediates: bool,
+    ) -> Self {
+        Self {
+            dir,
+            name,
+            path,
+            emit,
+            keep_intermediates,
+        }
+    }
+
+    pub fn path(&self, emit: Emit) -> PathBuf {
+        match &self.path {
+            Some(path) if Some(&emit) == self.emit.iter().max() => path.clone(),
+            _ => self.dir.join(format!("{}{}", self.name, emit.extension())),
+        }
+    }
+
+    fn last(&self) -> Emit {
+        self.emit.iter().max().copied().unwrap_or(Emit::Exe)
+    }
+
+    /*
+     * Assembly and object files which were only produced as the input to a later stage are removed
+     * once that stage is done, unless intermediates are kept
+     */
+    fn remove_intermediate(&self, emit: Emit) -> Result<(), InputError> {
+        if self.keep_intermediates || self.emit.contains(&emit) {
+            return Ok(());
+        }
+        fs::remove_file(self.path(emit)).map_err(|e| InputError::ContentWriteFailure(e.into()))
+    }
+}
+
+pub fn read_file(file_path: &Path) -> Result<String, InputError> {
+    if file_path.as_os_str() == STDIO_PATH {
+        let mut contents = String::new();
+        io::stdin()
+            .read_to_string(&mut contents)
+            .map_err(InputError::StdinReadFailure)?;
+        return Ok(contents);
+    }
+    if file_path.is_dir() {
+        return Err(InputError::NotAFile(file_path.to_path_buf()));
+    }
+    fs::read_to_string(file_path).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => InputError::FileNotFound(file_path.to_path_buf()),
+        _ => InputError::FileReadFailure(file_path.to_path_buf(), e),
+    })
+}
+
+/*
+* The assembly is always written since the assembler reads it from a file, the object file and
+* executable are only produced when a stage up to them was asked for. Assembly written to stdout is
+* therefore always the last stage
+*/
+pub fn write_program(
+    output: &Output,
+    content: &str,
+    toolchain: &Toolchain,
+) -> Result<(), InputError> {
+    let assembly_path = output.path(Emit::Asm);
+    write_build_file(&assembly_path, content)?;
+    if output.last() < Emit::Obj {
+        return Ok(());
+    }
+
+    let object_path = output.path(Emit::Obj);
+    create_parent_dir(&object_path)?;
+    let assembler = toolchain
+        .assembler()
+        .map_err(InputError::ToolchainFailure)?;
+    generate_object_file(&assembler, &object_path, &assembly_path)?;
+    output.remove_intermediate(Emit::Asm)?;
+    if output.last() < Emit::Exe {
+        return Ok(());
+    }
+
+    let executable_path = output.path(Emit::Exe);
+    create_parent_dir(&executable_path)?;
+    let linker = toolchain.linker().map_err(InputError::ToolchainFailure)?;
+    generate_executable_file(&linker, &executable_path, &object_path)?;
+    output.remove_intermediate(Emit::Obj)
+}
+
+pub fn write_artifact(output: &Output, emit: Emit, content: &str) -> Result<(), InputError> {
+    write_build_file(&output.path(emit), content)
+}
+
+pub fn write_graph(output: &Output, suffix: &str, content: &str) -> Result<(), InputError> {
+    write_build_file(
+        &output.dir.join(format!("{}.{}.dot", output.name, suffix)),
+        content,
+    )
+}
+
+fn create_parent_dir(path: &Path) -> Result<(), InputError> {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => {
+            fs::create_dir_all(dir).map_err(|e| InputError::ContentWriteFailure(e.into()))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn write_build_file(path: &Path, content: &str) -> Result<(), InputError> {
+    if path.as_os_str() == STDIO_PATH {
+        return io::stdout()
+            .write_all(content.as_bytes())
+            .map_err(|e| InputError::ContentWriteFailure(e.into()));
+    }
+    create_parent_dir(path)?;
+
+    let mut file = File::create(path).map_err(|e| InputError::ContentWriteFailure(e.into()))?;
+    file.write_all(content.as_bytes())
+        .map_err(|e| InputError::ContentWriteFailure(e.into()))
+}
+
+fn generate_object_file(
+    assembler: &Tool,
+    object_path: &Path,
+    assembly_path: &Path,
+) -> Result<(), InputError> {
+    let mut command = assembler.command();
+    command
+        .arg(assembly_path)
+        .arg("-o")
+        .arg(object_path)
+        .arg("-g");
+    run_tool(assembler, command)
+}
+
+fn generate_executable_file(
+    linker: &Tool,
+    executable_path: &Path,
+    object_path: &Path,
+) -> Result<(), InputError> {
+    let mut command = linker.command();
+    command.arg(object_path).arg("-o").arg(executable_path);
+    run_tool(linker, command)
+}
+
+/*
+* A tool has failed only when it exits unsuccessfully, anything it writes to stderr while succeeding
+* is passed on as warnings
+*/
+fn run_tool(tool: &Tool, mut command: std::process::Command) -> Result<(), InputError> {
+    let command_line = std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|x| x.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let result = command
+        .output()
+        .map_err(|e| InputError::ExecutableGenerationFailure {
+            command: command_line.clone(),
+            reason: e.to_string(),
+        })?;
+
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    if !result.status.success() {
+        return Err(InputError::ExecutableGenerationFailure {
+            command: command_line,
+            reason: match stderr.trim_end() {
+                "" => result.status.to_string(),
+                x => format!("{}:\n{}", result.status, x),
+            },
+        });
+    }
+    for line in stderr.lines().filter(|x| !x.trim().is_empty()) {
+        eprintln!("Warning: {} [{}]", line, tool.program);
+    }
+    Ok(())
+}
+
+/*
+* Runs the executable which was just built, through the runner when the host can't run it directly,
+* and returns its exit code. The program shares the compiler's stdin, stdout and stderr, and a program
+* killed by a signal gives 128 plus the signal like a shell would. A bare file name is run from the
+* current directory rather than being looked up on the PATH
+*/
+pub fn run_executable(
+    output: &Output,
+    toolchain: &Toolchain,
+    args: &[String],
+) -> Result<i32, InputError> {
+    let mut path = output.path(Emit::Exe);
+    if path.parent().is_some_and(|x| x.as_os_str().is_empty()) {
+        path = Path::new(".").join(path);
+    }
+    let mut command = match toolchain.runner().map_err(InputError::ToolchainFailure)? {
+        Some(runner) => {
+            let mut command = runner.command();
+            command.arg(&path);
+            command
+        }
+        None => std::process::Command::new(&path),
+    };
+    let status = command
+        .args(args)
+        .status()
+        .map_err(|e| InputError::ExecutionFailure(path.clone(), e))?;
+    Ok(status
+        .code()
+        .or(status.signal().map(|x| 128 + x))
+        .unwrap_or(1))
+}
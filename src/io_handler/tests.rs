@@ -0,0 +1,127 @@
+use super::*;
+
+#[test]
+fn test_parse_emit_kinds() {
+    for emit in Emit::ALL {
+        assert_eq!(Emit::from_str(&emit.to_string()).unwrap(), emit);
+    }
+    assert_eq!(Emit::from_str("asm").unwrap(), Emit::Asm);
+    assert!(Emit::from_str("assembly").is_err());
+    assert!(Emit::from_str("Asm").is_err());
+    assert_eq!(Emit::get_values_string(), "tokens,ast,ir,asm,obj,exe");
+}
+
+#[test]
+fn test_read_file_errors() {
+    let dir = std::env::temp_dir().join(format!("mgs_io_read_{}", std::process::id()));
+    fs::create_dir_all(dir.join(".hidden")).unwrap();
+    fs::write(dir.join(".hidden/main.mgs"), "int x = 1;").unwrap();
+    fs::write(dir.join("invalid.mgs"), [0xff, 0xfe]).unwrap();
+
+    let contents = read_file(&dir.join(".hidden/main.mgs"));
+    let directory = read_file(&dir.join(".hidden"));
+    let missing = read_file(&dir.join("missing.mgs"));
+    let invalid = read_file(&dir.join("invalid.mgs"));
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(contents.unwrap(), "int x = 1;");
+    assert!(matches!(directory, Err(InputError::NotAFile(x)) if x == dir.join(".hidden")));
+    assert!(matches!(missing, Err(InputError::FileNotFound(x)) if x == dir.join("missing.mgs")));
+    assert!(
+        matches!(invalid, Err(InputError::FileReadFailure(x, _)) if x == dir.join("invalid.mgs"))
+    );
+}
+
+#[test]
+fn test_output_path_applies_only_to_the_last_stage() {
+    let output = Output::new(
+        PathBuf::from("build"),
+        String::from("main"),
+        Some(PathBuf::from("bin/app")),
+        vec![Emit::Asm, Emit::Exe],
+        true,
+    );
+    assert_eq!(output.last(), Emit::Exe);
+    assert_eq!(output.path(Emit::Exe), PathBuf::from("bin/app"));
+    assert_eq!(output.path(Emit::Asm), PathBuf::from("build/main.s"));
+    assert_eq!(output.path(Emit::Obj), PathBuf::from("build/main.o"));
+
+    let output = Output::new(
+        PathBuf::from("build"),
+        String::from("lib.test"),
+        Some(PathBuf::from(STDIO_PATH)),
+        vec![Emit::Ir, Emit::Asm],
+        true,
+    );
+    assert_eq!(output.last(), Emit::Asm);
+    assert_eq!(output.path(Emit::Asm), PathBuf::from(STDIO_PATH));
+    assert_eq!(output.path(Emit::Ir), PathBuf::from("build/lib.test.ir"));
+
+    let output = Output::new(
+        PathBuf::from("build"),
+        String::from("main"),
+        None,
+        vec![Emit::Exe],
+        true,
+    );
+    assert_eq!(output.path(Emit::Exe), PathBuf::from("build/main"));
+}
+
+#[test]
+fn test_intermediates_are_removed_unless_kept_or_emitted() {
+    let dir = std::env::temp_dir().join(format!("mgs_io_intermediates_{}", std::process::id()));
+    let output = |emit, keep_intermediates| {
+        for emit in [Emit::Asm, Emit::Obj] {
+            write_build_file(&dir.join(format!("main{}", emit.extension())), "").unwrap();
+        }
+        Output::new(
+            dir.clone(),
+            String::from("main"),
+            None,
+            emit,
+            keep_intermediates,
+        )
+    };
+
+    let kept = output(vec![Emit::Exe], true);
+    kept.remove_intermediate(Emit::Asm).unwrap();
+    kept.remove_intermediate(Emit::Obj).unwrap();
+    assert!(kept.path(Emit::Asm).exists());
+    assert!(kept.path(Emit::Obj).exists());
+
+    let removed = output(vec![Emit::Asm, Emit::Exe], false);
+    removed.remove_intermediate(Emit::Asm).unwrap();
+    removed.remove_intermediate(Emit::Obj).unwrap();
+    let asm_exists = removed.path(Emit::Asm).exists();
+    let obj_exists = removed.path(Emit::Obj).exists();
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(asm_exists);
+    assert!(!obj_exists);
+}
+
+#[test]
+fn test_tools_fail_by_their_exit_status() {
+    let tool = |script: &str| Tool {
+        program: String::from("sh"),
+        args: vec![String::from("-c"), script.to_string()],
+    };
+
+    let warning = tool("echo w >&2");
+    assert!(run_tool(&warning, warning.command()).is_ok());
+
+    let silent_failure = tool("exit 3");
+    let e = run_tool(&silent_failure, silent_failure.command()).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "Failed to generate executable file, sh -c exit 3 failed with exit status: 3"
+    );
+
+    let failure = tool("echo e >&2; exit 1");
+    let mut command = failure.command();
+    command.arg("main.s");
+    let e = run_tool(&failure, command).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "Failed to generate executable file, sh -c echo e >&2; exit 1 main.s failed with exit status: 1:\ne"
+    );
+}
@@ -0,0 +1,217 @@
+/*
+* The layout of a type is the size and alignment in bytes of its values in memory. Primitive types
+* are aligned to their size, an array is aligned like its elements and a struct like its most aligned
+* field. Fields are placed in the order they are defined, each at the next offset which is a multiple
+* of its alignment, and the size of a struct is rounded up to its alignment so that every element of
+* an array of structs is aligned
+*
+* Each variant of an enum is laid out like a struct whose first field is a u32 tag, the index of the
+* variant, followed by the values it carries. Every variant shares the same memory, so an enum is as
+* large as its largest variant and aligned like its most aligned one
+*/
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::HashMap;
+
+use crate::parser::{EnumVariant, Program, Statement, StructField, Type};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    pub size: usize,
+    pub align: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldLayout {
+    pub name: String,
+    pub ty: Type,
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariantLayout {
+    pub name: String,
+    pub tag: usize,
+    pub payload: Vec<FieldLayout>,
+}
+
+#[derive(Debug)]
+struct StructLayout {
+    layout: Layout,
+    fields: Vec<FieldLayout>,
+}
+
+#[derive(Debug)]
+struct EnumLayout {
+    layout: Layout,
+    variants: Vec<VariantLayout>,
+}
+
+#[derive(Debug, Default)]
+pub struct Layouts {
+    structs: HashMap<String, StructLayout>,
+    enums: HashMap<String, EnumLayout>,
+}
+
+#[derive(Clone, Copy)]
+enum Definition<'a> {
+    Struct(&'a [StructField]),
+    Enum(&'a [EnumVariant]),
+}
+
+const TAG_TYPE: Type = Type::U32;
+
+impl Layouts {
+    /*
+     * Semantic analysis has already checked that every struct and enum used is defined exactly once
+     * and that none of them contain themselves
+     */
+    pub fn new(program: &Program) -> Self {
+        let definitions: HashMap<&str, Definition> = program
+            .statements
+            .iter()
+            .filter_map(|x| match x {
+                Statement::StructStatement { name, fields, .. } => {
+                    Some((name.as_str(), Definition::Struct(fields)))
+                }
+                Statement::EnumStatement { name, variants, .. } => {
+                    Some((name.as_str(), Definition::Enum(variants)))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let mut layouts = Layouts::default();
+        for name in definitions.keys() {
+            layouts.compute_named(name, &definitions);
+        }
+        layouts
+    }
+
+    fn compute_named(&mut self, name: &str, definitions: &HashMap<&str, Definition>) -> Layout {
+        if let Some(x) = self.structs.get(name) {
+            return x.layout;
+        }
+        if let Some(x) = self.enums.get(name) {
+            return x.layout;
+        }
+
+        match definitions[name] {
+            Definition::Struct(definition) => {
+                let fields: Vec<(String, Type)> = definition
+                    .iter()
+                    .map(|x| (x.name.clone(), x.ty.clone()))
+                    .collect();
+                let (layout, fields) = self.compute_fields(fields, definitions);
+                self.structs
+                    .insert(name.to_string(), StructLayout { layout, fields });
+                layout
+            }
+            Definition::Enum(definition) => {
+                let mut layout = Layout { size: 0, align: 1 };
+                let mut variants = Vec::new();
+                for (tag, variant) in definition.iter().enumerate() {
+                    let fields = std::iter::once(TAG_TYPE)
+                        .chain(variant.payload.iter().cloned())
+                        .enumerate()
+                        .map(|(idx, ty)| (idx.to_string(), ty))
+                        .collect();
+                    let (variant_layout, mut payload) = self.compute_fields(fields, definitions);
+                    payload.remove(0);
+                    layout.size = layout.size.max(variant_layout.size);
+                    layout.align = layout.align.max(variant_layout.align);
+                    variants.push(VariantLayout {
+                        name: variant.name.clone(),
+                        tag,
+                        payload,
+                    });
+                }
+                layout.size = align_up(layout.size, layout.align);
+                self.enums
+                    .insert(name.to_string(), EnumLayout { layout, variants });
+                layout
+            }
+        }
+    }
+
+    /*
+     * Places fields one after another like the fields of a struct, returning the layout of them all
+     */
+    fn compute_fields(
+        &mut self,
+        definition: Vec<(String, Type)>,
+        definitions: &HashMap<&str, Definition>,
+    ) -> (Layout, Vec<FieldLayout>) {
+        let mut size = 0;
+        let mut align = 1;
+        let mut fields = Vec::new();
+        for (name, ty) in definition {
+            let layout = self.compute(&ty, definitions);
+            let offset = align_up(size, layout.align);
+            size = offset + layout.size;
+            align = align.max(layout.align);
+            fields.push(FieldLayout { name, ty, offset });
+        }
+
+        let layout = Layout {
+            size: align_up(size, align),
+            align,
+        };
+        (layout, fields)
+    }
+
+    fn compute(&mut self, ty: &Type, definitions: &HashMap<&str, Definition>) -> Layout {
+        match ty {
+            Type::Struct(name) | Type::Enum(name) => self.compute_named(name, definitions),
+            Type::Array(elem, len) => array_layout(self.compute(elem, definitions), *len),
+            _ => self.of(ty),
+        }
+    }
+
+    pub fn of(&self, ty: &Type) -> Layout {
+        match ty {
+            Type::Struct(name) => self.structs[name].layout,
+            Type::Enum(name) => self.enums[name].layout,
+            Type::Array(elem, len) => array_layout(self.of(elem), *len),
+            _ => {
+                let size = ty.size().expect("Primitive types should have a size");
+                Layout { size, align: size }
+            }
+        }
+    }
+
+    pub fn fields(&self, name: &str) -> &[FieldLayout] {
+        &self.structs[name].fields
+    }
+
+    pub fn field(&self, name: &str, field: &str) -> &FieldLayout {
+        self.fields(name)
+            .iter()
+            .find(|x| x.name == field)
+            .expect("Fields should be checked by semantic analysis")
+    }
+
+    pub fn variants(&self, name: &str) -> &[VariantLayout] {
+        &self.enums[name].variants
+    }
+
+    pub fn variant(&self, name: &str, variant: &str) -> &VariantLayout {
+        self.variants(name)
+            .iter()
+            .find(|x| x.name == variant)
+            .expect("Variants should be checked by semantic analysis")
+    }
+}
+
+fn array_layout(elem: Layout, len: usize) -> Layout {
+    Layout {
+        size: elem.size.saturating_mul(len),
+        align: elem.align,
+    }
+}
+
+pub fn align_up(value: usize, align: usize) -> usize {
+    value.next_multiple_of(align)
+}
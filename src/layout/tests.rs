@@ -0,0 +1,92 @@
+use super::*;
+use crate::{lexer, parser};
+
+fn layouts_for(contents: &str) -> Layouts {
+    let tokens = lexer::parse_text(contents).unwrap();
+    Layouts::new(&parser::parse_module(tokens).unwrap().program)
+}
+
+fn offsets(layouts: &Layouts, name: &str, fields: &[&str]) -> Vec<usize> {
+    fields
+        .iter()
+        .map(|x| layouts.field(name, x).offset)
+        .collect()
+}
+
+#[test]
+fn test_primitive_types_should_be_aligned_to_their_size() {
+    let layouts = Layouts::default();
+    let test_cases = vec![
+        (Type::Bool, 1),
+        (Type::I16, 2),
+        (Type::Char, 4),
+        (Type::F32, 4),
+        (Type::U64, 8),
+    ];
+    for (ty, size) in test_cases {
+        assert_eq!(layouts.of(&ty), Layout { size, align: size });
+    }
+    assert_eq!(
+        layouts.of(&Type::Array(Box::new(Type::I32), 3)),
+        Layout { size: 12, align: 4 }
+    );
+}
+
+#[test]
+fn test_fields_should_be_padded_to_their_alignment() {
+    let layouts = layouts_for("struct S { u8 a; i32 b; u8 c; i64 d; i16 e; }");
+    assert_eq!(
+        offsets(&layouts, "S", &["a", "b", "c", "d", "e"]),
+        vec![0, 4, 8, 16, 24]
+    );
+    assert_eq!(
+        layouts.of(&Type::Struct("S".to_string())),
+        Layout { size: 32, align: 8 }
+    );
+}
+
+#[test]
+fn test_nested_structs_and_arrays() {
+    let layouts = layouts_for(
+        "struct Line { Point start; Point end; u8[3] tag; }\n\
+         struct Point { i32 x; i32 y; }\n\
+         struct Empty { }",
+    );
+    assert_eq!(
+        offsets(&layouts, "Line", &["start", "end", "tag"]),
+        vec![0, 8, 16]
+    );
+    assert_eq!(
+        layouts.of(&Type::Struct("Line".to_string())),
+        Layout { size: 20, align: 4 }
+    );
+    assert_eq!(
+        layouts.of(&Type::Array(Box::new(Type::Struct("Line".to_string())), 2)),
+        Layout { size: 40, align: 4 }
+    );
+    assert_eq!(
+        layouts.of(&Type::Struct("Empty".to_string())),
+        Layout { size: 0, align: 1 }
+    );
+}
+
+#[test]
+fn test_enum_variants_share_a_tag_and_the_largest_payload() {
+    let layouts = layouts_for("enum Opt { Some(i64); Pair(u8, u32); None; }");
+    let payload_offsets = |variant: &str| -> Vec<usize> {
+        layouts
+            .variant("Opt", variant)
+            .payload
+            .iter()
+            .map(|x| x.offset)
+            .collect()
+    };
+    assert_eq!(payload_offsets("Some"), vec![8]);
+    assert_eq!(payload_offsets("Pair"), vec![4, 8]);
+    assert_eq!(payload_offsets("None"), Vec::<usize>::new());
+    assert_eq!(layouts.variant("Opt", "None").tag, 2);
+    assert_eq!(
+        layouts.of(&Type::Enum("Opt".to_string())),
+        Layout { size: 16, align: 8 }
+    );
+}
@@ -3,6 +3,7 @@ mod tests;
 
 use crate::{
     constants,
+    source::{Located, SourceMap, Span},
     token::{TextLocation, Token, TokenType},
 };
 
@@ -13,6 +14,14 @@ pub enum LexError {
 
 impl std::error::Error for LexError {}
 
+impl Located for LexError {
+    fn location_mut(&mut self) -> Option<&mut TextLocation> {
+        match self {
+            LexError::InvalidToken(x) => Some(&mut x.location),
+        }
+    }
+}
+
 impl std::fmt::Display for LexError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -21,30 +30,53 @@ impl std::fmt::Display for LexError {
     }
 }
 
-struct LexState {
-    location: TextLocation,
+/*
+* Token start and end indices are byte offsets into the source text, so they can always be used to
+* slice the contents even when it contains multi-byte characters. The spans given to tokens are
+* moved by the offset the contents start at in the source map
+*/
+struct LexState<'a> {
+    source_map: &'a SourceMap,
+    offset: usize,
     t_start_idx: usize,
     t_end_idx: usize,
     tokens: Vec<Token>,
+    quote: Option<char>,
+    escaped: bool,
 }
 
-impl LexState {
-    fn new() -> Self {
+impl<'a> LexState<'a> {
+    fn new(source_map: &'a SourceMap, offset: usize) -> Self {
         LexState {
-            location: TextLocation::new(),
+            source_map,
+            offset,
             t_start_idx: 0,
             t_end_idx: 0,
             tokens: Vec::new(),
+            quote: None,
+            escaped: false,
         }
     }
 }
 
 pub fn parse_text(contents: &str) -> Result<Vec<Token>, LexError> {
-    let mut state = LexState::new();
+    parse_file(&SourceMap::new(contents), 0, contents)
+}
 
-    for (i, c) in contents.chars().enumerate() {
+/*
+* Lexes a file which was added to the source map at the given offset
+*/
+pub fn parse_file(
+    source_map: &SourceMap,
+    offset: usize,
+    contents: &str,
+) -> Result<Vec<Token>, LexError> {
+    let mut state = LexState::new(source_map, offset);
+
+    for (i, c) in contents.char_indices() {
         handle_next_char(&mut state, contents, c, i)?;
     }
+    state.quote = None;
     handle_next_char(&mut state, contents, ' ', contents.len())?;
 
     Ok(state.tokens)
@@ -56,23 +88,70 @@ fn handle_next_char(
     curr: char,
     idx: usize,
 ) -> Result<(), LexError> {
-    state.location.col_num += 1;
+    if let Some(quote) = state.quote {
+        state.t_end_idx += curr.len_utf8();
+        if state.escaped {
+            state.escaped = false;
+        } else if curr == '\\' {
+            state.escaped = true;
+        } else if curr == quote {
+            state.quote = None;
+        }
+        return Ok(());
+    }
+
     match curr {
-        '\n' => {
-            process_token(state, contents, Some(idx))?;
-            state.location.line_num += 1;
-            state.location.col_num = 0;
+        '"' | '\'' => {
+            state.quote = Some(curr);
+            state.t_end_idx += curr.len_utf8();
         }
-        ';' => {
+        /*
+         * Two colons together are a single token separating an enum from one of its variants
+         * (e.g. Shape::Circle)
+         */
+        ':' if &contents[state.t_start_idx..state.t_end_idx] == ":" => {
+            state.t_end_idx += curr.len_utf8();
+            process_token(state, contents, Some(idx + curr.len_utf8()))?;
+        }
+        ':' if contents[idx + curr.len_utf8()..].starts_with(':') => {
+            process_token(state, contents, None)?;
+            state.t_start_idx = idx;
+            state.t_end_idx = idx + curr.len_utf8();
+        }
+        ';' | '{' | '}' | '[' | ']' | '(' | ')' | ':' | ',' => {
+            process_token(state, contents, None)?;
+            state.t_start_idx = idx;
+            state.t_end_idx = idx + curr.len_utf8();
+            process_token(state, contents, Some(idx + curr.len_utf8()))?;
+        }
+        /*
+         * A dot within a number is part of a float literal, anywhere else it is a field access
+         */
+        '.' if !contents[state.t_start_idx..state.t_end_idx]
+            .starts_with(|x: char| x.is_ascii_digit()) =>
+        {
             process_token(state, contents, None)?;
-            state.location.col_num += 1;
             state.t_start_idx = idx;
-            state.t_end_idx = idx + 1;
-            process_token(state, contents, Some(idx))?;
-            state.location.col_num -= 1;
+            state.t_end_idx = idx + curr.len_utf8();
+            process_token(state, contents, Some(idx + curr.len_utf8()))?;
+        }
+        /*
+         * A * or & directly before a name or another of them is a prefix operator (e.g. *p, &x or
+         * **int), anywhere else a * is part of an operator separated by whitespace
+         */
+        '*' | '&'
+            if state.t_start_idx == state.t_end_idx
+                && contents[idx + curr.len_utf8()..]
+                    .starts_with(|x: char| x.is_ascii_alphanumeric() || "_*&".contains(x)) =>
+        {
+            state.t_start_idx = idx;
+            state.t_end_idx = idx + curr.len_utf8();
+            process_token(state, contents, Some(idx + curr.len_utf8()))?;
+        }
+        _ if curr.is_ascii_whitespace() => {
+            process_token(state, contents, Some(idx + curr.len_utf8()))?
         }
-        _ if curr.is_ascii_whitespace() => process_token(state, contents, Some(idx))?,
-        _ => state.t_end_idx += 1,
+        _ => state.t_end_idx += curr.len_utf8(),
     }
     Ok(())
 }
@@ -80,15 +159,15 @@ fn handle_next_char(
 fn process_token(
     state: &mut LexState,
     in_str: &str,
-    current_idx: Option<usize>,
+    next_start_idx: Option<usize>,
 ) -> Result<(), LexError> {
     if state.t_end_idx != state.t_start_idx {
         let t_str = &in_str[state.t_start_idx..state.t_end_idx];
         let token = get_token(t_str, state)?;
         state.tokens.push(token);
     }
-    if let Some(idx) = current_idx {
-        state.t_start_idx = idx + 1;
+    if let Some(idx) = next_start_idx {
+        state.t_start_idx = idx;
         state.t_end_idx = state.t_start_idx;
     }
     Ok(())
@@ -97,11 +176,36 @@ fn process_token(
 fn get_token(t_str: &str, state: &LexState) -> Result<Token, LexError> {
     let mut is_unknown_token = false;
     let t_type = match t_str {
-        "int" => TokenType::Int,
+        "int" | "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64" | "f32" | "f64"
+        | "bool" | "char" => TokenType::Type,
+        "as" => TokenType::As,
+        "mut" => TokenType::Mut,
+        "const" => TokenType::Const,
+        "if" => TokenType::If,
+        "else" => TokenType::Else,
+        "while" => TokenType::While,
+        "struct" => TokenType::Struct,
+        "enum" => TokenType::Enum,
+        "match" => TokenType::Match,
+        "import" | "mod" => TokenType::Import,
+        "pub" => TokenType::Pub,
+        "true" | "false" | "null" => TokenType::Value,
         "=" => TokenType::Eq,
         ";" => TokenType::Semi,
-        "+" | "-" => TokenType::ArithmeticOp,
-        ">" | "<" => TokenType::BooleanOp,
+        "{" => TokenType::LBrace,
+        "}" => TokenType::RBrace,
+        "[" => TokenType::LBracket,
+        "]" => TokenType::RBracket,
+        "(" => TokenType::LParen,
+        ")" => TokenType::RParen,
+        "." => TokenType::Dot,
+        ":" => TokenType::Colon,
+        "::" => TokenType::DoubleColon,
+        "," => TokenType::Comma,
+        "&" => TokenType::Ampersand,
+        "=>" => TokenType::Arrow,
+        "+" | "-" | "*" | "/" | "+%" | "-%" | "*%" | "+|" | "-|" | "*|" => TokenType::ArithmeticOp,
+        ">" | "<" | ">=" | "<=" | "==" | "!=" => TokenType::BooleanOp,
         x if constants::VARIABLE_REGEX.is_match(x) => TokenType::Variable,
         x if constants::VALUE_REGEX.is_match(x) => TokenType::Value,
         _ => {
@@ -111,12 +215,13 @@ fn get_token(t_str: &str, state: &LexState) -> Result<Token, LexError> {
     };
 
     let token = Token {
-        t_type: t_type,
+        t_type,
         value: String::from(t_str),
-        location: TextLocation {
-            line_num: state.location.line_num,
-            col_num: state.location.col_num - (state.t_end_idx - state.t_start_idx),
-        },
+        location: state.source_map.location(state.offset + state.t_start_idx),
+        span: Span::new(
+            state.offset + state.t_start_idx,
+            state.offset + state.t_end_idx,
+        ),
     };
 
     if is_unknown_token {
@@ -16,7 +16,7 @@ fn test_lex_variable_line() {
 
     let token_cols: Vec<usize> = tokens.iter().map(|x| x.location.col_num).collect();
     let expected_types: Vec<TokenType> = vec![
-        TokenType::Int,
+        TokenType::Type,
         TokenType::Variable,
         TokenType::Eq,
         TokenType::Value,
@@ -57,3 +57,254 @@ fn test_should_error_for_invalid_token() {
         String::from("Unable to parse token: [(Unknown: 1hello), Line: 1, Col: 5]")
     );
 }
+
+#[test]
+fn test_lex_token_spans() {
+    let input = "int x = 10;\nx = 2;";
+    let tokens = parse_text(input).unwrap();
+
+    let spans: Vec<(usize, usize)> = tokens.iter().map(|x| (x.span.start, x.span.end)).collect();
+    assert_eq!(
+        spans,
+        vec![
+            (0, 3),
+            (4, 5),
+            (6, 7),
+            (8, 10),
+            (10, 11),
+            (12, 13),
+            (14, 15),
+            (16, 17),
+            (17, 18)
+        ]
+    );
+}
+
+#[test]
+fn test_lex_multi_byte_characters() {
+    let input = "int xéy = 10;";
+    let tokens = parse_text(input).unwrap();
+
+    let token_cols: Vec<usize> = tokens.iter().map(|x| x.location.col_num).collect();
+    assert_eq!(tokens[1].value, "xéy");
+    assert_eq!((tokens[1].span.start, tokens[1].span.end), (4, 8));
+    assert_eq!(token_cols, vec![1, 5, 9, 11, 13]);
+    assert_eq!(&input[tokens[3].span.start..tokens[3].span.end], "10");
+}
+
+#[test]
+fn test_should_report_char_column_after_multi_byte_characters() {
+    let input = "int é = 10;";
+    let result = parse_text(input).unwrap_err();
+
+    assert_eq!(
+        result.to_string(),
+        String::from("Unable to parse token: [(Unknown: é), Line: 1, Col: 5]")
+    );
+}
+
+#[test]
+fn test_lex_braces_without_whitespace() {
+    let input = "{int x = 10;}";
+    let tokens = parse_text(input).unwrap();
+
+    let expected_types: Vec<TokenType> = vec![
+        TokenType::LBrace,
+        TokenType::Type,
+        TokenType::Variable,
+        TokenType::Eq,
+        TokenType::Value,
+        TokenType::Semi,
+        TokenType::RBrace,
+    ];
+    let token_types: Vec<TokenType> = tokens.iter().map(|x| x.t_type).collect();
+    assert_eq!(token_types, expected_types);
+}
+
+#[test]
+fn test_lex_brackets_without_whitespace() {
+    let input = "int[10] xs;\nxs[i] = 1;";
+    let tokens = parse_text(input).unwrap();
+
+    let expected_types: Vec<TokenType> = vec![
+        TokenType::Type,
+        TokenType::LBracket,
+        TokenType::Value,
+        TokenType::RBracket,
+        TokenType::Variable,
+        TokenType::Semi,
+        TokenType::Variable,
+        TokenType::LBracket,
+        TokenType::Variable,
+        TokenType::RBracket,
+        TokenType::Eq,
+        TokenType::Value,
+        TokenType::Semi,
+    ];
+    let token_types: Vec<TokenType> = tokens.iter().map(|x| x.t_type).collect();
+    assert_eq!(token_types, expected_types);
+    assert_eq!(tokens[8].location.col_num, 4);
+}
+
+#[test]
+fn test_lex_types_and_literals() {
+    let input = "f64 x = 1.5 as f64;\nchar c = ' ';\nbool b = x >= 2;";
+    let tokens = parse_text(input).unwrap();
+
+    let expected_types: Vec<TokenType> = vec![
+        TokenType::Type,
+        TokenType::Variable,
+        TokenType::Eq,
+        TokenType::Value,
+        TokenType::As,
+        TokenType::Type,
+        TokenType::Semi,
+        TokenType::Type,
+        TokenType::Variable,
+        TokenType::Eq,
+        TokenType::Value,
+        TokenType::Semi,
+        TokenType::Type,
+        TokenType::Variable,
+        TokenType::Eq,
+        TokenType::Variable,
+        TokenType::BooleanOp,
+        TokenType::Value,
+        TokenType::Semi,
+    ];
+    let token_types: Vec<TokenType> = tokens.iter().map(|x| x.t_type).collect();
+    assert_eq!(token_types, expected_types);
+    assert_eq!(tokens[10].value, "' '");
+}
+
+#[test]
+fn test_should_error_for_unterminated_quote() {
+    let input = "char c = 'a;";
+    let result = parse_text(input).unwrap_err();
+
+    assert_eq!(
+        result.to_string(),
+        String::from("Unable to parse token: [(Unknown: 'a;), Line: 1, Col: 10]")
+    );
+}
+
+#[test]
+fn test_lex_struct_definitions_and_field_access() {
+    let input = "struct Point {int x;}\np.x = Point {x: 1.5, y: 2};";
+    let tokens = parse_text(input).unwrap();
+
+    let expected_types: Vec<TokenType> = vec![
+        TokenType::Struct,
+        TokenType::Variable,
+        TokenType::LBrace,
+        TokenType::Type,
+        TokenType::Variable,
+        TokenType::Semi,
+        TokenType::RBrace,
+        TokenType::Variable,
+        TokenType::Dot,
+        TokenType::Variable,
+        TokenType::Eq,
+        TokenType::Variable,
+        TokenType::LBrace,
+        TokenType::Variable,
+        TokenType::Colon,
+        TokenType::Value,
+        TokenType::Comma,
+        TokenType::Variable,
+        TokenType::Colon,
+        TokenType::Value,
+        TokenType::RBrace,
+        TokenType::Semi,
+    ];
+    let token_types: Vec<TokenType> = tokens.iter().map(|x| x.t_type).collect();
+    assert_eq!(token_types, expected_types);
+    assert_eq!(tokens[15].value, "1.5");
+    assert_eq!(tokens[9].location.col_num, 3);
+}
+
+#[test]
+fn test_lex_pointer_types_address_of_and_dereference() {
+    let input = "**int pp = &p;\n*pp = p * *q;\nbool b = p != null;";
+    let tokens = parse_text(input).unwrap();
+
+    let expected_types: Vec<TokenType> = vec![
+        TokenType::ArithmeticOp,
+        TokenType::ArithmeticOp,
+        TokenType::Type,
+        TokenType::Variable,
+        TokenType::Eq,
+        TokenType::Ampersand,
+        TokenType::Variable,
+        TokenType::Semi,
+        TokenType::ArithmeticOp,
+        TokenType::Variable,
+        TokenType::Eq,
+        TokenType::Variable,
+        TokenType::ArithmeticOp,
+        TokenType::ArithmeticOp,
+        TokenType::Variable,
+        TokenType::Semi,
+        TokenType::Type,
+        TokenType::Variable,
+        TokenType::Eq,
+        TokenType::Variable,
+        TokenType::BooleanOp,
+        TokenType::Value,
+        TokenType::Semi,
+    ];
+    let token_types: Vec<TokenType> = tokens.iter().map(|x| x.t_type).collect();
+    assert_eq!(token_types, expected_types);
+    assert_eq!(tokens[13].value, "*");
+    assert_eq!(tokens[14].location.col_num, 12);
+}
+
+#[test]
+fn test_lex_enums_and_match() {
+    let input = "enum E {A(int, u8);B;}\nint x = match e {E::A(a, _) => a, E::B => 0};";
+    let tokens = parse_text(input).unwrap();
+
+    let expected_types: Vec<TokenType> = vec![
+        TokenType::Enum,
+        TokenType::Variable,
+        TokenType::LBrace,
+        TokenType::Variable,
+        TokenType::LParen,
+        TokenType::Type,
+        TokenType::Comma,
+        TokenType::Type,
+        TokenType::RParen,
+        TokenType::Semi,
+        TokenType::Variable,
+        TokenType::Semi,
+        TokenType::RBrace,
+        TokenType::Type,
+        TokenType::Variable,
+        TokenType::Eq,
+        TokenType::Match,
+        TokenType::Variable,
+        TokenType::LBrace,
+        TokenType::Variable,
+        TokenType::DoubleColon,
+        TokenType::Variable,
+        TokenType::LParen,
+        TokenType::Variable,
+        TokenType::Comma,
+        TokenType::Variable,
+        TokenType::RParen,
+        TokenType::Arrow,
+        TokenType::Variable,
+        TokenType::Comma,
+        TokenType::Variable,
+        TokenType::DoubleColon,
+        TokenType::Variable,
+        TokenType::Arrow,
+        TokenType::Value,
+        TokenType::RBrace,
+        TokenType::Semi,
+    ];
+    let token_types: Vec<TokenType> = tokens.iter().map(|x| x.t_type).collect();
+    assert_eq!(token_types, expected_types);
+    assert_eq!(tokens[20].value, "::");
+    assert_eq!(tokens[21].location.col_num, 21);
+}
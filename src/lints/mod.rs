@@ -0,0 +1,341 @@
+/*
+* Lints look for code that is valid but is probably a mistake, such as a variable that is never
+* read. Every lint warns by default, but can be allowed so that it is not reported at all, or denied
+* so that any warning it reports stops the compilation
+*
+* Whether an assigned value is ever read is decided by the same liveness walk as dead store
+* elimination. Unlike the optimiser no variable is live when the program exits, a value that can
+* only be seen with a debugger is still never read. A variable which has its address taken counts as
+* assigned and stores to it are never reported
+*/
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    liveness::{self, Mode},
+    parser::{Expression, ExpressionKind, Program, Statement},
+    source::{SourceMap, Span},
+    token::TextLocation,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
+pub enum Lint {
+    UnusedVariable,
+    UnusedAssignment,
+    UnusedMut,
+}
+
+#[derive(Debug)]
+pub struct LintParseError;
+
+impl Lint {
+    pub const ALL: [Lint; 3] = [
+        Lint::UnusedVariable,
+        Lint::UnusedAssignment,
+        Lint::UnusedMut,
+    ];
+
+    pub fn get_values_string() -> String {
+        Lint::ALL
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+}
+
+impl std::fmt::Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Lint::UnusedVariable => write!(f, "unused-variable"),
+            Lint::UnusedAssignment => write!(f, "unused-assignment"),
+            Lint::UnusedMut => write!(f, "unused-mut"),
+        }
+    }
+}
+
+impl std::str::FromStr for Lint {
+    type Err = LintParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Lint::ALL
+            .into_iter()
+            .find(|x| x.to_string() == s)
+            .ok_or(LintParseError)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+#[derive(Debug, Default)]
+pub struct LintOptions {
+    levels: Vec<(Lint, LintLevel)>,
+}
+
+impl LintOptions {
+    pub fn set_level(&mut self, lint: Lint, level: LintLevel) {
+        self.levels.retain(|(x, _)| *x != lint);
+        self.levels.push((lint, level));
+    }
+
+    pub fn level(&self, lint: Lint) -> LintLevel {
+        self.levels
+            .iter()
+            .find(|(x, _)| *x == lint)
+            .map_or(LintLevel::Warn, |(_, level)| *level)
+    }
+}
+
+#[derive(Debug)]
+pub struct Warning {
+    pub lint: Lint,
+    pub level: LintLevel,
+    pub v_name: String,
+    pub location: TextLocation,
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.level {
+            LintLevel::Deny => write!(f, "Error: ")?,
+            LintLevel::Allow | LintLevel::Warn => write!(f, "Warning: ")?,
+        }
+        match self.lint {
+            Lint::UnusedVariable => write!(f, "Unused variable: {}", self.v_name)?,
+            Lint::UnusedAssignment => write!(f, "Value assigned to {} is never read", self.v_name)?,
+            Lint::UnusedMut => write!(f, "Variable does not need to be mutable: {}", self.v_name)?,
+        }
+        write!(f, ", {} [{}]", self.location, self.lint)
+    }
+}
+
+#[derive(Debug)]
+pub enum LintError {
+    DeniedWarnings(usize),
+}
+
+impl std::error::Error for LintError {}
+
+impl std::fmt::Display for LintError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LintError::DeniedWarnings(1) => write!(f, "Compilation stopped by 1 denied warning"),
+            LintError::DeniedWarnings(x) => {
+                write!(f, "Compilation stopped by {} denied warnings", x)
+            }
+        }
+    }
+}
+
+/*
+* Runs every lint that isn't allowed, returning the warnings in the order they appear in the source.
+* Denied warnings are returned along with the rest so that each can be reported on its own
+*/
+pub fn check(program: &Program, options: &LintOptions, source_map: &SourceMap) -> Vec<Warning> {
+    let mut usage = Usage {
+        declarations: Vec::new(),
+        scopes: vec![HashMap::new()],
+        stores: HashMap::new(),
+    };
+    usage.process_statements(&program.statements);
+
+    let dead_stores = liveness::dead_stores(program, HashSet::new(), Mode::Report);
+
+    let mut found: Vec<(Lint, Span, &str)> = Vec::new();
+    for declaration in &usage.declarations {
+        if !declaration.read && !declaration.v_name.starts_with('_') {
+            found.push((Lint::UnusedVariable, declaration.span, &declaration.v_name));
+        }
+        if declaration.mutable && !declaration.assigned {
+            found.push((Lint::UnusedMut, declaration.span, &declaration.v_name));
+        }
+    }
+    for span in &dead_stores {
+        let declaration = &usage.declarations[usage.stores[span]];
+        if declaration.read && !declaration.v_name.starts_with('_') {
+            found.push((Lint::UnusedAssignment, *span, &declaration.v_name));
+        }
+    }
+    found.sort_by_key(|(lint, span, _)| (span.start, Lint::ALL.iter().position(|x| x == lint)));
+
+    found
+        .into_iter()
+        .filter(|(lint, _, _)| options.level(*lint) != LintLevel::Allow)
+        .map(|(lint, span, v_name)| Warning {
+            lint,
+            level: options.level(lint),
+            v_name: v_name.to_string(),
+            location: source_map.location(span.start),
+        })
+        .collect()
+}
+
+/*
+* Stops the compilation once the warnings have been reported if any of them were denied
+*/
+pub fn check_denied(warnings: &[Warning]) -> Result<(), LintError> {
+    match warnings
+        .iter()
+        .filter(|x| x.level == LintLevel::Deny)
+        .count()
+    {
+        0 => Ok(()),
+        x => Err(LintError::DeniedWarnings(x)),
+    }
+}
+
+struct Declaration {
+    v_name: String,
+    span: Span,
+    mutable: bool,
+    read: bool,
+    assigned: bool,
+}
+
+/*
+* Resolves every use of a variable to its declaration, recording whether each one is ever read or
+* assigned to. Stores are keyed by the span of the declared name or of the assignment statement, the
+* same spans the liveness walk reports
+*/
+struct Usage {
+    declarations: Vec<Declaration>,
+    scopes: Vec<HashMap<String, usize>>,
+    stores: HashMap<Span, usize>,
+}
+
+impl Usage {
+    fn process_statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.process_statement(statement);
+        }
+    }
+
+    fn process_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::DeclarationStatement {
+                v_name,
+                v_span,
+                mutable,
+                expr,
+                ..
+            } => {
+                if let Some(x) = expr {
+                    self.read_expression(x);
+                }
+                self.declare(v_name, *v_span, *mutable);
+            }
+            Statement::ConstStatement {
+                v_name,
+                v_span,
+                expr,
+                ..
+            } => {
+                self.read_expression(expr);
+                self.declare(v_name, *v_span, false);
+            }
+            Statement::AssignmentStatement { target, expr, span } => {
+                self.read_expression(expr);
+                let mut used = HashSet::new();
+                target.collect_index_variables(&mut used);
+                self.read_variables(used);
+                if let Some(id) = target.root_variable().and_then(|x| self.lookup(x)) {
+                    self.declarations[id].assigned = true;
+                    if let ExpressionKind::VarExpr(_) = target.kind {
+                        self.stores.insert(*span, id);
+                    }
+                }
+            }
+            Statement::BlockStatement { statements, .. } => {
+                self.scopes.push(HashMap::new());
+                self.process_statements(statements);
+                self.scopes.pop();
+            }
+            Statement::IfStatement {
+                condition,
+                then_block,
+                else_block,
+                ..
+            } => {
+                self.read_expression(condition);
+                self.process_statement(then_block);
+                if let Some(x) = else_block {
+                    self.process_statement(x);
+                }
+            }
+            Statement::WhileStatement {
+                condition, body, ..
+            } => {
+                self.read_expression(condition);
+                self.process_statement(body);
+            }
+            Statement::MatchStatement {
+                scrutinee, arms, ..
+            } => {
+                self.read_expression(scrutinee);
+                for arm in arms {
+                    self.scopes.push(HashMap::new());
+                    for (_, v_name, v_span) in arm.pattern.bindings() {
+                        self.declare(v_name, v_span, false);
+                    }
+                    self.process_statement(&arm.body);
+                    self.scopes.pop();
+                }
+            }
+            Statement::StructStatement { .. } | Statement::EnumStatement { .. } => {}
+        }
+    }
+
+    fn declare(&mut self, v_name: &str, span: Span, mutable: bool) {
+        let id = self.declarations.len();
+        self.declarations.push(Declaration {
+            v_name: v_name.to_string(),
+            span,
+            mutable,
+            read: false,
+            assigned: false,
+        });
+        self.stores.insert(span, id);
+        self.scopes
+            .last_mut()
+            .expect("Usage should always contain the global scope")
+            .insert(v_name.to_string(), id);
+    }
+
+    fn lookup(&self, v_name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(v_name).copied())
+    }
+
+    fn read_expression(&mut self, expr: &Expression) {
+        let mut used = HashSet::new();
+        expr.collect_variables(&mut used);
+        self.read_variables(used);
+
+        let mut address_taken = HashSet::new();
+        expr.collect_address_taken(&mut address_taken);
+        for v_name in address_taken {
+            if let Some(id) = self.lookup(&v_name) {
+                self.declarations[id].assigned = true;
+            }
+        }
+    }
+
+    fn read_variables(&mut self, used: HashSet<String>) {
+        for v_name in used {
+            if let Some(id) = self.lookup(&v_name) {
+                self.declarations[id].read = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::str::FromStr;
+
+use super::*;
+use crate::test_utils;
+
+fn check_text(contents: &str, options: &LintOptions) -> Vec<Warning> {
+    let (program, source_map) = test_utils::analyse_text(contents);
+    check(&program, options, &source_map)
+}
+
+fn warnings(contents: &str) -> Vec<String> {
+    check_text(contents, &LintOptions::default())
+        .iter()
+        .map(|x| x.to_string())
+        .collect()
+}
+
+#[test]
+fn test_parse_lint_names() {
+    assert_eq!(
+        Lint::from_str("unused-variable").unwrap(),
+        Lint::UnusedVariable
+    );
+    assert_eq!(
+        Lint::from_str("unused-assignment").unwrap(),
+        Lint::UnusedAssignment
+    );
+    assert_eq!(Lint::from_str("unused-mut").unwrap(), Lint::UnusedMut);
+    assert!(Lint::from_str("unused").is_err());
+    assert_eq!(
+        Lint::get_values_string(),
+        "unused-variable,unused-assignment,unused-mut"
+    );
+}
+
+#[test]
+fn test_lint_levels() {
+    let mut options = LintOptions::default();
+    assert_eq!(options.level(Lint::UnusedMut), LintLevel::Warn);
+    options.set_level(Lint::UnusedMut, LintLevel::Deny);
+    options.set_level(Lint::UnusedMut, LintLevel::Allow);
+    assert_eq!(options.level(Lint::UnusedMut), LintLevel::Allow);
+    assert_eq!(options.level(Lint::UnusedVariable), LintLevel::Warn);
+}
+
+#[test]
+fn test_unused_variable() {
+    assert_eq!(
+        warnings("int x = 1;\nint y = 2;\nint z = y;\nconst int C = 3;\nint _ignored = 4;"),
+        vec![
+            "Warning: Unused variable: x, Line: 1, Col: 5 [unused-variable]",
+            "Warning: Unused variable: z, Line: 3, Col: 5 [unused-variable]",
+            "Warning: Unused variable: C, Line: 4, Col: 11 [unused-variable]",
+        ]
+    );
+}
+
+#[test]
+fn test_unused_variable_with_shadowing() {
+    assert_eq!(
+        warnings("int x = 1;\n{\n  int x = 2;\n  int y = x;\n  int z = y;\n}"),
+        vec![
+            "Warning: Unused variable: x, Line: 1, Col: 5 [unused-variable]",
+            "Warning: Unused variable: z, Line: 5, Col: 7 [unused-variable]",
+        ]
+    );
+    assert!(warnings("int x = 1;\n{\n  int y = x;\n  int _z = y + 1;\n}").is_empty());
+}
+
+#[test]
+fn test_unused_assignment() {
+    assert_eq!(
+        warnings("mut int x = 10;\nx = 20;\nx = 30;\nint y = x;\nx = 40;\nint _z = y;"),
+        vec![
+            "Warning: Value assigned to x is never read, Line: 1, Col: 9 [unused-assignment]",
+            "Warning: Value assigned to x is never read, Line: 2, Col: 1 [unused-assignment]",
+            "Warning: Value assigned to x is never read, Line: 5, Col: 1 [unused-assignment]",
+        ]
+    );
+}
+
+#[test]
+fn test_unused_assignment_in_control_flow() {
+    assert!(warnings(
+        "mut int x = 0;\nmut int i = 0;\nwhile i < 10 {\n  x = x + i;\n  i = i + 1;\n}\nint _y = x;"
+    )
+    .is_empty());
+    assert!(warnings("mut int x = 0;\nif true {\n  x = 1;\n}\nint _y = x;").is_empty());
+    assert_eq!(
+        warnings("mut int x = 0;\nif true {\n  x = 1;\n} else {\n  x = 2;\n}\nint _y = x;"),
+        vec!["Warning: Value assigned to x is never read, Line: 1, Col: 9 [unused-assignment]"]
+    );
+    assert_eq!(
+        warnings("mut int x = 0;\nmut int i = 0;\nwhile i < 3 {\n  x = i;\n  i = i + 1;\n}"),
+        vec!["Warning: Unused variable: x, Line: 1, Col: 9 [unused-variable]"]
+    );
+}
+
+#[test]
+fn test_unused_mut() {
+    assert_eq!(
+        warnings("mut int x = 1;\nmut int y = 2;\ny = y + x;\nint _z = y;"),
+        vec!["Warning: Variable does not need to be mutable: x, Line: 1, Col: 9 [unused-mut]"]
+    );
+}
+
+#[test]
+fn test_allowed_and_denied_lints() {
+    let contents = "mut int x = 1;";
+    assert_eq!(
+        warnings(contents),
+        vec![
+            "Warning: Unused variable: x, Line: 1, Col: 9 [unused-variable]",
+            "Warning: Variable does not need to be mutable: x, Line: 1, Col: 9 [unused-mut]",
+        ]
+    );
+
+    let mut options = LintOptions::default();
+    options.set_level(Lint::UnusedVariable, LintLevel::Allow);
+    let warnings = check_text(contents, &options);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(
+        warnings[0].to_string(),
+        "Warning: Variable does not need to be mutable: x, Line: 1, Col: 9 [unused-mut]"
+    );
+    assert!(check_denied(&warnings).is_ok());
+
+    options.set_level(Lint::UnusedMut, LintLevel::Deny);
+    let warnings = check_text(contents, &options);
+    assert_eq!(
+        warnings[0].to_string(),
+        "Error: Variable does not need to be mutable: x, Line: 1, Col: 9 [unused-mut]"
+    );
+    assert_eq!(
+        check_denied(&warnings).unwrap_err().to_string(),
+        "Compilation stopped by 1 denied warning"
+    );
+
+    options.set_level(Lint::UnusedVariable, LintLevel::Deny);
+    let warnings = check_text(contents, &options);
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(
+        check_denied(&warnings).unwrap_err().to_string(),
+        "Compilation stopped by 2 denied warnings"
+    );
+}
+
+#[test]
+fn test_declarations_without_value() {
+    assert!(warnings("int x;\nif true {\n  x = 1;\n} else {\n  x = 2;\n}\nint _y = x;").is_empty());
+    assert_eq!(
+        warnings("mut int x;\nx = 1;\nx = 2;\nint _y = x;"),
+        vec!["Warning: Value assigned to x is never read, Line: 2, Col: 1 [unused-assignment]"]
+    );
+}
+
+#[test]
+fn test_array_element_assignments() {
+    assert!(warnings("mut i64[3] a;\na[0] = 1;\na[1] = 2;\ni64 _x = a[0];").is_empty());
+    assert_eq!(
+        warnings("mut i64[3] a;\nint i = 0;\na[i] = 1;"),
+        vec!["Warning: Unused variable: a, Line: 1, Col: 12 [unused-variable]"]
+    );
+}
+
+#[test]
+fn test_variables_with_their_address_taken() {
+    assert!(warnings("mut int x = 1;\n*int p = &x;\n*p = 2;\nx = 3;\nint _y = *p;").is_empty());
+    assert_eq!(
+        warnings("mut int x = 1;\nmut *int p = &x;\nint _y = *p;\np = null;"),
+        vec!["Warning: Value assigned to p is never read, Line: 4, Col: 1 [unused-assignment]"]
+    );
+}
+
+#[test]
+fn test_match_bindings() {
+    assert!(warnings("enum Opt { Some(int); None; }\nOpt o = Opt::None;\nint _y = match o { Opt::Some(x) => x, _ => 0 };").is_empty());
+    assert_eq!(
+        warnings(
+            "enum Opt { Some(int); None; }\nOpt o = Opt::None;\nmatch o {\n  Opt::Some(x) => {}\n  _ => {}\n}"
+        ),
+        vec!["Warning: Unused variable: x, Line: 4, Col: 13 [unused-variable]"]
+    );
+}
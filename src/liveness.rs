@@ -0,0 +1,225 @@
+/*
+* Finds the stores whose value is never read by walking the program backwards while tracking the
+* live variables, those whose current value may still be read. A store is recorded by the span of
+* the declared name for a declaration and of the whole statement for an assignment. Dead store
+* elimination and the unused assignment lint both use this walk over the syntax tree, whereas SSA
+* construction computes liveness over the control flow graph
+*
+* A block can declare a variable which shadows one from an enclosing scope, so the outer variable's
+* liveness is put aside while walking the block and restored before its start, and the same goes
+* for the variables bound by the arms of a match. Loops are walked repeatedly until the variables
+* live at the start of the loop stop changing, and only then are stores in the loop recorded
+*
+* When reporting, anything a dead store reads still counts as read, and only stores to the whole of
+* a variable are recorded. When eliminating, the reads of a dead store go along with it, so a chain
+* of dead stores is found in one walk, and stores to the elements of a dead array are recorded too.
+* A declaration is then only recorded if nothing later assigns to the variable, since it is what
+* introduces it
+*/
+
+use std::collections::HashSet;
+
+use crate::{
+    parser::{Expression, ExpressionKind, Program, Statement},
+    source::Span,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Report,
+    Eliminate,
+}
+
+struct Liveness {
+    mode: Mode,
+    live: HashSet<String>,
+    assigned: HashSet<String>,
+    address_taken: HashSet<String>,
+    dead_stores: HashSet<Span>,
+}
+
+/*
+* The variables in live_at_exit are read once the program ends
+*/
+pub fn dead_stores(program: &Program, live_at_exit: HashSet<String>, mode: Mode) -> HashSet<Span> {
+    let mut liveness = Liveness {
+        mode,
+        live: live_at_exit,
+        assigned: HashSet::new(),
+        address_taken: program.address_taken_variables(),
+        dead_stores: HashSet::new(),
+    };
+    liveness.process_statements(&program.statements, true);
+    liveness.dead_stores
+}
+
+pub fn declared_variables(statements: &[Statement]) -> HashSet<String> {
+    statements
+        .iter()
+        .filter_map(|x| match x {
+            Statement::DeclarationStatement { v_name, .. }
+            | Statement::ConstStatement { v_name, .. } => Some(v_name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+impl Liveness {
+    fn process_statements(&mut self, statements: &[Statement], record: bool) {
+        for statement in statements.iter().rev() {
+            self.process_statement(statement, record);
+        }
+    }
+
+    /*
+     * Updates the live variables to those live before the statement
+     */
+    fn process_statement(&mut self, statement: &Statement, record: bool) {
+        match statement {
+            Statement::DeclarationStatement {
+                v_name,
+                v_span,
+                expr,
+                ..
+            } => {
+                let is_store = expr.is_some() || self.mode == Mode::Eliminate;
+                let is_assigned_later =
+                    self.mode == Mode::Eliminate && self.assigned.contains(v_name);
+                let is_dead = is_store
+                    && !is_assigned_later
+                    && !self.live.contains(v_name)
+                    && !self.address_taken.contains(v_name);
+                self.live.remove(v_name);
+                if is_dead {
+                    self.record(*v_span, record);
+                    if self.mode == Mode::Eliminate {
+                        return;
+                    }
+                }
+                if let Some(x) = expr {
+                    x.collect_variables(&mut self.live);
+                }
+            }
+            Statement::ConstStatement { v_name, .. } => {
+                self.live.remove(v_name);
+            }
+            Statement::AssignmentStatement { target, expr, span } => {
+                self.assign(target, expr, *span, record)
+            }
+            Statement::BlockStatement { statements, .. } => {
+                let outer_live: Vec<String> = declared_variables(statements)
+                    .into_iter()
+                    .filter(|x| self.live.remove(x))
+                    .collect();
+                self.process_statements(statements, record);
+                self.live.extend(outer_live);
+            }
+            Statement::IfStatement {
+                condition,
+                then_block,
+                else_block,
+                ..
+            } => {
+                let live_after = self.live.clone();
+                self.process_statement(then_block, record);
+                let live_then = std::mem::replace(&mut self.live, live_after);
+                if let Some(x) = else_block {
+                    self.process_statement(x, record);
+                }
+                self.live.extend(live_then);
+                condition.collect_variables(&mut self.live);
+            }
+            Statement::WhileStatement {
+                condition, body, ..
+            } => {
+                let mut live_after = self.live.clone();
+                condition.collect_variables(&mut live_after);
+
+                let mut live_start = live_after.clone();
+                loop {
+                    self.live = live_start.clone();
+                    self.process_statement(body, false);
+                    self.live.extend(live_after.iter().cloned());
+                    if self.live == live_start {
+                        break;
+                    }
+                    live_start = self.live.clone();
+                }
+
+                if record {
+                    self.live = live_start.clone();
+                    self.process_statement(body, true);
+                }
+                self.live = live_start;
+            }
+            /*
+             * The variables bound by an arm are stored when it is entered, and shadow any outer
+             * variables with the same name while walking it
+             */
+            Statement::MatchStatement {
+                scrutinee, arms, ..
+            } => {
+                let live_after = self.live.clone();
+                let mut live_before = HashSet::new();
+                for arm in arms {
+                    self.live = live_after.clone();
+                    let bindings = arm.pattern.bindings();
+                    for (_, v_name, _) in &bindings {
+                        self.live.remove(*v_name);
+                    }
+                    self.process_statement(&arm.body, record);
+                    for (_, v_name, v_span) in bindings {
+                        let is_dead =
+                            !self.live.remove(v_name) && !self.address_taken.contains(v_name);
+                        if is_dead && self.mode == Mode::Report {
+                            self.record(v_span, record);
+                        }
+                        if live_after.contains(v_name) {
+                            self.live.insert(v_name.to_string());
+                        }
+                    }
+                    live_before.extend(self.live.drain());
+                }
+                self.live = live_before;
+                scrutinee.collect_variables(&mut self.live);
+            }
+            Statement::StructStatement { .. } | Statement::EnumStatement { .. } => {}
+        }
+    }
+
+    /*
+     * Writing to an element leaves the rest of the array as it was, so it doesn't end the lifetime
+     * of the value stored before it. Writing through a pointer is never a dead store
+     */
+    fn assign(&mut self, target: &Expression, expr: &Expression, span: Span, record: bool) {
+        let is_whole = matches!(target.kind, ExpressionKind::VarExpr(_));
+        let v_name = match target.root_variable() {
+            Some(x) if is_whole || self.mode == Mode::Eliminate => x,
+            _ => {
+                target.collect_index_variables(&mut self.live);
+                expr.collect_variables(&mut self.live);
+                return;
+            }
+        };
+
+        let address_taken = self.address_taken.contains(v_name);
+        if !address_taken && !self.live.contains(v_name) {
+            self.record(span, record);
+            if self.mode == Mode::Eliminate {
+                return;
+            }
+        }
+        if is_whole && !address_taken {
+            self.live.remove(v_name);
+        }
+        self.assigned.insert(v_name.to_string());
+        target.collect_index_variables(&mut self.live);
+        expr.collect_variables(&mut self.live);
+    }
+
+    fn record(&mut self, span: Span, record: bool) {
+        if record {
+            self.dead_stores.insert(span);
+        }
+    }
+}
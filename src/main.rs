@@ -1,29 +1,129 @@
+mod cfg;
+mod cli;
+mod config;
 mod constants;
+mod folding;
 mod generator;
 mod io_handler;
+mod layout;
 mod lexer;
+mod lints;
+mod liveness;
+mod manifest;
+mod modules;
+mod optimiser;
 mod parser;
+mod semantic;
+mod source;
 mod target;
+#[cfg(test)]
+mod test_utils;
 mod token;
+mod toolchain;
+
+use std::path::Path;
+
+use cli::Command;
+use io_handler::Emit;
+use source::SourceError;
 
 fn main() {
-    if let Err(e) = run() {
-        println!("{}", e);
-        std::process::exit(1)
+    match run() {
+        Ok(code) => std::process::exit(code),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1)
+        }
     }
 }
 
-fn run() -> Result<(), Box<dyn std::error::Error>> {
-    let cmd_args = match io_handler::process_cmd_args()? {
+/*
+* Returns the exit code for the compiler, which is the program's own exit code when it is run
+*/
+fn run() -> Result<i32, Box<dyn std::error::Error>> {
+    let cmd_args = match cli::process_cmd_args()? {
         Some(args) => args,
-        None => return Ok(()),
+        None => return Ok(0),
     };
-    let contents = io_handler::read_file(&cmd_args.file_name)?;
-
-    let tokens = lexer::parse_text(&contents)?;
-    let program = parser::parse_program(tokens)?;
-    let out_assembly = generator::generate(&cmd_args.target, program)?;
-    let out_file = &cmd_args.get_file_name()?;
-    io_handler::write_program(&out_file, &out_assembly)?;
-    Ok(())
+    if cmd_args.command == Command::New {
+        let dir = &cmd_args.sources[0];
+        let project = manifest::create(dir)?;
+        eprintln!("Created project {} in {}", project, dir.display());
+        return Ok(0);
+    }
+
+    let mut code = 0;
+    for file in &cmd_args.sources {
+        code = compile(&cmd_args, file)?;
+    }
+    Ok(code)
+}
+
+fn compile(cmd_args: &cli::CmdArgs, file: &Path) -> Result<i32, Box<dyn std::error::Error>> {
+    let contents = io_handler::read_file(file)?;
+    let name = cmd_args.source_name(file);
+
+    let mut source_map = source::SourceMap::new(&contents);
+
+    let output = cmd_args.output(file)?;
+
+    let tokens = lexer::parse_text(&contents).map_err(|e| SourceError::located(&name, e))?;
+    if cmd_args.emits(Emit::Tokens) {
+        let lines: Vec<String> = tokens.iter().map(|x| x.to_string() + "\n").collect();
+        io_handler::write_artifact(&output, Emit::Tokens, &lines.concat())?;
+    }
+    if !cmd_args.runs_after(Emit::Tokens) {
+        return Ok(0);
+    }
+
+    let mut program =
+        modules::link(file, tokens, &mut source_map).map_err(|e| SourceError::located(&name, e))?;
+    if cmd_args.emits(Emit::Ast) {
+        io_handler::write_artifact(&output, Emit::Ast, &program.to_string())?;
+    }
+    if !cmd_args.runs_after(Emit::Ast) {
+        return Ok(0);
+    }
+
+    semantic::analyse(&mut program, &source_map).map_err(|e| SourceError::located(&name, e))?;
+    let mut warnings = lints::check(&program, &cmd_args.lints, &source_map);
+    for warning in &mut warnings {
+        let file = warning.location.file.take();
+        eprintln!("{}: {}", file.as_deref().unwrap_or(&name), warning);
+    }
+    lints::check_denied(&warnings).map_err(|e| SourceError::new(&name, e))?;
+    folding::fold_program(&mut program, &source_map).map_err(|e| SourceError::located(&name, e))?;
+    if cmd_args.command == Command::Check {
+        return Ok(0);
+    }
+    optimiser::optimise(&mut program, &cmd_args.optimiser, &source_map);
+    if cmd_args.dump_cfg {
+        let mut cfg = cfg::build(&program);
+        cfg::construct_ssa(&mut cfg);
+        io_handler::write_graph(&output, "ssa", &cfg.to_dot())?;
+        cfg::destruct_ssa(&mut cfg);
+        io_handler::write_graph(&output, "cfg", &cfg.to_dot())?;
+    }
+    if cmd_args.emits(Emit::Ir) {
+        let mut cfg = cfg::build(&program);
+        cfg::construct_ssa(&mut cfg);
+        io_handler::write_artifact(&output, Emit::Ir, &cfg.to_string())?;
+    }
+    if !cmd_args.runs_after(Emit::Ir) {
+        return Ok(0);
+    }
+
+    let out_assembly =
+        generator::generate(&cmd_args.target, program, &cmd_args.codegen, &source_map)
+            .map_err(|e| SourceError::located(&name, e))?;
+    let toolchain = toolchain::Toolchain::new(&cmd_args.target, &cmd_args.toolchain)?;
+    io_handler::write_program(&output, &out_assembly, &toolchain)?;
+    match cmd_args.command {
+        Command::Run => Ok(io_handler::run_executable(
+            &output,
+            &toolchain,
+            &cmd_args.program_args,
+        )?),
+        _ => Ok(0),
+    }
 }
@@ -0,0 +1,262 @@
+/*
+* A project is a directory with an mgs.toml manifest, found by searching from the current directory
+* upward. The [package] table names the project and lists its entries, the source files which are
+* each built into a program of their own, and the [build] table gives the defaults for the target,
+* optimisation level and output directory which the command line can override:
+*
+*   [package]
+*   name = "demo"
+*   entries = ["src/main.mgs", "src/tool.mgs"]
+*
+*   [build]
+*   target = "arm64"
+*   opt-level = 2
+*   out-dir = "build"
+*
+* Paths in the manifest are relative to the directory it is in
+*/
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    config::{self, ConfigError, Value},
+    optimiser::OptLevel,
+    target::Target,
+};
+
+pub const MANIFEST_FILE: &str = "mgs.toml";
+const DEFAULT_OUT_DIR: &str = "build";
+const MAIN_ENTRY: &str = "src/main.mgs";
+const STARTER_CODE: &str = "int x = 10;\nint _y = x + 9 - 5;\n";
+
+#[derive(Debug)]
+pub enum ManifestError {
+    ReadFailure(PathBuf, io::Error),
+    InvalidConfig(PathBuf, ConfigError),
+    MissingKey {
+        path: PathBuf,
+        table: &'static str,
+        key: &'static str,
+    },
+    InvalidValue {
+        path: PathBuf,
+        key: &'static str,
+        value: Value,
+        expected: String,
+    },
+    InvalidProjectName(PathBuf),
+    ProjectExists(PathBuf),
+    CreateFailure(PathBuf, io::Error),
+}
+
+impl std::error::Error for ManifestError {}
+
+impl std::fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ManifestError::ReadFailure(x, e) => {
+                write!(
+                    f,
+                    "Unable to read manifest {}, caused by: {}",
+                    x.display(),
+                    e
+                )
+            }
+            ManifestError::InvalidConfig(x, e) => {
+                write!(f, "Invalid manifest {}: {}", x.display(), e)
+            }
+            ManifestError::MissingKey { path, table, key } => write!(
+                f,
+                "Invalid manifest {}: {} is missing from the [{}] table",
+                path.display(),
+                key,
+                table
+            ),
+            ManifestError::InvalidValue {
+                path,
+                key,
+                value,
+                expected,
+            } => write!(
+                f,
+                "Invalid manifest {}: {} cannot be {}, expected {}",
+                path.display(),
+                key,
+                value,
+                expected
+            ),
+            ManifestError::InvalidProjectName(x) => {
+                write!(
+                    f,
+                    "Unable to name a project after the path: {}",
+                    x.display()
+                )
+            }
+            ManifestError::ProjectExists(x) => write!(
+                f,
+                "Unable to create a project in {}, it already exists and is not empty",
+                x.display()
+            ),
+            ManifestError::CreateFailure(x, e) => {
+                write!(f, "Unable to create {}, caused by: {}", x.display(), e)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Manifest {
+    pub name: String,
+    pub entries: Vec<PathBuf>,
+    pub target: Option<Target>,
+    pub opt_level: Option<OptLevel>,
+    pub out_dir: PathBuf,
+}
+
+/*
+* Returns the path to the nearest manifest in the directory or one of its parents. The path is
+* built from the directory given and .. for each parent, so a relative directory keeps the entries
+* relative for diagnostics ("" gives mgs.toml or ../mgs.toml)
+*/
+pub fn find(dir: &Path) -> Option<PathBuf> {
+    let start = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let absolute = fs::canonicalize(start).ok()?;
+    absolute
+        .ancestors()
+        .position(|x| x.join(MANIFEST_FILE).is_file())
+        .map(|parents| {
+            let mut path = dir.to_path_buf();
+            for _ in 0..parents {
+                path.push("..");
+            }
+            path.join(MANIFEST_FILE)
+        })
+}
+
+pub fn read(path: &Path) -> Result<Manifest, ManifestError> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| ManifestError::ReadFailure(path.to_path_buf(), e))?;
+    parse(path, &contents)
+}
+
+fn parse(path: &Path, contents: &str) -> Result<Manifest, ManifestError> {
+    let config =
+        config::parse(contents).map_err(|e| ManifestError::InvalidConfig(path.to_path_buf(), e))?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let invalid = |key, value: &Value, expected: &str| ManifestError::InvalidValue {
+        path: path.to_path_buf(),
+        key,
+        value: value.clone(),
+        expected: expected.to_string(),
+    };
+    let required = |key| {
+        config.get("package", key).ok_or(ManifestError::MissingKey {
+            path: path.to_path_buf(),
+            table: "package",
+            key,
+        })
+    };
+
+    let name = match required("name")? {
+        Value::String(x) if !x.is_empty() => x.clone(),
+        x => return Err(invalid("name", x, "the name of the project")),
+    };
+
+    /*
+     * A single entry can be given without the brackets of a list
+     */
+    let entries = match required("entries")? {
+        Value::String(x) => vec![x.clone()],
+        x @ Value::Array(values) if !values.is_empty() => values
+            .iter()
+            .map(|value| match value {
+                Value::String(entry) => Ok(entry.clone()),
+                _ => Err(invalid("entries", x, "a list of source files")),
+            })
+            .collect::<Result<Vec<String>, ManifestError>>()?,
+        x => return Err(invalid("entries", x, "a list of source files")),
+    };
+
+    let target = match config.get("build", "target") {
+        Some(x @ Value::String(value)) => Some(Target::from_str(value).map_err(|_| {
+            invalid(
+                "target",
+                x,
+                &format!("one of [{}]", Target::get_values_string().to_lowercase()),
+            )
+        })?),
+        Some(x) => return Err(invalid("target", x, "the name of a target")),
+        None => None,
+    };
+    let opt_level = match config.get("build", "opt-level") {
+        Some(x @ Value::Integer(value)) => Some(
+            OptLevel::from_str(&value.to_string())
+                .map_err(|_| invalid("opt-level", x, "one of [0,1,2]"))?,
+        ),
+        Some(x) => return Err(invalid("opt-level", x, "one of [0,1,2]")),
+        None => None,
+    };
+    let out_dir = match config.get("build", "out-dir") {
+        Some(Value::String(x)) => x.as_str(),
+        Some(x) => return Err(invalid("out-dir", x, "the path to a directory")),
+        None => DEFAULT_OUT_DIR,
+    };
+
+    Ok(Manifest {
+        name,
+        entries: entries.iter().map(|x| dir.join(x)).collect(),
+        target,
+        opt_level,
+        out_dir: dir.join(out_dir),
+    })
+}
+
+/*
+* Creates a project named after its directory, with a manifest and a main entry holding some
+* starter code. The directory may already exist as long as it is empty
+*/
+pub fn create(dir: &Path) -> Result<String, ManifestError> {
+    let name = dir
+        .file_name()
+        .and_then(|x| x.to_str())
+        .ok_or(ManifestError::InvalidProjectName(dir.to_path_buf()))?;
+    if fs::read_dir(dir).is_ok_and(|mut x| x.next().is_some()) {
+        return Err(ManifestError::ProjectExists(dir.to_path_buf()));
+    }
+
+    let manifest = format!(
+        "[package]\nname = {}\nentries = [{}]\n\n[build]\ntarget = {}\nopt-level = 0\nout-dir = {}\n",
+        Value::String(name.to_string()),
+        Value::String(MAIN_ENTRY.to_string()),
+        Value::String(Target::ARM64.config_name()),
+        Value::String(DEFAULT_OUT_DIR.to_string())
+    );
+    let files = [
+        (PathBuf::from(MANIFEST_FILE), manifest),
+        (PathBuf::from(MAIN_ENTRY), STARTER_CODE.to_string()),
+        (
+            PathBuf::from(".gitignore"),
+            format!("/{}\n", DEFAULT_OUT_DIR),
+        ),
+    ];
+    for (file, contents) in files {
+        let path = dir.join(file);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| ManifestError::CreateFailure(parent.to_path_buf(), e))?;
+        }
+        fs::write(&path, contents).map_err(|e| ManifestError::CreateFailure(path, e))?;
+    }
+    Ok(name.to_string())
+}
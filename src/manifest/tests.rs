@@ -0,0 +1,93 @@
+use super::*;
+
+fn temp_dir(test_name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("mgs_manifest_{}_{}", std::process::id(), test_name))
+}
+
+#[test]
+fn test_parse_manifest() {
+    let manifest = parse(
+        Path::new("demo/mgs.toml"),
+        "[package]\nname = \"demo\"\nentries = [\"src/main.mgs\", \"src/tool.mgs\"]\n\n[build]\ntarget = \"arm64\"\nopt-level = 1\nout-dir = \"out\"",
+    )
+    .unwrap();
+    assert_eq!(manifest.name, "demo");
+    assert_eq!(
+        manifest.entries,
+        vec![
+            PathBuf::from("demo/src/main.mgs"),
+            PathBuf::from("demo/src/tool.mgs")
+        ]
+    );
+    assert_eq!(manifest.target, Some(Target::ARM64));
+    assert_eq!(manifest.opt_level, Some(OptLevel::O1));
+    assert_eq!(manifest.out_dir, PathBuf::from("demo/out"));
+
+    let manifest = parse(
+        Path::new("mgs.toml"),
+        "[package]\nname = \"demo\"\nentries = \"main.mgs\"",
+    )
+    .unwrap();
+    assert_eq!(manifest.entries, vec![PathBuf::from("main.mgs")]);
+    assert_eq!(manifest.target, None);
+    assert_eq!(manifest.opt_level, None);
+    assert_eq!(manifest.out_dir, PathBuf::from("build"));
+}
+
+#[test]
+fn test_should_error_for_invalid_manifest() {
+    let test_cases = vec![
+        (
+            "[package]\nentries = []",
+            "Invalid manifest mgs.toml: name is missing from the [package] table",
+        ),
+        (
+            "[package]\nname = \"demo\"\nentries = []",
+            "Invalid manifest mgs.toml: entries cannot be [], expected a list of source files",
+        ),
+        (
+            "[package]\nname = \"demo\"\nentries = [\"main.mgs\", 1]",
+            "Invalid manifest mgs.toml: entries cannot be [\"main.mgs\", 1], expected a list of source files",
+        ),
+        (
+            "[package]\nname = \"demo\"\nentries = \"main.mgs\"\n[build]\nopt-level = 3",
+            "Invalid manifest mgs.toml: opt-level cannot be 3, expected one of [0,1,2]",
+        ),
+        (
+            "[package]\nname = \"demo\"\nentries = \"main.mgs\"\n[build]\ntarget = \"x86\"",
+            "Invalid manifest mgs.toml: target cannot be \"x86\", expected one of [arm64]",
+        ),
+        (
+            "[package]\nname = \"demo\"\nname = \"other\"",
+            "Invalid manifest mgs.toml: Key defined more than once: name, Line: 3, Col: 1",
+        ),
+    ];
+    for (input, expected) in test_cases {
+        assert_eq!(
+            parse(Path::new("mgs.toml"), input).unwrap_err().to_string(),
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_create_and_find_project() {
+    let dir = temp_dir("create").join("demo");
+    assert_eq!(create(&dir).unwrap(), "demo");
+    assert!(matches!(create(&dir), Err(ManifestError::ProjectExists(_))));
+
+    let path = find(&dir.join("src")).unwrap();
+    assert_eq!(path, dir.join("src").join("..").join(MANIFEST_FILE));
+    let manifest = read(&path).unwrap();
+    assert_eq!(
+        manifest.entries,
+        vec![dir.join("src").join("..").join(MAIN_ENTRY)]
+    );
+    assert_eq!(
+        fs::read_to_string(&manifest.entries[0]).unwrap(),
+        STARTER_CODE
+    );
+    assert_eq!(find(&dir).unwrap(), dir.join(MANIFEST_FILE));
+
+    fs::remove_dir_all(temp_dir("create")).unwrap();
+}
@@ -0,0 +1,555 @@
+/*
+* A program can be split across several files, each of which is a module. A module imports another
+* with import (or mod) followed by its path from the importing file's directory, so import
+* shapes::circle; loads shapes/circle.mgs. The items at the top level of a module (variables,
+* constants, structs and enums) are in its own namespace, and the public ones, declared with pub,
+* are used by the importing module through the last name of the path (e.g. circle::area)
+*
+* The modules are linked into a single program. Each module's items are renamed to the path of the
+* module from the compiled file's directory (e.g. shapes::circle::area), while the items of the
+* compiled file itself keep their names. An imported module's statements run before those of the
+* module importing it, and a module imported from several places is only included once. Modules
+* can't import each other in a cycle, since neither could run first
+*/
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cli::SOURCE_EXTENSION,
+    lexer::{self, LexError},
+    parser::{self, Expression, ExpressionKind, ParseError, PatternKind, Program, Statement, Type},
+    source::{Located, SourceMap, Span},
+    token::{TextLocation, Token},
+};
+
+#[derive(Debug)]
+pub enum ModuleError {
+    LexFailure(LexError),
+    ParseFailure(ParseError),
+    ModuleNotFound {
+        path: PathBuf,
+        location: TextLocation,
+    },
+    ModuleReadFailure {
+        path: PathBuf,
+        error: io::Error,
+    },
+    ImportCycle(Vec<PathBuf>),
+    DuplicateImport {
+        name: String,
+        location: TextLocation,
+    },
+    UnknownModule {
+        name: String,
+        location: TextLocation,
+    },
+    UnknownItem {
+        module: String,
+        item: String,
+        location: TextLocation,
+    },
+    PrivateItem {
+        module: String,
+        item: String,
+        location: TextLocation,
+    },
+    ModuleAsEnum {
+        name: String,
+        location: TextLocation,
+    },
+}
+
+impl std::error::Error for ModuleError {}
+
+impl Located for ModuleError {
+    fn location_mut(&mut self) -> Option<&mut TextLocation> {
+        match self {
+            ModuleError::LexFailure(e) => e.location_mut(),
+            ModuleError::ParseFailure(e) => e.location_mut(),
+            ModuleError::ModuleNotFound { location, .. }
+            | ModuleError::DuplicateImport { location, .. }
+            | ModuleError::UnknownModule { location, .. }
+            | ModuleError::UnknownItem { location, .. }
+            | ModuleError::PrivateItem { location, .. }
+            | ModuleError::ModuleAsEnum { location, .. } => Some(location),
+            ModuleError::ModuleReadFailure { .. } | ModuleError::ImportCycle(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ModuleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModuleError::LexFailure(e) => write!(f, "{}", e),
+            ModuleError::ParseFailure(e) => write!(f, "{}", e),
+            ModuleError::ModuleNotFound { path, location } => write!(
+                f,
+                "Unable to find module file: {}, imported at {}",
+                path.display(),
+                location
+            ),
+            ModuleError::ModuleReadFailure { path, error } => write!(
+                f,
+                "Unable to read module file {}, caused by: {}",
+                path.display(),
+                error
+            ),
+            ModuleError::ImportCycle(chain) => {
+                write!(f, "Modules cannot import each other in a cycle: ")?;
+                for (idx, path) in chain.iter().enumerate() {
+                    match idx {
+                        0 => write!(f, "{}", path.display())?,
+                        1 => write!(f, " imports {}", path.display())?,
+                        _ => write!(f, ", which imports {}", path.display())?,
+                    }
+                }
+                Ok(())
+            }
+            ModuleError::DuplicateImport { name, location } => {
+                write!(f, "Module imported more than once: {}, {}", name, location)
+            }
+            ModuleError::UnknownModule { name, location } => {
+                write!(f, "Module {} is not imported, {}", name, location)
+            }
+            ModuleError::UnknownItem {
+                module,
+                item,
+                location,
+            } => write!(f, "Module {} has no item {}, {}", module, item, location),
+            ModuleError::PrivateItem {
+                module,
+                item,
+                location,
+            } => write!(
+                f,
+                "Item {} of module {} is private, it must be declared with pub to be used here, {}",
+                item, module, location
+            ),
+            ModuleError::ModuleAsEnum { name, location } => write!(
+                f,
+                "Expected an enum but found the module {}, {}",
+                name, location
+            ),
+        }
+    }
+}
+
+struct LinkedModule {
+    /*
+     * The path items are renamed to, which the compiled file doesn't have
+     */
+    prefix: Option<String>,
+    program: Program,
+    public: HashSet<String>,
+    values: HashSet<String>,
+    types: HashSet<String>,
+    imports: HashMap<String, usize>,
+}
+
+impl LinkedModule {
+    fn item_path(&self, name: &str) -> String {
+        match &self.prefix {
+            Some(prefix) => format!("{}::{}", prefix, name),
+            None => name.to_string(),
+        }
+    }
+}
+
+struct Linker<'a> {
+    root_dir: PathBuf,
+    source_map: &'a mut SourceMap,
+    modules: Vec<LinkedModule>,
+    loaded: HashMap<PathBuf, usize>,
+    importing: Vec<PathBuf>,
+}
+
+/*
+* Links the file being compiled, whose tokens are given, with every module it imports. The source
+* map starts with the compiled file and has the imported files added to it
+*/
+pub fn link(
+    path: &Path,
+    tokens: Vec<Token>,
+    source_map: &mut SourceMap,
+) -> Result<Program, ModuleError> {
+    let mut linker = Linker {
+        root_dir: path.parent().unwrap_or(Path::new("")).to_path_buf(),
+        source_map,
+        modules: Vec::new(),
+        loaded: HashMap::new(),
+        importing: Vec::new(),
+    };
+    linker.load(path.to_path_buf(), &[], tokens)?;
+
+    let mut statements = Vec::new();
+    for idx in 0..linker.modules.len() {
+        let mut module_statements = std::mem::take(&mut linker.modules[idx].program.statements);
+        let mut renamer = Renamer {
+            modules: &linker.modules,
+            module: &linker.modules[idx],
+            source_map: linker.source_map,
+            scopes: Vec::new(),
+        };
+        for statement in &mut module_statements {
+            renamer.rename_statement(statement)?;
+        }
+        statements.extend(module_statements);
+    }
+    Ok(Program { statements })
+}
+
+impl Linker<'_> {
+    /*
+     * Loads a module after the modules it imports, returning its index. The path of the module is
+     * given as the names of the directories it is in from the compiled file's directory followed by
+     * its own name, which is empty for the compiled file
+     */
+    fn load(
+        &mut self,
+        file: PathBuf,
+        path: &[String],
+        tokens: Vec<Token>,
+    ) -> Result<usize, ModuleError> {
+        let module = parser::parse_module(tokens).map_err(ModuleError::ParseFailure)?;
+        let dir = path.split_last().map(|(_, dir)| dir).unwrap_or_default();
+
+        self.importing.push(file.clone());
+        let mut imports = HashMap::new();
+        for import in &module.imports {
+            let location = self.source_map.location(import.span.start);
+            let import_path: Vec<String> = dir.iter().chain(&import.path).cloned().collect();
+            let mut import_file = self.root_dir.clone();
+            import_file.extend(&import_path);
+            import_file.set_extension(SOURCE_EXTENSION);
+
+            if self.importing.contains(&import_file) {
+                let mut chain = self.importing.clone();
+                chain.push(import_file);
+                return Err(ModuleError::ImportCycle(chain));
+            }
+            let idx = match self.loaded.get(&import_file) {
+                Some(idx) => *idx,
+                None => {
+                    let contents = read_module(&import_file, location.clone())?;
+                    let offset = self
+                        .source_map
+                        .add_file(&import_file.display().to_string(), &contents);
+                    let tokens = lexer::parse_file(self.source_map, offset, &contents)
+                        .map_err(ModuleError::LexFailure)?;
+                    self.load(import_file, &import_path, tokens)?
+                }
+            };
+            if imports.insert(import.name().to_string(), idx).is_some() {
+                return Err(ModuleError::DuplicateImport {
+                    name: import.name().to_string(),
+                    location,
+                });
+            }
+        }
+        self.importing.pop();
+
+        let mut values = HashSet::new();
+        let mut types = HashSet::new();
+        for statement in &module.program.statements {
+            match statement {
+                Statement::StructStatement { name, .. } | Statement::EnumStatement { name, .. } => {
+                    types.insert(name.clone());
+                }
+                x => {
+                    if let Some(name) = x.item_name() {
+                        values.insert(name.to_string());
+                    }
+                }
+            }
+        }
+
+        let idx = self.modules.len();
+        self.modules.push(LinkedModule {
+            prefix: (!path.is_empty()).then(|| path.join("::")),
+            program: module.program,
+            public: module.public,
+            values,
+            types,
+            imports,
+        });
+        self.loaded.insert(file, idx);
+        Ok(idx)
+    }
+}
+
+fn read_module(path: &Path, location: TextLocation) -> Result<String, ModuleError> {
+    fs::read_to_string(path).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => ModuleError::ModuleNotFound {
+            path: path.to_path_buf(),
+            location,
+        },
+        _ => ModuleError::ModuleReadFailure {
+            path: path.to_path_buf(),
+            error: e,
+        },
+    })
+}
+
+/*
+* Renames the items of a module, and the uses of them and of other modules' items, to their path.
+* Variables declared within blocks and bound by match arms are tracked in scopes since they may
+* shadow the module's items, whereas types can only be defined at the top level
+*/
+struct Renamer<'a> {
+    modules: &'a [LinkedModule],
+    module: &'a LinkedModule,
+    source_map: &'a SourceMap,
+    scopes: Vec<HashSet<String>>,
+}
+
+impl Renamer<'_> {
+    fn rename_statement(&mut self, statement: &mut Statement) -> Result<(), ModuleError> {
+        match statement {
+            Statement::DeclarationStatement {
+                v_name,
+                v_span,
+                ty,
+                expr,
+                ..
+            } => {
+                self.rename_type(ty, *v_span)?;
+                if let Some(x) = expr {
+                    self.rename_expression(x)?;
+                }
+                self.declare(v_name);
+            }
+            Statement::ConstStatement {
+                v_name,
+                v_span,
+                ty,
+                expr,
+                ..
+            } => {
+                self.rename_type(ty, *v_span)?;
+                self.rename_expression(expr)?;
+                self.declare(v_name);
+            }
+            Statement::AssignmentStatement { target, expr, .. } => {
+                self.rename_expression(target)?;
+                self.rename_expression(expr)?;
+            }
+            Statement::BlockStatement { statements, .. } => {
+                self.scopes.push(HashSet::new());
+                let result = statements
+                    .iter_mut()
+                    .try_for_each(|x| self.rename_statement(x));
+                self.scopes.pop();
+                result?;
+            }
+            Statement::IfStatement {
+                condition,
+                then_block,
+                else_block,
+                ..
+            } => {
+                self.rename_expression(condition)?;
+                self.rename_statement(then_block)?;
+                if let Some(x) = else_block {
+                    self.rename_statement(x)?;
+                }
+            }
+            Statement::WhileStatement {
+                condition, body, ..
+            } => {
+                self.rename_expression(condition)?;
+                self.rename_statement(body)?;
+            }
+            Statement::MatchStatement {
+                scrutinee, arms, ..
+            } => {
+                self.rename_expression(scrutinee)?;
+                for arm in arms {
+                    self.enter_arm(&mut arm.pattern.kind, arm.pattern.span)?;
+                    let result = self.rename_statement(&mut arm.body);
+                    self.scopes.pop();
+                    result?;
+                }
+            }
+            Statement::StructStatement { name, fields, .. } => {
+                *name = self.module.item_path(name);
+                for field in fields {
+                    self.rename_type(&mut field.ty, field.span)?;
+                }
+            }
+            Statement::EnumStatement { name, variants, .. } => {
+                *name = self.module.item_path(name);
+                for variant in variants {
+                    for ty in &mut variant.payload {
+                        self.rename_type(ty, variant.span)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn rename_expression(&mut self, expr: &mut Expression) -> Result<(), ModuleError> {
+        let span = expr.span;
+        match &mut expr.kind {
+            ExpressionKind::ValExpr(_) => {}
+            ExpressionKind::VarExpr(x) => *x = self.resolve_value(x, span)?,
+            ExpressionKind::ArithmeticExpr(x, _, y)
+            | ExpressionKind::BooleanExpr(x, _, y)
+            | ExpressionKind::IndexExpr(x, y) => {
+                self.rename_expression(x)?;
+                self.rename_expression(y)?;
+            }
+            ExpressionKind::CastExpr(x, ty) => {
+                self.rename_expression(x)?;
+                self.rename_type(ty, span)?;
+            }
+            ExpressionKind::FieldExpr(x, _)
+            | ExpressionKind::AddressOf(x)
+            | ExpressionKind::Deref(x) => self.rename_expression(x)?,
+            ExpressionKind::StructLiteral(name, fields) => {
+                *name = self.resolve_type(name, span)?;
+                for (_, value) in fields {
+                    self.rename_expression(value)?;
+                }
+            }
+            /*
+             * The parser takes a path of two names without values to be an enum variant, when the
+             * first name is a module it is that module's variable instead. A first name which is
+             * neither an imported module nor one of the module's types is taken to be a module that
+             * wasn't imported, as it is more often that than an undefined enum
+             */
+            ExpressionKind::VariantLiteral(name, variant, values)
+                if self.module.imports.contains_key(name.as_str())
+                    || (values.is_empty()
+                        && !name.contains("::")
+                        && !self.module.types.contains(name.as_str())) =>
+            {
+                if !values.is_empty() {
+                    return Err(ModuleError::ModuleAsEnum {
+                        name: name.clone(),
+                        location: self.source_map.location(span.start),
+                    });
+                }
+                let path = format!("{}::{}", name, variant);
+                expr.kind = ExpressionKind::VarExpr(self.resolve_value(&path, span)?);
+            }
+            ExpressionKind::VariantLiteral(name, _, values) => {
+                *name = self.resolve_type(name, span)?;
+                for value in values {
+                    self.rename_expression(value)?;
+                }
+            }
+            ExpressionKind::MatchExpr(x, arms) => {
+                self.rename_expression(x)?;
+                for arm in arms {
+                    self.enter_arm(&mut arm.pattern.kind, arm.pattern.span)?;
+                    let result = self.rename_expression(&mut arm.body);
+                    self.scopes.pop();
+                    result?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn rename_type(&self, ty: &mut Type, span: Span) -> Result<(), ModuleError> {
+        match ty {
+            Type::Array(x, _) | Type::Pointer(x) => self.rename_type(x, span),
+            Type::Struct(name) | Type::Enum(name) => {
+                *name = self.resolve_type(name, span)?;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /*
+     * Enters the scope of a match arm, which has the variables bound by its pattern
+     */
+    fn enter_arm(&mut self, pattern: &mut PatternKind, span: Span) -> Result<(), ModuleError> {
+        let mut scope = HashSet::new();
+        if let PatternKind::Variant(name, _, bindings) = pattern {
+            *name = self.resolve_type(name, span)?;
+            scope.extend(bindings.iter().map(|(v_name, _)| v_name.clone()));
+        }
+        self.scopes.push(scope);
+        Ok(())
+    }
+
+    /*
+     * A variable declared at the top level is one of the module's items
+     */
+    fn declare(&mut self, v_name: &mut String) {
+        match self.scopes.last_mut() {
+            Some(scope) => {
+                scope.insert(v_name.clone());
+            }
+            None => *v_name = self.module.item_path(v_name),
+        }
+    }
+
+    fn resolve_value(&self, name: &str, span: Span) -> Result<String, ModuleError> {
+        if let Some((module, item)) = name.split_once("::") {
+            return self.resolve_path(module, item, span, |x| &x.values);
+        }
+        if self.module.values.contains(name) && !self.scopes.iter().any(|x| x.contains(name)) {
+            return Ok(self.module.item_path(name));
+        }
+        Ok(name.to_string())
+    }
+
+    fn resolve_type(&self, name: &str, span: Span) -> Result<String, ModuleError> {
+        if let Some((module, item)) = name.split_once("::") {
+            return self.resolve_path(module, item, span, |x| &x.types);
+        }
+        if self.module.types.contains(name) {
+            return Ok(self.module.item_path(name));
+        }
+        Ok(name.to_string())
+    }
+
+    /*
+     * Finds a public item of an imported module, the items searched are either its values or its
+     * types
+     */
+    fn resolve_path(
+        &self,
+        module: &str,
+        item: &str,
+        span: Span,
+        items: impl Fn(&LinkedModule) -> &HashSet<String>,
+    ) -> Result<String, ModuleError> {
+        let location = self.source_map.location(span.start);
+        let imported = match self.module.imports.get(module) {
+            Some(idx) => &self.modules[*idx],
+            None => {
+                return Err(ModuleError::UnknownModule {
+                    name: module.to_string(),
+                    location,
+                });
+            }
+        };
+        if !items(imported).contains(item) {
+            return Err(ModuleError::UnknownItem {
+                module: module.to_string(),
+                item: item.to_string(),
+                location,
+            });
+        }
+        if !imported.public.contains(item) {
+            return Err(ModuleError::PrivateItem {
+                module: module.to_string(),
+                item: item.to_string(),
+                location,
+            });
+        }
+        Ok(imported.item_path(item))
+    }
+}
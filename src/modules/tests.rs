@@ -0,0 +1,198 @@
+use super::*;
+
+/*
+* Writes the files to a directory of their own and links the first of them
+*/
+fn link_files(test_name: &str, files: &[(&str, &str)]) -> Result<Program, ModuleError> {
+    let dir =
+        std::env::temp_dir().join(format!("mgs_modules_{}_{}", std::process::id(), test_name));
+    for (path, contents) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+    let root = dir.join(files[0].0);
+    let mut source_map = SourceMap::new(files[0].1);
+    let tokens = lexer::parse_text(files[0].1).unwrap();
+    let result = link(&root, tokens, &mut source_map);
+    fs::remove_dir_all(dir).unwrap();
+    result
+}
+
+fn statement_names(program: &Program) -> Vec<String> {
+    program
+        .statements
+        .iter()
+        .filter_map(|x| x.item_name())
+        .map(|x| x.to_string())
+        .collect()
+}
+
+#[test]
+fn test_modules_are_linked_after_their_imports() {
+    let program = link_files(
+        "linked",
+        &[
+            (
+                "main.mgs",
+                "import shapes;\nmod geo::scale;\nint x = shapes::width * scale::factor;",
+            ),
+            (
+                "shapes.mgs",
+                "import geo::scale;\npub int width = scale::factor;\nint hidden = width;",
+            ),
+            ("geo/scale.mgs", "pub const int factor = 2;"),
+        ],
+    )
+    .unwrap();
+    assert_eq!(
+        statement_names(&program),
+        vec!["geo::scale::factor", "shapes::width", "shapes::hidden", "x"]
+    );
+    assert_eq!(
+        program.statements[2].to_string(),
+        "Declaring shapes::hidden with value shapes::width"
+    );
+    assert_eq!(
+        program.statements[3].to_string(),
+        "Declaring x with value shapes::width * geo::scale::factor"
+    );
+}
+
+#[test]
+fn test_types_and_variants_of_modules_are_renamed() {
+    let program = link_files(
+        "types",
+        &[
+            (
+                "main.mgs",
+                "import shapes;\nshapes::Shape s = shapes::Shape::Rect(shapes::Point { x: 1 });\nint n = match s { shapes::Shape::Rect(p) => p.x, _ => 0 };\nshapes::count = n;",
+            ),
+            (
+                "shapes.mgs",
+                "pub struct Point { int x; }\npub enum Shape { Rect(Point); Empty; }\npub mut int count = 0;\n{ int count = 1; Shape e = Shape::Empty; }",
+            ),
+        ],
+    )
+    .unwrap();
+    let lines: Vec<String> = program.statements.iter().map(|x| x.to_string()).collect();
+    assert_eq!(
+        lines[1],
+        "Defining enum shapes::Shape with variants Rect(shapes::Point), Empty"
+    );
+    assert!(lines[3].contains("Declaring count with value 1"));
+    assert!(lines[3].contains("Declaring e with value shapes::Shape::Empty"));
+    assert_eq!(
+        lines[4],
+        "Declaring s with value shapes::Shape::Rect(shapes::Point { x: 1 })"
+    );
+    assert_eq!(
+        lines[5],
+        "Declaring n with value match s { shapes::Shape::Rect(p) => p.x, _ => 0 }"
+    );
+    assert_eq!(lines[6], "Assigning shapes::count to value n");
+}
+
+#[test]
+fn test_module_imported_twice_is_linked_once() {
+    let program = link_files(
+        "diamond",
+        &[
+            ("main.mgs", "import a;\nimport b;\nint x = a::y + b::z;"),
+            ("a.mgs", "import c;\npub int y = c::w;"),
+            ("b.mgs", "import c;\npub int z = c::w;"),
+            ("c.mgs", "pub int w = 1;"),
+        ],
+    )
+    .unwrap();
+    assert_eq!(statement_names(&program), vec!["c::w", "a::y", "b::z", "x"]);
+}
+
+#[test]
+fn test_should_error_for_invalid_imports() {
+    let test_cases = vec![
+        (
+            vec![
+                ("main.mgs", "import shapes;\nint x = shapes::hidden;"),
+                ("shapes.mgs", "int hidden = 1;"),
+            ],
+            "Item hidden of module shapes is private, it must be declared with pub to be used here, Line: 2, Col: 9",
+        ),
+        (
+            vec![
+                ("main.mgs", "import shapes;\nshapes::Point p;"),
+                ("shapes.mgs", "pub int width = 1;"),
+            ],
+            "Module shapes has no item Point, Line: 2, Col: 15",
+        ),
+        (
+            vec![("main.mgs", "int x = shapes::width[0];")],
+            "Module shapes is not imported, Line: 1, Col: 9",
+        ),
+        (
+            vec![("main.mgs", "int x = c::v;"), ("c.mgs", "pub int v = 1;")],
+            "Module c is not imported, Line: 1, Col: 9",
+        ),
+        (
+            vec![
+                ("main.mgs", "import shapes;\nint x = shapes::width(1);"),
+                ("shapes.mgs", "pub int width = 1;"),
+            ],
+            "Expected an enum but found the module shapes, Line: 2, Col: 9",
+        ),
+        (
+            vec![
+                ("main.mgs", "import shapes;\nmod shapes;"),
+                ("shapes.mgs", ""),
+            ],
+            "Module imported more than once: shapes, Line: 2, Col: 1",
+        ),
+        (
+            vec![
+                ("main.mgs", "import shapes;"),
+                ("shapes.mgs", "int x = 1;\nint y = ;"),
+            ],
+            "Unable to parse statement starting from token [(Equals: =), Line: 2, Col: 7 in ",
+        ),
+    ];
+    for (idx, (files, expected)) in test_cases.into_iter().enumerate() {
+        let e = link_files(&format!("invalid_{}", idx), &files).unwrap_err();
+        assert!(
+            e.to_string().starts_with(expected),
+            "{} does not start with {}",
+            e,
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_should_error_for_import_cycles_and_missing_modules() {
+    let e = link_files(
+        "cycle",
+        &[
+            ("main.mgs", "import a;"),
+            ("a.mgs", "import b;"),
+            ("b.mgs", "import a;"),
+        ],
+    )
+    .unwrap_err();
+    match &e {
+        ModuleError::ImportCycle(chain) => {
+            let names: Vec<_> = chain.iter().filter_map(|x| x.file_name()).collect();
+            assert_eq!(names, vec!["main.mgs", "a.mgs", "b.mgs", "a.mgs"]);
+        }
+        x => panic!("Unexpected error: {}", x),
+    }
+    let message = e.to_string();
+    assert!(message.starts_with("Modules cannot import each other in a cycle: "));
+    assert_eq!(message.matches(" imports ").count(), 3);
+    assert_eq!(message.matches(", which imports ").count(), 2);
+
+    let e = link_files("missing", &[("main.mgs", "\nimport geo::missing;")]).unwrap_err();
+    assert!(e.to_string().starts_with("Unable to find module file: "));
+    assert!(
+        e.to_string()
+            .ends_with("missing.mgs, imported at Line: 2, Col: 1")
+    );
+}
@@ -0,0 +1,61 @@
+/*
+* Dead store elimination removes assignments and declarations whose value is never read, as found by
+* the liveness walk. The variables in the outermost scope are live when the program exits. Stores to
+* a variable which has its address taken are never removed, and writing through a pointer reads the
+* pointer and the value stored
+*/
+
+use std::collections::HashSet;
+
+use crate::{
+    liveness::{self, Mode},
+    parser::{Program, Statement},
+    source::Span,
+};
+
+pub fn eliminate_dead_stores(program: &mut Program) {
+    let live_at_exit = liveness::declared_variables(&program.statements);
+    let dead_stores = liveness::dead_stores(program, live_at_exit, Mode::Eliminate);
+    remove_statements(&mut program.statements, &dead_stores);
+}
+
+fn remove_statements(statements: &mut Vec<Statement>, dead_stores: &HashSet<Span>) {
+    statements.retain_mut(|statement| {
+        let is_dead = match statement {
+            Statement::DeclarationStatement { v_span: span, .. }
+            | Statement::AssignmentStatement { span, .. } => dead_stores.contains(span),
+            _ => false,
+        };
+        if !is_dead {
+            remove_nested_statements(statement, dead_stores);
+        }
+        !is_dead
+    });
+}
+
+fn remove_nested_statements(statement: &mut Statement, dead_stores: &HashSet<Span>) {
+    match statement {
+        Statement::BlockStatement { statements, .. } => remove_statements(statements, dead_stores),
+        Statement::IfStatement {
+            then_block,
+            else_block,
+            ..
+        } => {
+            remove_nested_statements(then_block, dead_stores);
+            if let Some(x) = else_block {
+                remove_nested_statements(x, dead_stores);
+            }
+        }
+        Statement::WhileStatement { body, .. } => remove_nested_statements(body, dead_stores),
+        Statement::MatchStatement { arms, .. } => {
+            for arm in arms {
+                remove_nested_statements(&mut arm.body, dead_stores);
+            }
+        }
+        Statement::DeclarationStatement { .. }
+        | Statement::ConstStatement { .. }
+        | Statement::AssignmentStatement { .. }
+        | Statement::StructStatement { .. }
+        | Statement::EnumStatement { .. } => {}
+    }
+}
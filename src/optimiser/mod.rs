@@ -0,0 +1,275 @@
+/*
+* The optimiser runs a sequence of passes over the program once it has been analysed and folded.
+* Which passes run is decided by the optimisation level, but each pass can also be enabled or
+* disabled on its own, which is mostly useful for finding the pass responsible for a problem
+*
+* Until programs can produce output the only way to observe one is with a debugger, so the values
+* of variables in the outermost scope are treated as observable when the program exits
+*/
+
+mod dead_store;
+mod propagation;
+mod unreachable;
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::HashSet;
+
+use crate::{
+    parser::{Expression, ExpressionKind, Literal, Program, Statement},
+    source::SourceMap,
+};
+
+use propagation::Propagation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pass {
+    ConstantPropagation,
+    CopyPropagation,
+    CommonSubexpressionElimination,
+    UnreachableCodeRemoval,
+    DeadStoreElimination,
+}
+
+#[derive(Debug)]
+pub struct PassParseError;
+
+impl Pass {
+    /*
+     * Passes always run in this order, so that each pass can make use of the work done by the
+     * previous ones, e.g. propagating constants can make a branch condition known
+     */
+    const ALL: [Pass; 5] = [
+        Pass::ConstantPropagation,
+        Pass::CopyPropagation,
+        Pass::CommonSubexpressionElimination,
+        Pass::UnreachableCodeRemoval,
+        Pass::DeadStoreElimination,
+    ];
+
+    pub fn get_values_string() -> String {
+        Pass::ALL
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+}
+
+impl std::fmt::Display for Pass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pass::ConstantPropagation => write!(f, "const-prop"),
+            Pass::CopyPropagation => write!(f, "copy-prop"),
+            Pass::CommonSubexpressionElimination => write!(f, "cse"),
+            Pass::UnreachableCodeRemoval => write!(f, "unreachable-code"),
+            Pass::DeadStoreElimination => write!(f, "dead-store"),
+        }
+    }
+}
+
+impl std::str::FromStr for Pass {
+    type Err = PassParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Pass::ALL
+            .into_iter()
+            .find(|x| x.to_string() == s)
+            .ok_or(PassParseError)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptLevel {
+    O0,
+    O1,
+    O2,
+}
+
+#[derive(Debug)]
+pub struct OptLevelParseError;
+
+impl OptLevel {
+    fn includes(&self, pass: Pass) -> bool {
+        match self {
+            OptLevel::O0 => false,
+            OptLevel::O1 => matches!(
+                pass,
+                Pass::ConstantPropagation | Pass::CopyPropagation | Pass::UnreachableCodeRemoval
+            ),
+            OptLevel::O2 => true,
+        }
+    }
+}
+
+impl std::str::FromStr for OptLevel {
+    type Err = OptLevelParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "0" => Ok(OptLevel::O0),
+            "1" => Ok(OptLevel::O1),
+            "2" => Ok(OptLevel::O2),
+            _ => Err(OptLevelParseError),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct OptimiserOptions {
+    pub level: OptLevel,
+    enabled: Vec<Pass>,
+    disabled: Vec<Pass>,
+}
+
+impl OptimiserOptions {
+    pub fn new(level: OptLevel) -> Self {
+        Self {
+            level,
+            enabled: Vec::new(),
+            disabled: Vec::new(),
+        }
+    }
+
+    pub fn enable(&mut self, pass: Pass) {
+        self.disabled.retain(|x| *x != pass);
+        self.enabled.push(pass);
+    }
+
+    pub fn disable(&mut self, pass: Pass) {
+        self.enabled.retain(|x| *x != pass);
+        self.disabled.push(pass);
+    }
+
+    pub fn passes(&self) -> Vec<Pass> {
+        Pass::ALL
+            .into_iter()
+            .filter(|x| self.level.includes(*x) || self.enabled.contains(x))
+            .filter(|x| !self.disabled.contains(x))
+            .collect()
+    }
+}
+
+pub fn optimise(program: &mut Program, options: &OptimiserOptions, source_map: &SourceMap) {
+    for pass in options.passes() {
+        match pass {
+            Pass::ConstantPropagation => {
+                propagation::propagate(program, Propagation::Constant, source_map)
+            }
+            Pass::CopyPropagation => propagation::propagate(program, Propagation::Copy, source_map),
+            Pass::CommonSubexpressionElimination => {
+                propagation::propagate(program, Propagation::CommonSubexpression, source_map)
+            }
+            Pass::UnreachableCodeRemoval => unreachable::remove_unreachable_code(program),
+            Pass::DeadStoreElimination => dead_store::eliminate_dead_stores(program),
+        }
+    }
+}
+
+/*
+* Compares the values of two expressions, ignoring where they appear in the source
+*/
+fn same_expression(x: &Expression, y: &Expression) -> bool {
+    if x.ty != y.ty {
+        return false;
+    }
+    match (&x.kind, &y.kind) {
+        (ExpressionKind::ValExpr(a), ExpressionKind::ValExpr(b))
+        | (ExpressionKind::VarExpr(a), ExpressionKind::VarExpr(b)) => a == b,
+        (
+            ExpressionKind::ArithmeticExpr(a_lhs, a_op, a_rhs),
+            ExpressionKind::ArithmeticExpr(b_lhs, b_op, b_rhs),
+        )
+        | (
+            ExpressionKind::BooleanExpr(a_lhs, a_op, a_rhs),
+            ExpressionKind::BooleanExpr(b_lhs, b_op, b_rhs),
+        ) => a_op == b_op && same_expression(a_lhs, b_lhs) && same_expression(a_rhs, b_rhs),
+        (ExpressionKind::CastExpr(a, a_ty), ExpressionKind::CastExpr(b, b_ty)) => {
+            a_ty == b_ty && same_expression(a, b)
+        }
+        (ExpressionKind::IndexExpr(a, a_index), ExpressionKind::IndexExpr(b, b_index)) => {
+            same_expression(a, b) && same_expression(a_index, b_index)
+        }
+        (ExpressionKind::FieldExpr(a, a_field), ExpressionKind::FieldExpr(b, b_field)) => {
+            a_field == b_field && same_expression(a, b)
+        }
+        (ExpressionKind::AddressOf(a), ExpressionKind::AddressOf(b))
+        | (ExpressionKind::Deref(a), ExpressionKind::Deref(b)) => same_expression(a, b),
+        (
+            ExpressionKind::StructLiteral(a_name, a_fields),
+            ExpressionKind::StructLiteral(b_name, b_fields),
+        ) => {
+            a_name == b_name
+                && a_fields.len() == b_fields.len()
+                && a_fields
+                    .iter()
+                    .zip(b_fields)
+                    .all(|((a, x), (b, y))| a == b && same_expression(x, y))
+        }
+        (
+            ExpressionKind::VariantLiteral(a_name, a_variant, a_values),
+            ExpressionKind::VariantLiteral(b_name, b_variant, b_values),
+        ) => {
+            a_name == b_name
+                && a_variant == b_variant
+                && a_values.len() == b_values.len()
+                && a_values
+                    .iter()
+                    .zip(b_values)
+                    .all(|(x, y)| same_expression(x, y))
+        }
+        _ => false,
+    }
+}
+
+fn mentions(expr: &Expression, v_name: &str) -> bool {
+    let mut used = HashSet::new();
+    expr.collect_variables(&mut used);
+    used.contains(v_name)
+}
+
+fn assigned_variables(statement: &Statement, assigned: &mut HashSet<String>) {
+    match statement {
+        Statement::AssignmentStatement { target, .. } => {
+            if let Some(v_name) = target.root_variable() {
+                assigned.insert(v_name.to_string());
+            }
+        }
+        Statement::BlockStatement { statements, .. } => {
+            for statement in statements {
+                assigned_variables(statement, assigned);
+            }
+        }
+        Statement::IfStatement {
+            then_block,
+            else_block,
+            ..
+        } => {
+            assigned_variables(then_block, assigned);
+            if let Some(x) = else_block {
+                assigned_variables(x, assigned);
+            }
+        }
+        Statement::WhileStatement { body, .. } => assigned_variables(body, assigned),
+        Statement::MatchStatement { arms, .. } => {
+            for arm in arms {
+                assigned_variables(&arm.body, assigned);
+            }
+        }
+        Statement::DeclarationStatement { .. }
+        | Statement::ConstStatement { .. }
+        | Statement::StructStatement { .. }
+        | Statement::EnumStatement { .. } => {}
+    }
+}
+
+fn literal_condition(expr: &Expression) -> Option<bool> {
+    match &expr.kind {
+        ExpressionKind::ValExpr(x) => match Literal::parse(x) {
+            Some(Literal::Bool(x)) => Some(x),
+            _ => None,
+        },
+        _ => None,
+    }
+}
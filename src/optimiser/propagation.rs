@@ -0,0 +1,360 @@
+/*
+* Constant propagation, copy propagation and common subexpression elimination all work by tracking
+* which variables are known to hold the value of an expression at each point of the program. They
+* only differ in which expressions are tracked and how that knowledge is used:
+* - Constant propagation replaces a variable known to hold a literal with that literal, and then
+*   folds the expression it was used in
+* - Copy propagation replaces a variable known to hold a copy of another variable with the original
+* - Common subexpression elimination replaces an operation with a variable already holding its value
+*
+* Known values are stored in the scope which declares the variable, and any value which mentions a
+* variable is forgotten when that variable is assigned to or shadowed, including when one of its
+* elements is assigned to. At the end of an if statement only the values known after both branches
+* are kept, and likewise after every arm of a match. A loop forgets every variable assigned to in
+* its body before the loop starts, since the body may run any number of times
+*
+* The variables bound by the arms of a match expression could be captured by a substituted value,
+* so only the value matched on is rewritten
+*
+* Values read through a pointer or mentioning a variable which has its address taken are never
+* tracked, so writing through a pointer can't change any value which is known
+*/
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    folding,
+    parser::{Expression, ExpressionKind, Program, Statement},
+    source::SourceMap,
+};
+
+use super::{assigned_variables, mentions, same_expression};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Propagation {
+    Constant,
+    Copy,
+    CommonSubexpression,
+}
+
+type Scope = HashMap<String, Option<Expression>>;
+
+struct Propagator<'a> {
+    kind: Propagation,
+    source_map: &'a SourceMap,
+    scopes: Vec<Scope>,
+    address_taken: HashSet<String>,
+}
+
+pub fn propagate(program: &mut Program, kind: Propagation, source_map: &SourceMap) {
+    let mut propagator = Propagator {
+        kind,
+        source_map,
+        scopes: vec![HashMap::new()],
+        address_taken: program.address_taken_variables(),
+    };
+    propagator.process_statements(&mut program.statements);
+}
+
+impl Propagator<'_> {
+    fn process_statements(&mut self, statements: &mut [Statement]) {
+        for statement in statements {
+            self.process_statement(statement);
+        }
+    }
+
+    fn process_statement(&mut self, statement: &mut Statement) {
+        match statement {
+            Statement::DeclarationStatement { v_name, expr, .. } => {
+                if let Some(x) = expr {
+                    self.rewrite(x);
+                }
+                self.declare(v_name);
+                if let Some(x) = expr {
+                    self.assign(v_name, x);
+                }
+            }
+            Statement::ConstStatement { v_name, .. } => self.declare(v_name),
+            Statement::AssignmentStatement { target, expr, .. } => {
+                self.rewrite_place(target);
+                self.rewrite(expr);
+                match &target.kind {
+                    ExpressionKind::VarExpr(v_name) => self.assign(v_name, expr),
+                    _ => {
+                        if let Some(v_name) = target.root_variable() {
+                            self.forget(v_name);
+                        }
+                    }
+                }
+            }
+            Statement::BlockStatement { statements, .. } => {
+                self.scopes.push(HashMap::new());
+                self.process_statements(statements);
+                self.scopes.pop();
+            }
+            Statement::IfStatement {
+                condition,
+                then_block,
+                else_block,
+                ..
+            } => {
+                self.rewrite(condition);
+                let before = self.scopes.clone();
+                self.process_statement(then_block);
+                let after_then = std::mem::replace(&mut self.scopes, before);
+                if let Some(x) = else_block {
+                    self.process_statement(x);
+                }
+                self.merge(&after_then);
+            }
+            Statement::WhileStatement {
+                condition, body, ..
+            } => {
+                let mut assigned = HashSet::new();
+                assigned_variables(body, &mut assigned);
+                for v_name in &assigned {
+                    self.forget(v_name);
+                }
+
+                self.rewrite(condition);
+                let before = self.scopes.clone();
+                self.process_statement(body);
+                self.scopes = before;
+            }
+            Statement::MatchStatement {
+                scrutinee, arms, ..
+            } => {
+                self.rewrite(scrutinee);
+                let before = self.scopes.clone();
+                let mut after: Option<Vec<Scope>> = None;
+                for arm in arms {
+                    self.scopes = before.clone();
+                    self.scopes.push(HashMap::new());
+                    for (_, v_name, _) in arm.pattern.bindings() {
+                        self.declare(v_name);
+                    }
+                    self.process_statement(&mut arm.body);
+                    self.scopes.pop();
+                    if let Some(x) = &after {
+                        self.merge(x);
+                    }
+                    after = Some(self.scopes.clone());
+                }
+            }
+            Statement::StructStatement { .. } | Statement::EnumStatement { .. } => {}
+        }
+    }
+
+    fn declare(&mut self, v_name: &str) {
+        self.forget_mentions(v_name);
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(v_name.to_string(), None);
+        }
+    }
+
+    fn assign(&mut self, v_name: &str, expr: &Expression) {
+        self.forget(v_name);
+        if !self.is_tracked(expr)
+            || mentions(expr, v_name)
+            || self.may_change_through_pointer(v_name, expr)
+        {
+            return;
+        }
+        if let Some(value) = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(v_name))
+        {
+            *value = Some(expr.clone());
+        }
+    }
+
+    fn forget(&mut self, v_name: &str) {
+        self.forget_mentions(v_name);
+        if let Some(value) = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(v_name))
+        {
+            *value = None;
+        }
+    }
+
+    fn forget_mentions(&mut self, v_name: &str) {
+        for value in self.scopes.iter_mut().flat_map(|scope| scope.values_mut()) {
+            if value.as_ref().is_some_and(|x| mentions(x, v_name)) {
+                *value = None;
+            }
+        }
+    }
+
+    /*
+     * Keeps only the values which are also known in the other set of scopes, which will always
+     * have the same shape since both come from the same point in the program
+     */
+    fn merge(&mut self, other: &[Scope]) {
+        for (scope, other_scope) in self.scopes.iter_mut().zip(other) {
+            for (v_name, value) in scope.iter_mut() {
+                let other_value = other_scope.get(v_name).and_then(|x| x.as_ref());
+                let is_same = match (value.as_ref(), other_value) {
+                    (Some(x), Some(y)) => same_expression(x, y),
+                    _ => false,
+                };
+                if !is_same {
+                    *value = None;
+                }
+            }
+        }
+    }
+
+    fn lookup(&self, v_name: &str) -> Option<&Expression> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(v_name))
+            .and_then(|x| x.as_ref())
+    }
+
+    fn is_tracked(&self, expr: &Expression) -> bool {
+        match self.kind {
+            Propagation::Constant => matches!(expr.kind, ExpressionKind::ValExpr(_)),
+            Propagation::Copy => matches!(expr.kind, ExpressionKind::VarExpr(_)),
+            Propagation::CommonSubexpression => !matches!(
+                expr.kind,
+                ExpressionKind::ValExpr(_) | ExpressionKind::VarExpr(_)
+            ),
+        }
+    }
+
+    fn may_change_through_pointer(&self, v_name: &str, expr: &Expression) -> bool {
+        let mut used = HashSet::new();
+        expr.collect_variables(&mut used);
+        used.insert(v_name.to_string());
+        expr.dereferences() || !used.is_disjoint(&self.address_taken)
+    }
+
+    /*
+     * Folding a propagated constant fails if the result overflows, in that case the expression is
+     * left alone so that it behaves the same as it would without optimisation
+     */
+    fn rewrite(&self, expr: &mut Expression) {
+        match self.kind {
+            Propagation::Constant => {
+                let original = expr.clone();
+                self.substitute_variables(expr);
+                if folding::fold_expression(expr, self.source_map).is_err() {
+                    *expr = original;
+                }
+            }
+            Propagation::Copy => self.substitute_variables(expr),
+            Propagation::CommonSubexpression => self.substitute_subexpressions(expr),
+        }
+    }
+
+    /*
+     * Only the indices of a place expression and the pointer it is reached through are rewritten,
+     * the variable it refers to stays
+     */
+    fn rewrite_place(&self, place: &mut Expression) {
+        match &mut place.kind {
+            ExpressionKind::IndexExpr(x, index) => {
+                self.rewrite_place(x);
+                self.rewrite(index);
+            }
+            ExpressionKind::FieldExpr(x, _) if x.root_variable().is_none() => self.rewrite(x),
+            ExpressionKind::FieldExpr(x, _) => self.rewrite_place(x),
+            ExpressionKind::Deref(x) => self.rewrite(x),
+            _ => {}
+        }
+    }
+
+    fn substitute_variables(&self, expr: &mut Expression) {
+        match &mut expr.kind {
+            ExpressionKind::ValExpr(_) => {}
+            ExpressionKind::VarExpr(v_name) => {
+                if let Some(value) = self.lookup(v_name) {
+                    expr.kind = value.kind.clone();
+                }
+            }
+            ExpressionKind::ArithmeticExpr(x, _, y) | ExpressionKind::BooleanExpr(x, _, y) => {
+                self.substitute_variables(x);
+                self.substitute_variables(y);
+            }
+            ExpressionKind::CastExpr(x, _)
+            | ExpressionKind::FieldExpr(x, _)
+            | ExpressionKind::AddressOf(x)
+            | ExpressionKind::Deref(x) => self.substitute_variables(x),
+            ExpressionKind::IndexExpr(x, index) => {
+                self.substitute_variables(x);
+                self.substitute_variables(index);
+            }
+            ExpressionKind::StructLiteral(_, fields) => {
+                for (_, value) in fields {
+                    self.substitute_variables(value);
+                }
+            }
+            ExpressionKind::VariantLiteral(_, _, values) => {
+                for value in values {
+                    self.substitute_variables(value);
+                }
+            }
+            ExpressionKind::MatchExpr(scrutinee, _) => self.substitute_variables(scrutinee),
+        }
+    }
+
+    fn substitute_subexpressions(&self, expr: &mut Expression) {
+        if !self.is_tracked(expr) {
+            return;
+        }
+        let holder = self.scopes.iter().rev().find_map(|scope| {
+            scope.iter().find_map(|(v_name, value)| match value {
+                Some(x) if same_expression(x, expr) && self.resolves_to(v_name, scope) => {
+                    Some(v_name.clone())
+                }
+                _ => None,
+            })
+        });
+
+        match (holder, &mut expr.kind) {
+            (Some(v_name), _) => expr.kind = ExpressionKind::VarExpr(v_name),
+            (None, ExpressionKind::ArithmeticExpr(x, _, y))
+            | (None, ExpressionKind::BooleanExpr(x, _, y)) => {
+                self.substitute_subexpressions(x);
+                self.substitute_subexpressions(y);
+            }
+            (None, ExpressionKind::CastExpr(x, _))
+            | (None, ExpressionKind::AddressOf(x))
+            | (None, ExpressionKind::Deref(x)) => self.substitute_subexpressions(x),
+            (None, ExpressionKind::IndexExpr(_, index)) => self.substitute_subexpressions(index),
+            (None, ExpressionKind::FieldExpr(x, _)) => self.substitute_subexpressions(x),
+            (None, ExpressionKind::StructLiteral(_, fields)) => {
+                for (_, value) in fields {
+                    self.substitute_subexpressions(value);
+                }
+            }
+            (None, ExpressionKind::VariantLiteral(_, _, values)) => {
+                for value in values {
+                    self.substitute_subexpressions(value);
+                }
+            }
+            (None, ExpressionKind::MatchExpr(scrutinee, _)) => {
+                self.substitute_subexpressions(scrutinee)
+            }
+            (None, _) => {}
+        }
+    }
+
+    /*
+     * Whether the name refers to the variable declared in the given scope, rather than one which
+     * shadows it in an inner scope
+     */
+    fn resolves_to(&self, v_name: &str, scope: &Scope) -> bool {
+        self.scopes
+            .iter()
+            .rev()
+            .find(|x| x.contains_key(v_name))
+            .is_some_and(|x| std::ptr::eq(x, scope))
+    }
+}
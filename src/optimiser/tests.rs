@@ -0,0 +1,272 @@
+use std::str::FromStr;
+
+use super::*;
+use crate::test_utils;
+
+fn optimise_text(contents: &str, passes: &[Pass]) -> Program {
+    let (mut program, source_map) = test_utils::fold_text(contents);
+
+    let mut options = OptimiserOptions::new(OptLevel::O0);
+    for pass in passes {
+        options.enable(*pass);
+    }
+    optimise(&mut program, &options, &source_map);
+    program
+}
+
+fn assert_program(program: &Program, expected: &[&str]) {
+    let statements: Vec<String> = program.statements.iter().map(|x| x.to_string()).collect();
+    assert_eq!(statements, expected);
+}
+
+#[test]
+fn test_passes_for_optimisation_levels() {
+    assert_eq!(OptimiserOptions::new(OptLevel::O0).passes(), vec![]);
+    assert_eq!(
+        OptimiserOptions::new(OptLevel::O1).passes(),
+        vec![
+            Pass::ConstantPropagation,
+            Pass::CopyPropagation,
+            Pass::UnreachableCodeRemoval
+        ]
+    );
+    assert_eq!(OptimiserOptions::new(OptLevel::O2).passes(), Pass::ALL);
+}
+
+#[test]
+fn test_passes_can_be_toggled() {
+    let mut options = OptimiserOptions::new(OptLevel::O1);
+    options.disable(Pass::CopyPropagation);
+    options.enable(Pass::DeadStoreElimination);
+    options.enable(Pass::CommonSubexpressionElimination);
+    options.disable(Pass::CommonSubexpressionElimination);
+    assert_eq!(
+        options.passes(),
+        vec![
+            Pass::ConstantPropagation,
+            Pass::UnreachableCodeRemoval,
+            Pass::DeadStoreElimination
+        ]
+    );
+}
+
+#[test]
+fn test_parse_pass_names_and_levels() {
+    for pass in Pass::ALL {
+        assert_eq!(Pass::from_str(&pass.to_string()).unwrap(), pass);
+    }
+    assert!(Pass::from_str("inline").is_err());
+    assert_eq!(OptLevel::from_str("2").unwrap(), OptLevel::O2);
+    assert!(OptLevel::from_str("3").is_err());
+}
+
+#[test]
+fn test_constant_propagation() {
+    let program = optimise_text(
+        "int x = 10;\nmut int y = x + 9 - 5;\ny = y * 2;\nbool z = y > x;",
+        &[Pass::ConstantPropagation],
+    );
+    assert_program(
+        &program,
+        &[
+            "Declaring x with value 10",
+            "Declaring mutable y with value 14",
+            "Assigning y to value 28",
+            "Declaring z with value true",
+        ],
+    );
+}
+
+#[test]
+fn test_constant_propagation_forgets_values_assigned_in_loops() {
+    let program = optimise_text(
+        "mut int i = 0;\nwhile i < 10 {\n  i = i + 1;\n}\nint j = i;",
+        &[Pass::ConstantPropagation],
+    );
+    assert_program(
+        &program,
+        &[
+            "Declaring mutable i with value 0",
+            "While i < 10:\nBlock:\nAssigning i to value i + 1\nEnd Block",
+            "Declaring j with value i",
+        ],
+    );
+}
+
+#[test]
+fn test_constant_propagation_merges_branches() {
+    let program = optimise_text(
+        "mut int i = 0;\nwhile i < 3 {\n  i = i + 1;\n}\nmut int x = 1;\nmut int y = 1;\nif i > 1 {\n  x = 2;\n  y = 2;\n} else {\n  y = 2;\n}\nint a = x;\nint b = y;",
+        &[Pass::ConstantPropagation],
+    );
+    assert_eq!(
+        program.statements[5].to_string(),
+        "Declaring a with value x"
+    );
+    assert_eq!(
+        program.statements[6].to_string(),
+        "Declaring b with value 2"
+    );
+}
+
+#[test]
+fn test_constant_propagation_leaves_overflow_to_runtime() {
+    let program = optimise_text("mut u8 x = 255;\nx = x + 1;", &[Pass::ConstantPropagation]);
+    assert_program(
+        &program,
+        &[
+            "Declaring mutable x with value 255",
+            "Assigning x to value x + 1",
+        ],
+    );
+}
+
+#[test]
+fn test_copy_propagation() {
+    let program = optimise_text(
+        "int i = 5;\nint a = i;\nint b = a + a;\n{\n  int i = 6;\n  int c = a;\n}",
+        &[Pass::CopyPropagation],
+    );
+    assert_program(
+        &program,
+        &[
+            "Declaring i with value 5",
+            "Declaring a with value i",
+            "Declaring b with value i + i",
+            "Block:\nDeclaring i with value 6\nDeclaring c with value a\nEnd Block",
+        ],
+    );
+}
+
+#[test]
+fn test_common_subexpression_elimination() {
+    let program = optimise_text(
+        "mut int i = 5;\nint a = i * 2;\nint b = 1 + i * 2;\ni = 3;\nint c = i * 2;",
+        &[Pass::CommonSubexpressionElimination],
+    );
+    assert_program(
+        &program,
+        &[
+            "Declaring mutable i with value 5",
+            "Declaring a with value i * 2",
+            "Declaring b with value 1 + a",
+            "Assigning i to value 3",
+            "Declaring c with value i * 2",
+        ],
+    );
+}
+
+#[test]
+fn test_unreachable_code_removal() {
+    let program = optimise_text(
+        "mut int x = 1;\nif true {\n  x = 2;\n} else {\n  x = 3;\n}\nif false {\n  x = 4;\n}\nwhile false {\n  x = 5;\n}\nwhile true {\n  x = 6;\n}\nx = 7;",
+        &[Pass::UnreachableCodeRemoval],
+    );
+    assert_program(
+        &program,
+        &[
+            "Declaring mutable x with value 1",
+            "Block:\nAssigning x to value 2\nEnd Block",
+            "While true:\nBlock:\nAssigning x to value 6\nEnd Block",
+        ],
+    );
+}
+
+#[test]
+fn test_dead_store_elimination() {
+    let program = optimise_text(
+        "mut int x = 1;\nx = 2;\n{\n  int unused = x;\n  mut int y = x;\n  y = 3;\n  x = y;\n}\nx = x + 1;",
+        &[Pass::DeadStoreElimination],
+    );
+    assert_program(
+        &program,
+        &[
+            "Declaring mutable x with value 1",
+            "Assigning x to value 2",
+            "Block:\nDeclaring mutable y with value x\nAssigning y to value 3\nAssigning x to value y\nEnd Block",
+            "Assigning x to value x + 1",
+        ],
+    );
+}
+
+#[test]
+fn test_dead_store_elimination_keeps_values_used_by_later_iterations() {
+    let program = optimise_text(
+        "mut int i = 0;\n{\n  mut int total = 0;\n  mut int last = 0;\n  while i < 10 {\n    total = total + i;\n    last = i;\n    i = i + 1;\n  }\n}",
+        &[Pass::DeadStoreElimination],
+    );
+    assert_program(
+        &program,
+        &[
+            "Declaring mutable i with value 0",
+            "Block:\nWhile i < 10:\nBlock:\nAssigning i to value i + 1\nEnd Block\nEnd Block",
+        ],
+    );
+}
+
+#[test]
+fn test_array_element_stores() {
+    let program = optimise_text(
+        "mut i64[4] a;\nmut int i = 2;\na[i] = 4;\nint x = a[i] + 1;\na[i] = 10;\nint y = a[i] + 1;\n{\n  mut i64[2] b;\n  b[0] = x;\n}",
+        &[
+            Pass::ConstantPropagation,
+            Pass::CommonSubexpressionElimination,
+            Pass::DeadStoreElimination,
+        ],
+    );
+    assert_program(
+        &program,
+        &[
+            "Declaring mutable a",
+            "Declaring mutable i with value 2",
+            "Assigning a[2] to value 4",
+            "Declaring x with value a[2] + 1",
+            "Assigning a[2] to value 10",
+            "Declaring y with value a[2] + 1",
+            "Block:\nEnd Block",
+        ],
+    );
+}
+
+#[test]
+fn test_variables_with_their_address_taken() {
+    let program = optimise_text(
+        "mut int a = 1;\n*int p = &a;\n*p = 2;\nint b = a + 1;\na = 3;\nint c = *p;",
+        &[
+            Pass::ConstantPropagation,
+            Pass::CommonSubexpressionElimination,
+            Pass::DeadStoreElimination,
+        ],
+    );
+    assert_program(
+        &program,
+        &[
+            "Declaring mutable a with value 1",
+            "Declaring p with value &a",
+            "Assigning *p to value 2",
+            "Declaring b with value a + 1",
+            "Assigning a to value 3",
+            "Declaring c with value *p",
+        ],
+    );
+}
+
+#[test]
+fn test_match_arms() {
+    let program = optimise_text(
+        "enum Opt { Some(int); None; }\nOpt o = Opt::Some(3);\nint x = 1;\nmut int y;\nmatch o {\n  Opt::Some(x) => { y = x; }\n  Opt::None => { y = x; }\n}\nint a = x;\nint b = y;",
+        &[Pass::ConstantPropagation, Pass::DeadStoreElimination],
+    );
+    assert_program(
+        &program,
+        &[
+            "Defining enum Opt with variants Some(i64), None",
+            "Declaring o with value Opt::Some(3)",
+            "Declaring x with value 1",
+            "Declaring mutable y",
+            "Match o:\nOpt::Some(x) => Block:\nAssigning y to value x\nEnd Block\nOpt::None => Block:\nAssigning y to value 1\nEnd Block",
+            "Declaring a with value 1",
+            "Declaring b with value y",
+        ],
+    );
+}
@@ -0,0 +1,107 @@
+/*
+* Removes code which can never run. Branches of an if statement with a literal condition that can't
+* be taken are removed, as are loops whose condition is false. A loop whose condition is true never
+* finishes, so anything after it is also removed, except for struct and enum definitions since a
+* type can be used before it is defined. A match can only finish if one of its arms can
+*/
+
+use crate::parser::{Program, Statement};
+
+use super::literal_condition;
+
+pub fn remove_unreachable_code(program: &mut Program) {
+    process_statements(&mut program.statements);
+}
+
+/*
+* Returns true if the end of the statements can never be reached
+*/
+fn process_statements(statements: &mut Vec<Statement>) -> bool {
+    let mut diverges = false;
+    let mut idx = 0;
+    while idx < statements.len() {
+        if process_statement(&mut statements[idx]) {
+            let rest = statements.split_off(idx + 1);
+            statements.extend(rest.into_iter().filter(|x| {
+                matches!(
+                    x,
+                    Statement::StructStatement { .. } | Statement::EnumStatement { .. }
+                )
+            }));
+            diverges = true;
+        }
+        idx += 1;
+    }
+    statements.retain(|x| !is_empty_block(x));
+    diverges
+}
+
+fn process_statement(statement: &mut Statement) -> bool {
+    match statement {
+        Statement::BlockStatement { statements, .. } => process_statements(statements),
+        Statement::IfStatement {
+            condition,
+            then_block,
+            else_block,
+            span,
+        } => match literal_condition(condition) {
+            Some(true) => {
+                *statement = std::mem::replace(then_block.as_mut(), empty_block(*span));
+                process_statement(statement)
+            }
+            Some(false) => {
+                *statement = match else_block.take() {
+                    Some(x) => *x,
+                    None => empty_block(*span),
+                };
+                process_statement(statement)
+            }
+            None => {
+                let then_diverges = process_statement(then_block);
+                let else_diverges = else_block.as_mut().is_some_and(|x| process_statement(x));
+                then_diverges && else_diverges
+            }
+        },
+        Statement::WhileStatement {
+            condition,
+            body,
+            span,
+        } => match literal_condition(condition) {
+            Some(false) => {
+                *statement = empty_block(*span);
+                false
+            }
+            Some(true) => {
+                process_statement(body);
+                true
+            }
+            None => {
+                process_statement(body);
+                false
+            }
+        },
+        Statement::MatchStatement { arms, .. } => {
+            let mut diverges = true;
+            for arm in arms {
+                diverges &= process_statement(&mut arm.body);
+            }
+            diverges
+        }
+        Statement::DeclarationStatement { .. }
+        | Statement::ConstStatement { .. }
+        | Statement::AssignmentStatement { .. }
+        | Statement::StructStatement { .. }
+        | Statement::EnumStatement { .. } => false,
+    }
+}
+
+fn empty_block(span: crate::source::Span) -> Statement {
+    Statement::BlockStatement {
+        statements: Vec::new(),
+        span,
+    }
+}
+
+fn is_empty_block(statement: &Statement) -> bool {
+    matches!(statement, Statement::BlockStatement { statements, .. } if statements.is_empty())
+}
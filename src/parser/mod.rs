@@ -5,16 +5,46 @@
 *
 * Terminal symbols (tokens) are provided in all upper case, anything else is a non-terminal
 *
-* Program = { Statement, SEMI }
-* Statement = DeclarationStatement | AssignmentStatement
-* DeclarationStatement = INT, VARIABLE, EQ, ArithmeticExpression
-* AssignmentStatement = VARIABLE, EQ, ArithmeticExpression
-* Expression = ValExpr | VarExpr | ArithmeticExpr | BooleanExpr
+* Module = { [ PUB ], (Statement | StructStatement | EnumStatement) | ImportStatement }
+* ImportStatement = IMPORT, Path, SEMI
+* Path = VARIABLE, { DOUBLECOLON, VARIABLE }
+* Statement = (DeclarationStatement | ConstStatement | AssignmentStatement), SEMI | BlockStatement
+*     | IfStatement | WhileStatement | MatchStatement
+* StructStatement = STRUCT, VARIABLE, LBRACE, { Type, VARIABLE, SEMI }, RBRACE
+* EnumStatement = ENUM, VARIABLE, LBRACE, { VARIABLE, [ LPAREN, Type, { COMMA, Type }, RPAREN ], SEMI },
+*     RBRACE
+* BlockStatement = LBRACE, { Statement }, RBRACE
+* IfStatement = IF, Expression, BlockStatement, [ ELSE, (IfStatement | BlockStatement) ]
+* WhileStatement = WHILE, Expression, BlockStatement
+* MatchStatement = MATCH, Expression, LBRACE, { Pattern, ARROW, BlockStatement }, RBRACE
+* DeclarationStatement = [ MUT ], Type, VARIABLE, [ EQ, Expression ]
+* ConstStatement = CONST, Type, VARIABLE, EQ, Expression
+* AssignmentStatement = PlaceExpr, EQ, Expression
+* Type = { * }, (TYPE | Path), { LBRACKET, VALUE, RBRACKET }
+* Expression = BooleanExpr | ArithmeticExpr | TermExpr | CastExpr
+* BooleanExpr = ArithmeticExpr, BooleanOperator, Expression
+* ArithmeticExpr = TermExpr, AdditiveOperator, TermExpr, { AdditiveOperator, TermExpr }
+* TermExpr = CastExpr, MultiplicativeOperator, CastExpr, { MultiplicativeOperator, CastExpr }
+* CastExpr = PrimaryExpr, { AS, TYPE }
+* PrimaryExpr = ValExpr | PlaceExpr | StructLiteral | VariantLiteral | MatchExpr | AddressOfExpr
+* PlaceExpr = VarExpr | PathExpr | IndexExpr | FieldExpr | DerefExpr
+* IndexExpr = PlaceExpr, LBRACKET, Expression, RBRACKET
+* FieldExpr = PlaceExpr, DOT, VARIABLE
+* DerefExpr = *, PrimaryExpr
+* AddressOfExpr = AMPERSAND, PrimaryExpr
+* StructLiteral = Path, LBRACE, [ FieldValue, { COMMA, FieldValue }, [ COMMA ] ], RBRACE
+* FieldValue = VARIABLE, COLON, Expression
+* VariantLiteral = Path, DOUBLECOLON, VARIABLE, [ LPAREN, Expression, { COMMA, Expression }, RPAREN ]
+* MatchExpr = MATCH, Expression, LBRACE, [ MatchArm, { COMMA, MatchArm }, [ COMMA ] ], RBRACE
+* MatchArm = Pattern, ARROW, Expression
+* Pattern = Path, DOUBLECOLON, VARIABLE, [ LPAREN, VARIABLE, { COMMA, VARIABLE }, RPAREN ] | WILDCARD
+* WILDCARD = _
 * ValExpr = VALUE
 * VarExpr = VARIABLE
-* ArithmeticExpr = Expression ArithmeticOperator Expression
-* BooleanExpr = Expression BooleanExpr Expression
-* ArithmeticOperator = + | -
+* PathExpr = VARIABLE, DOUBLECOLON, Path
+* AdditiveOperator = + | - | +% | -% | +| | -|
+* MultiplicativeOperator = * | / | *% | *|
+* BooleanOperator = < | > | <= | >= | == | !=
 *
 *
 */
@@ -27,14 +57,15 @@
 #[cfg(test)]
 mod tests;
 
-use std::collections::HashMap;
+use std::collections::HashSet;
 
 use crate::{
-    constants,
-    token::{Token, TokenType},
+    source::{Located, Span},
+    token::{TextLocation, Token, TokenType},
 };
 
-const MIN_DECLARATION_LENGTH: usize = 4;
+const MIN_DECLARATION_LENGTH: usize = 2;
+const MIN_CONST_LENGTH: usize = 5;
 const MIN_ASSIGNMENT_LENGTH: usize = 3;
 
 #[derive(Debug)]
@@ -43,14 +74,41 @@ pub enum ParseError {
     MissingSemicolon(Token),
     EmptyStatement(Token),
     UnexpectedToken(Token, TokenType),
-    RedeclaringVariable(Token),
-    UndefinedVariable(Token),
+    UnclosedBlock(Token),
+    UnmatchedBrace(Token),
+    MissingBlock(Token),
     InvalidExpression(Token),
     InvalidOperator(Token),
+    InvalidArrayLength(Token),
+    InvalidAssignmentTarget(Token),
+    InvalidPattern(Token),
+    NestedDefinition(Token),
+    MissingType,
 }
 
 impl std::error::Error for ParseError {}
 
+impl Located for ParseError {
+    fn location_mut(&mut self) -> Option<&mut TextLocation> {
+        match self {
+            ParseError::InvalidStatement(x)
+            | ParseError::MissingSemicolon(x)
+            | ParseError::EmptyStatement(x)
+            | ParseError::UnexpectedToken(x, _)
+            | ParseError::UnclosedBlock(x)
+            | ParseError::UnmatchedBrace(x)
+            | ParseError::MissingBlock(x)
+            | ParseError::InvalidExpression(x)
+            | ParseError::InvalidOperator(x)
+            | ParseError::InvalidArrayLength(x)
+            | ParseError::InvalidAssignmentTarget(x)
+            | ParseError::InvalidPattern(x)
+            | ParseError::NestedDefinition(x) => Some(&mut x.location),
+            ParseError::MissingType => None,
+        }
+    }
+}
+
 impl std::fmt::Display for ParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -70,11 +128,18 @@ impl std::fmt::Display for ParseError {
             ParseError::MissingSemicolon(x) => {
                 write!(f, "No semicolon found after statement starting with: {}", x)
             }
-            ParseError::RedeclaringVariable(x) => {
-                write!(f, "Attempted to redeclare variable: {}", x)
+            ParseError::UnclosedBlock(x) => {
+                write!(f, "No closing brace found for block starting at: {}", x)
             }
-            ParseError::UndefinedVariable(x) => {
-                write!(f, "Undefined variable: {}", x)
+            ParseError::UnmatchedBrace(x) => {
+                write!(
+                    f,
+                    "Found closing brace without a matching opening brace: {}",
+                    x
+                )
+            }
+            ParseError::MissingBlock(x) => {
+                write!(f, "No block found after: {}", x)
             }
             ParseError::InvalidExpression(x) => {
                 write!(f, "Unable to parse expression starting from token {}", x)
@@ -82,6 +147,39 @@ impl std::fmt::Display for ParseError {
             ParseError::InvalidOperator(x) => {
                 write!(f, "Invalid Operator: {}", x)
             }
+            ParseError::InvalidArrayLength(x) => {
+                write!(
+                    f,
+                    "Invalid array length: {}, expected a positive integer",
+                    x
+                )
+            }
+            ParseError::NestedDefinition(x) => {
+                let kind = match x.t_type {
+                    TokenType::Enum => "Enums",
+                    TokenType::Import => "Imports",
+                    TokenType::Pub => "Public items",
+                    _ => "Structs",
+                };
+                write!(
+                    f,
+                    "{} can only be defined at the top level of a program, found {}",
+                    kind, x
+                )
+            }
+            ParseError::InvalidPattern(x) => {
+                write!(f, "Unable to parse pattern starting from token {}", x)
+            }
+            ParseError::MissingType => {
+                write!(f, "Expected a type but found nothing")
+            }
+            ParseError::InvalidAssignmentTarget(x) => {
+                write!(
+                    f,
+                    "Unable to assign to expression starting from token {}",
+                    x
+                )
+            }
         }
     }
 }
@@ -91,6 +189,48 @@ pub struct Program {
     pub statements: Vec<Statement>,
 }
 
+impl Program {
+    /*
+     * The variables which have their address taken anywhere in the program. Any of them may be read
+     * or written through a pointer at any point, so no analysis can assume it knows the value of one
+     * or that a store to one is never read
+     */
+    pub fn address_taken_variables(&self) -> HashSet<String> {
+        let mut address_taken = HashSet::new();
+        for statement in &self.statements {
+            statement.collect_address_taken(&mut address_taken);
+        }
+        address_taken
+    }
+}
+
+/*
+* A source file is parsed as a module, which is its program along with the modules it imports and
+* the names of the top level items it makes public with pub
+*/
+#[derive(Debug)]
+pub struct Module {
+    pub program: Program,
+    pub imports: Vec<Import>,
+    pub public: HashSet<String>,
+}
+
+/*
+* An import names a module by its path from the directory of the importing file, e.g. import
+* shapes::circle; is the file shapes/circle.mgs and its items are then used as circle::item
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Import {
+    pub path: Vec<String>,
+    pub span: Span,
+}
+
+impl Import {
+    pub fn name(&self) -> &str {
+        self.path.last().map(|x| x.as_str()).unwrap_or_default()
+    }
+}
+
 impl std::fmt::Display for Program {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Program: ")?;
@@ -101,12 +241,35 @@ impl std::fmt::Display for Program {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/*
+* Integer arithmetic that overflows its type is an error, unless the operator explicitly wraps around
+* (e.g. +%) or saturates at the minimum or maximum value of the type (e.g. +|)
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowMode {
+    Checked,
+    Wrapping,
+    Saturating,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Operator {
     Add,
     Sub,
+    Mul,
+    Div,
+    WrappingAdd,
+    WrappingSub,
+    WrappingMul,
+    SaturatingAdd,
+    SaturatingSub,
+    SaturatingMul,
     LessThan,
     GreaterThan,
+    LessThanEq,
+    GreaterThanEq,
+    Equal,
+    NotEqual,
 }
 
 impl Operator {
@@ -114,20 +277,95 @@ impl Operator {
         match t.value.as_str() {
             "+" => Ok(Operator::Add),
             "-" => Ok(Operator::Sub),
+            "*" => Ok(Operator::Mul),
+            "/" => Ok(Operator::Div),
+            "+%" => Ok(Operator::WrappingAdd),
+            "-%" => Ok(Operator::WrappingSub),
+            "*%" => Ok(Operator::WrappingMul),
+            "+|" => Ok(Operator::SaturatingAdd),
+            "-|" => Ok(Operator::SaturatingSub),
+            "*|" => Ok(Operator::SaturatingMul),
             "<" => Ok(Operator::LessThan),
             ">" => Ok(Operator::GreaterThan),
+            "<=" => Ok(Operator::LessThanEq),
+            ">=" => Ok(Operator::GreaterThanEq),
+            "==" => Ok(Operator::Equal),
+            "!=" => Ok(Operator::NotEqual),
             _ => Err(ParseError::InvalidOperator(t.clone())),
         }
     }
 
-    pub fn to_arm_command(&self) -> String {
+    pub fn overflow_mode(self) -> OverflowMode {
+        match self {
+            Operator::WrappingAdd | Operator::WrappingSub | Operator::WrappingMul => {
+                OverflowMode::Wrapping
+            }
+            Operator::SaturatingAdd | Operator::SaturatingSub | Operator::SaturatingMul => {
+                OverflowMode::Saturating
+            }
+            _ => OverflowMode::Checked,
+        }
+    }
+
+    /*
+     * The plain arithmetic operator computing the same result when there is no overflow
+     */
+    pub fn base_operator(self) -> Operator {
         match self {
-            Operator::Add => String::from("add"),
-            Operator::Sub => String::from("sub"),
-            Operator::LessThan => String::from("LESSTHAN"),
-            Operator::GreaterThan => String::from("GREATERTHAN"),
+            Operator::WrappingAdd | Operator::SaturatingAdd => Operator::Add,
+            Operator::WrappingSub | Operator::SaturatingSub => Operator::Sub,
+            Operator::WrappingMul | Operator::SaturatingMul => Operator::Mul,
+            x => x,
         }
     }
+
+    pub fn to_arm_command(self, ty: &Type) -> String {
+        let command = match (self.base_operator(), ty) {
+            (Operator::Add, x) if x.is_float() => "fadd",
+            (Operator::Sub, x) if x.is_float() => "fsub",
+            (Operator::Mul, x) if x.is_float() => "fmul",
+            (Operator::Div, x) if x.is_float() => "fdiv",
+            (Operator::Add, _) => "add",
+            (Operator::Sub, _) => "sub",
+            (Operator::Mul, _) => "mul",
+            (Operator::Div, x) if x.is_signed() => "sdiv",
+            (Operator::Div, _) => "udiv",
+            (_, x) if x.is_float() => "fcmp",
+            _ => "cmp",
+        };
+        String::from(command)
+    }
+
+    /*
+     * The condition codes used for floats are chosen so that comparisons involving NaN are false,
+     * with the exception of !=
+     */
+    pub fn to_arm_condition(self, ty: &Type) -> Option<String> {
+        let condition = match self {
+            Operator::LessThan if ty.is_float() => "mi",
+            Operator::LessThan if ty.is_signed() => "lt",
+            Operator::LessThan => "lo",
+            Operator::GreaterThan if ty.is_float() || ty.is_signed() => "gt",
+            Operator::GreaterThan => "hi",
+            Operator::LessThanEq if ty.is_signed() => "le",
+            Operator::LessThanEq => "ls",
+            Operator::GreaterThanEq if ty.is_float() || ty.is_signed() => "ge",
+            Operator::GreaterThanEq => "hs",
+            Operator::Equal => "eq",
+            Operator::NotEqual => "ne",
+            Operator::Add
+            | Operator::Sub
+            | Operator::Mul
+            | Operator::Div
+            | Operator::WrappingAdd
+            | Operator::WrappingSub
+            | Operator::WrappingMul
+            | Operator::SaturatingAdd
+            | Operator::SaturatingSub
+            | Operator::SaturatingMul => return None,
+        };
+        Some(String::from(condition))
+    }
 }
 
 impl std::fmt::Display for Operator {
@@ -135,136 +373,1374 @@ impl std::fmt::Display for Operator {
         match self {
             Operator::Add => write!(f, "+"),
             Operator::Sub => write!(f, "-"),
+            Operator::Mul => write!(f, "*"),
+            Operator::Div => write!(f, "/"),
+            Operator::WrappingAdd => write!(f, "+%"),
+            Operator::WrappingSub => write!(f, "-%"),
+            Operator::WrappingMul => write!(f, "*%"),
+            Operator::SaturatingAdd => write!(f, "+|"),
+            Operator::SaturatingSub => write!(f, "-|"),
+            Operator::SaturatingMul => write!(f, "*|"),
             Operator::LessThan => write!(f, "<"),
             Operator::GreaterThan => write!(f, ">"),
+            Operator::LessThanEq => write!(f, "<="),
+            Operator::GreaterThanEq => write!(f, ">="),
+            Operator::Equal => write!(f, "=="),
+            Operator::NotEqual => write!(f, "!="),
+        }
+    }
+}
+
+/*
+* An array type holds its element type and length, a multi-dimensional array is an array whose
+* elements are themselves arrays. A struct or enum type is only its name, its fields or variants are
+* found from the statement defining it. The parser can't tell which of the two a name refers to, so
+* it always gives a struct type which semantic analysis changes to an enum type where needed. A
+* pointer type holds the type of the value it points to
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Type {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+    Bool,
+    Char,
+    Array(Box<Type>, usize),
+    Struct(String),
+    Enum(String),
+    Pointer(Box<Type>),
+}
+
+impl Type {
+    fn from_token(t: &Token) -> Result<Self, ParseError> {
+        match (t.t_type, t.value.as_str()) {
+            (TokenType::Type, "i8") => Ok(Type::I8),
+            (TokenType::Type, "i16") => Ok(Type::I16),
+            (TokenType::Type, "i32") => Ok(Type::I32),
+            (TokenType::Type, "i64" | "int") => Ok(Type::I64),
+            (TokenType::Type, "u8") => Ok(Type::U8),
+            (TokenType::Type, "u16") => Ok(Type::U16),
+            (TokenType::Type, "u32") => Ok(Type::U32),
+            (TokenType::Type, "u64") => Ok(Type::U64),
+            (TokenType::Type, "f32") => Ok(Type::F32),
+            (TokenType::Type, "f64") => Ok(Type::F64),
+            (TokenType::Type, "bool") => Ok(Type::Bool),
+            (TokenType::Type, "char") => Ok(Type::Char),
+            _ => Err(ParseError::UnexpectedToken(t.clone(), TokenType::Type)),
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.is_signed() || matches!(self, Type::U8 | Type::U16 | Type::U32 | Type::U64)
+    }
+
+    pub fn is_signed(&self) -> bool {
+        matches!(self, Type::I8 | Type::I16 | Type::I32 | Type::I64)
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Type::F32 | Type::F64)
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || self.is_float()
+    }
+
+    pub fn is_aggregate(&self) -> bool {
+        matches!(self, Type::Array(..) | Type::Struct(_) | Type::Enum(_))
+    }
+
+    /*
+     * The size in bytes of a primitive type, arrays, structs and enums have no size here since it
+     * depends on the layout of the structs and enums they contain
+     */
+    pub fn size(&self) -> Option<usize> {
+        match self {
+            Type::I8 | Type::U8 | Type::Bool => Some(1),
+            Type::I16 | Type::U16 => Some(2),
+            Type::I32 | Type::U32 | Type::F32 | Type::Char => Some(4),
+            Type::I64 | Type::U64 | Type::F64 | Type::Pointer(_) => Some(8),
+            Type::Array(..) | Type::Struct(_) | Type::Enum(_) => None,
+        }
+    }
+
+    /*
+     * The struct whose fields are accessed with a dot, which can be used on a struct or directly on
+     * a pointer to one
+     */
+    pub fn struct_name(&self) -> Option<&str> {
+        match self {
+            Type::Struct(x) => Some(x),
+            Type::Pointer(x) => match x.as_ref() {
+                Type::Struct(x) => Some(x),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn int_range(&self) -> Option<(i128, i128)> {
+        if !self.is_integer() {
+            return None;
+        }
+        let bits = self.size()? as u32 * 8;
+        if self.is_signed() {
+            Some((-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1))
+        } else {
+            Some((0, (1i128 << bits) - 1))
+        }
+    }
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::I8 => write!(f, "i8"),
+            Type::I16 => write!(f, "i16"),
+            Type::I32 => write!(f, "i32"),
+            Type::I64 => write!(f, "i64"),
+            Type::U8 => write!(f, "u8"),
+            Type::U16 => write!(f, "u16"),
+            Type::U32 => write!(f, "u32"),
+            Type::U64 => write!(f, "u64"),
+            Type::F32 => write!(f, "f32"),
+            Type::F64 => write!(f, "f64"),
+            Type::Bool => write!(f, "bool"),
+            Type::Char => write!(f, "char"),
+            Type::Struct(x) | Type::Enum(x) => write!(f, "{}", x),
+            Type::Pointer(x) => write!(f, "*{}", x),
+            Type::Array(..) => {
+                let mut elem = self;
+                let mut lengths = Vec::new();
+                while let Type::Array(x, len) = elem {
+                    lengths.push(len);
+                    elem = x;
+                }
+                write!(f, "{}", elem)?;
+                for len in lengths {
+                    write!(f, "[{}]", len)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/*
+* Integer literals are held as i128 so that every value of every integer type can be represented,
+* the source only contains non-negative integers but negative values can be produced by folding.
+* Integers outside of the range of all of the integer types are rejected when parsed
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Int(i128),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+    Str(String),
+    Null,
+}
+
+impl Literal {
+    /*
+     * Values which the lexer accepted but that cannot be represented, such as integers too large
+     * for any type or invalid character escapes, will return None
+     */
+    pub fn parse(value: &str) -> Option<Literal> {
+        match value {
+            "true" => Some(Literal::Bool(true)),
+            "false" => Some(Literal::Bool(false)),
+            "null" => Some(Literal::Null),
+            x if x.starts_with('"') => Some(Literal::Str(x[1..x.len() - 1].to_string())),
+            x if x.starts_with('\'') => Literal::parse_char(&x[1..x.len() - 1]),
+            x if is_integer_literal(x) => x
+                .parse::<i128>()
+                .ok()
+                .filter(|x| (i64::MIN as i128..=u64::MAX as i128).contains(x))
+                .map(Literal::Int),
+            x => x.parse::<f64>().ok().map(Literal::Float),
+        }
+    }
+
+    fn parse_char(value: &str) -> Option<Literal> {
+        let mut chars = value.chars();
+        let c = match chars.next()? {
+            '\\' => match chars.next()? {
+                'n' => '\n',
+                't' => '\t',
+                'r' => '\r',
+                '0' => '\0',
+                x @ ('\\' | '\'' | '"') => x,
+                _ => return None,
+            },
+            x => x,
+        };
+        match chars.next() {
+            Some(_) => None,
+            None => Some(Literal::Char(c)),
+        }
+    }
+}
+
+fn is_integer_literal(value: &str) -> bool {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    !digits.is_empty() && digits.chars().all(|x| x.is_ascii_digit())
+}
+
+/*
+* Literals are displayed in a form which Literal::parse will read back as the same value
+*/
+impl std::fmt::Display for Literal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Literal::Int(x) => write!(f, "{}", x),
+            Literal::Float(x) => write!(f, "{:?}", x),
+            Literal::Bool(x) => write!(f, "{}", x),
+            Literal::Char(x) => match x {
+                '\n' => write!(f, "'\\n'"),
+                '\t' => write!(f, "'\\t'"),
+                '\r' => write!(f, "'\\r'"),
+                '\0' => write!(f, "'\\0'"),
+                '\\' | '\'' => write!(f, "'\\{}'", x),
+                x => write!(f, "'{}'", x),
+            },
+            Literal::Str(x) => write!(f, "\"{}\"", x),
+            Literal::Null => write!(f, "null"),
         }
     }
 }
 
+/*
+* The type of an expression is unknown after parsing, it is filled in by the semantic analysis pass
+* so that later stages can rely on it
+*/
 #[derive(Debug, Clone, PartialEq)]
-pub enum Expression {
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: Span,
+    pub ty: Option<Type>,
+}
+
+impl Expression {
+    pub fn new(kind: ExpressionKind, span: Span) -> Self {
+        Self {
+            kind,
+            span,
+            ty: None,
+        }
+    }
+
+    pub fn collect_variables(&self, variables: &mut HashSet<String>) {
+        match &self.kind {
+            ExpressionKind::ValExpr(_) => {}
+            ExpressionKind::VarExpr(x) => {
+                variables.insert(x.clone());
+            }
+            ExpressionKind::ArithmeticExpr(x, _, y) | ExpressionKind::BooleanExpr(x, _, y) => {
+                x.collect_variables(variables);
+                y.collect_variables(variables);
+            }
+            ExpressionKind::CastExpr(x, _)
+            | ExpressionKind::FieldExpr(x, _)
+            | ExpressionKind::AddressOf(x)
+            | ExpressionKind::Deref(x) => x.collect_variables(variables),
+            ExpressionKind::IndexExpr(x, index) => {
+                x.collect_variables(variables);
+                index.collect_variables(variables);
+            }
+            ExpressionKind::StructLiteral(_, fields) => {
+                for (_, value) in fields {
+                    value.collect_variables(variables);
+                }
+            }
+            ExpressionKind::VariantLiteral(_, _, values) => {
+                for value in values {
+                    value.collect_variables(variables);
+                }
+            }
+            ExpressionKind::MatchExpr(x, arms) => {
+                x.collect_variables(variables);
+                for arm in arms {
+                    let mut used = HashSet::new();
+                    arm.body.collect_variables(&mut used);
+                    for (_, v_name, _) in arm.pattern.bindings() {
+                        used.remove(v_name);
+                    }
+                    variables.extend(used);
+                }
+            }
+        }
+    }
+
+    /*
+     * Whether the expression refers to somewhere in memory, so it can be assigned to or have its
+     * address taken
+     */
+    pub fn is_place(&self) -> bool {
+        match &self.kind {
+            ExpressionKind::VarExpr(_) | ExpressionKind::Deref(_) => true,
+            ExpressionKind::IndexExpr(x, _) | ExpressionKind::FieldExpr(x, _) => x.is_place(),
+            _ => false,
+        }
+    }
+
+    fn is_pointer(&self) -> bool {
+        matches!(self.ty, Some(Type::Pointer(_)))
+    }
+
+    /*
+     * The variable which a place expression (e.g. xs[i] or p.x) refers to all or part of. A place
+     * reached through a pointer has no variable, telling when a field is accessed through a pointer
+     * relies on the types given by semantic analysis
+     */
+    pub fn root_variable(&self) -> Option<&str> {
+        match &self.kind {
+            ExpressionKind::VarExpr(x) => Some(x),
+            ExpressionKind::FieldExpr(x, _) if x.is_pointer() => None,
+            ExpressionKind::IndexExpr(x, _) | ExpressionKind::FieldExpr(x, _) => x.root_variable(),
+            _ => None,
+        }
+    }
+
+    /*
+     * The variables read to find where a place expression is, i.e. those used by its indices or by
+     * the pointer it is reached through
+     */
+    pub fn collect_index_variables(&self, variables: &mut HashSet<String>) {
+        match &self.kind {
+            ExpressionKind::IndexExpr(x, index) => {
+                x.collect_index_variables(variables);
+                index.collect_variables(variables);
+            }
+            ExpressionKind::FieldExpr(x, _) if x.is_pointer() => x.collect_variables(variables),
+            ExpressionKind::FieldExpr(x, _) => x.collect_index_variables(variables),
+            ExpressionKind::Deref(x) => x.collect_variables(variables),
+            _ => {}
+        }
+    }
+
+    /*
+     * Whether evaluating the expression reads memory through a pointer, which writing through any
+     * other pointer may change
+     */
+    pub fn dereferences(&self) -> bool {
+        match &self.kind {
+            ExpressionKind::ValExpr(_) | ExpressionKind::VarExpr(_) => false,
+            ExpressionKind::Deref(_) => true,
+            ExpressionKind::FieldExpr(x, _) => x.is_pointer() || x.dereferences(),
+            ExpressionKind::ArithmeticExpr(x, _, y)
+            | ExpressionKind::BooleanExpr(x, _, y)
+            | ExpressionKind::IndexExpr(x, y) => x.dereferences() || y.dereferences(),
+            ExpressionKind::CastExpr(x, _) | ExpressionKind::AddressOf(x) => x.dereferences(),
+            ExpressionKind::StructLiteral(_, fields) => {
+                fields.iter().any(|(_, x)| x.dereferences())
+            }
+            ExpressionKind::VariantLiteral(_, _, values) => values.iter().any(|x| x.dereferences()),
+            ExpressionKind::MatchExpr(x, arms) => {
+                x.dereferences() || arms.iter().any(|arm| arm.body.dereferences())
+            }
+        }
+    }
+
+    /*
+     * The variables which have their address taken by the expression
+     */
+    pub fn collect_address_taken(&self, variables: &mut HashSet<String>) {
+        match &self.kind {
+            ExpressionKind::ValExpr(_) | ExpressionKind::VarExpr(_) => {}
+            ExpressionKind::AddressOf(x) => {
+                if let Some(v_name) = x.root_variable() {
+                    variables.insert(v_name.to_string());
+                }
+                x.collect_address_taken(variables);
+            }
+            ExpressionKind::ArithmeticExpr(x, _, y)
+            | ExpressionKind::BooleanExpr(x, _, y)
+            | ExpressionKind::IndexExpr(x, y) => {
+                x.collect_address_taken(variables);
+                y.collect_address_taken(variables);
+            }
+            ExpressionKind::CastExpr(x, _)
+            | ExpressionKind::FieldExpr(x, _)
+            | ExpressionKind::Deref(x) => x.collect_address_taken(variables),
+            ExpressionKind::StructLiteral(_, fields) => {
+                for (_, value) in fields {
+                    value.collect_address_taken(variables);
+                }
+            }
+            ExpressionKind::VariantLiteral(_, _, values) => {
+                for value in values {
+                    value.collect_address_taken(variables);
+                }
+            }
+            ExpressionKind::MatchExpr(x, arms) => {
+                x.collect_address_taken(variables);
+                for arm in arms {
+                    arm.body.collect_address_taken(variables);
+                }
+            }
+        }
+    }
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpressionKind {
     ValExpr(String),
     VarExpr(String),
     ArithmeticExpr(Box<Expression>, Operator, Box<Expression>),
     BooleanExpr(Box<Expression>, Operator, Box<Expression>),
+    CastExpr(Box<Expression>, Type),
+    IndexExpr(Box<Expression>, Box<Expression>),
+    FieldExpr(Box<Expression>, String),
+    StructLiteral(String, Vec<(String, Expression)>),
+    VariantLiteral(String, String, Vec<Expression>),
+    MatchExpr(Box<Expression>, Vec<MatchArm<Expression>>),
+    AddressOf(Box<Expression>),
+    Deref(Box<Expression>),
 }
 
 impl std::fmt::Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl std::fmt::Display for ExpressionKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Expression::ValExpr(x) | Expression::VarExpr(x) => write!(f, "{}", x),
-            Expression::ArithmeticExpr(x, op, y) | Expression::BooleanExpr(x, op, y) => {
+            ExpressionKind::ValExpr(x) | ExpressionKind::VarExpr(x) => write!(f, "{}", x),
+            ExpressionKind::ArithmeticExpr(x, op, y) | ExpressionKind::BooleanExpr(x, op, y) => {
                 write!(f, "{} {} {}", x, op, y)
             }
+            ExpressionKind::CastExpr(x, ty) => write!(f, "{} as {}", x, ty),
+            ExpressionKind::IndexExpr(x, index) => write!(f, "{}[{}]", x, index),
+            ExpressionKind::FieldExpr(x, field) => write!(f, "{}.{}", x, field),
+            ExpressionKind::StructLiteral(name, fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(field, value)| format!("{}: {}", field, value))
+                    .collect();
+                write!(f, "{} {{ {} }}", name, fields.join(", "))
+            }
+            ExpressionKind::VariantLiteral(name, variant, values) => {
+                write!(f, "{}::{}", name, variant)?;
+                if !values.is_empty() {
+                    let values: Vec<String> = values.iter().map(|x| x.to_string()).collect();
+                    write!(f, "({})", values.join(", "))?;
+                }
+                Ok(())
+            }
+            ExpressionKind::MatchExpr(x, arms) => {
+                let arms: Vec<String> = arms.iter().map(|x| x.to_string()).collect();
+                write!(f, "match {} {{ {} }}", x, arms.join(", "))
+            }
+            ExpressionKind::AddressOf(x) => write!(f, "&{}", x),
+            ExpressionKind::Deref(x) => write!(f, "*{}", x),
         }
     }
 }
 
+/*
+* A pattern matches one variant of an enum, binding each of the values it carries to a new variable
+* or leaving it unbound when _ is given in place of a name. The wildcard pattern _ matches any value
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatternKind {
+    Variant(String, String, Vec<(String, Span)>),
+    Wildcard,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub span: Span,
+}
+
+impl Pattern {
+    /*
+     * The variables bound by the pattern, along with the position of the value each one is bound to
+     * and the span of its name
+     */
+    pub fn bindings(&self) -> Vec<(usize, &str, Span)> {
+        match &self.kind {
+            PatternKind::Variant(_, _, names) => names
+                .iter()
+                .enumerate()
+                .filter(|(_, (v_name, _))| v_name != "_")
+                .map(|(idx, (v_name, span))| (idx, v_name.as_str(), *span))
+                .collect(),
+            PatternKind::Wildcard => Vec::new(),
+        }
+    }
+}
+
+impl std::fmt::Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            PatternKind::Variant(name, variant, names) => {
+                write!(f, "{}::{}", name, variant)?;
+                if !names.is_empty() {
+                    let names: Vec<&str> = names.iter().map(|(x, _)| x.as_str()).collect();
+                    write!(f, "({})", names.join(", "))?;
+                }
+                Ok(())
+            }
+            PatternKind::Wildcard => write!(f, "_"),
+        }
+    }
+}
+
+/*
+* The body of an arm is a block in a match statement and an expression in a match expression
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchArm<T> {
+    pub pattern: Pattern,
+    pub body: T,
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for MatchArm<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} => {}", self.pattern, self.body)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructField {
+    pub name: String,
+    pub ty: Type,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumVariant {
+    pub name: String,
+    pub payload: Vec<Type>,
+    pub span: Span,
+}
+
+impl std::fmt::Display for EnumVariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.payload.is_empty() {
+            let payload: Vec<String> = self.payload.iter().map(|x| x.to_string()).collect();
+            write!(f, "({})", payload.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug)]
 pub enum Statement {
-    DeclarationStatement { v_name: String, expr: Expression },
-    AssignmentStatement { v_name: String, expr: Expression },
+    DeclarationStatement {
+        v_name: String,
+        v_span: Span,
+        ty: Type,
+        mutable: bool,
+        expr: Option<Expression>,
+        span: Span,
+    },
+    ConstStatement {
+        v_name: String,
+        v_span: Span,
+        ty: Type,
+        expr: Expression,
+        span: Span,
+    },
+    AssignmentStatement {
+        target: Expression,
+        expr: Expression,
+        span: Span,
+    },
+    BlockStatement {
+        statements: Vec<Statement>,
+        span: Span,
+    },
+    IfStatement {
+        condition: Expression,
+        then_block: Box<Statement>,
+        else_block: Option<Box<Statement>>,
+        span: Span,
+    },
+    WhileStatement {
+        condition: Expression,
+        body: Box<Statement>,
+        span: Span,
+    },
+    MatchStatement {
+        scrutinee: Expression,
+        arms: Vec<MatchArm<Statement>>,
+        span: Span,
+    },
+    StructStatement {
+        name: String,
+        fields: Vec<StructField>,
+        span: Span,
+    },
+    EnumStatement {
+        name: String,
+        variants: Vec<EnumVariant>,
+        span: Span,
+    },
+}
+
+impl Statement {
+    /*
+     * The name defined by a statement which can be made public
+     */
+    pub fn item_name(&self) -> Option<&str> {
+        match self {
+            Statement::DeclarationStatement { v_name, .. }
+            | Statement::ConstStatement { v_name, .. } => Some(v_name),
+            Statement::StructStatement { name, .. } | Statement::EnumStatement { name, .. } => {
+                Some(name)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::DeclarationStatement { span, .. }
+            | Statement::ConstStatement { span, .. }
+            | Statement::AssignmentStatement { span, .. }
+            | Statement::BlockStatement { span, .. }
+            | Statement::IfStatement { span, .. }
+            | Statement::WhileStatement { span, .. }
+            | Statement::MatchStatement { span, .. }
+            | Statement::StructStatement { span, .. }
+            | Statement::EnumStatement { span, .. } => *span,
+        }
+    }
+
+    pub fn collect_address_taken(&self, variables: &mut HashSet<String>) {
+        match self {
+            Statement::DeclarationStatement { expr, .. } => {
+                if let Some(x) = expr {
+                    x.collect_address_taken(variables);
+                }
+            }
+            Statement::ConstStatement { expr, .. } => expr.collect_address_taken(variables),
+            Statement::AssignmentStatement { target, expr, .. } => {
+                target.collect_address_taken(variables);
+                expr.collect_address_taken(variables);
+            }
+            Statement::BlockStatement { statements, .. } => {
+                for statement in statements {
+                    statement.collect_address_taken(variables);
+                }
+            }
+            Statement::IfStatement {
+                condition,
+                then_block,
+                else_block,
+                ..
+            } => {
+                condition.collect_address_taken(variables);
+                then_block.collect_address_taken(variables);
+                if let Some(x) = else_block {
+                    x.collect_address_taken(variables);
+                }
+            }
+            Statement::WhileStatement {
+                condition, body, ..
+            } => {
+                condition.collect_address_taken(variables);
+                body.collect_address_taken(variables);
+            }
+            Statement::MatchStatement {
+                scrutinee, arms, ..
+            } => {
+                scrutinee.collect_address_taken(variables);
+                for arm in arms {
+                    arm.body.collect_address_taken(variables);
+                }
+            }
+            Statement::StructStatement { .. } | Statement::EnumStatement { .. } => {}
+        }
+    }
 }
 
 impl std::fmt::Display for Statement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Statement::DeclarationStatement { v_name, expr } => {
-                write!(f, "Declaring {} with value {}", v_name, expr)
+            Statement::DeclarationStatement {
+                v_name,
+                mutable,
+                expr,
+                ..
+            } => {
+                let kind = if *mutable { "mutable " } else { "" };
+                match expr {
+                    Some(x) => write!(f, "Declaring {}{} with value {}", kind, v_name, x),
+                    None => write!(f, "Declaring {}{}", kind, v_name),
+                }
+            }
+            Statement::ConstStatement { v_name, expr, .. } => {
+                write!(f, "Declaring constant {} with value {}", v_name, expr)
+            }
+            Statement::AssignmentStatement { target, expr, .. } => {
+                write!(f, "Assigning {} to value {}", target, expr)
+            }
+            Statement::BlockStatement { statements, .. } => {
+                writeln!(f, "Block:")?;
+                for statement in statements {
+                    writeln!(f, "{}", statement)?;
+                }
+                write!(f, "End Block")
+            }
+            Statement::IfStatement {
+                condition,
+                then_block,
+                else_block,
+                ..
+            } => {
+                writeln!(f, "If {}:", condition)?;
+                write!(f, "{}", then_block)?;
+                if let Some(else_block) = else_block {
+                    write!(f, "\nElse:\n{}", else_block)?;
+                }
+                Ok(())
+            }
+            Statement::WhileStatement {
+                condition, body, ..
+            } => {
+                writeln!(f, "While {}:", condition)?;
+                write!(f, "{}", body)
+            }
+            Statement::MatchStatement {
+                scrutinee, arms, ..
+            } => {
+                write!(f, "Match {}:", scrutinee)?;
+                for arm in arms {
+                    write!(f, "\n{}", arm)?;
+                }
+                Ok(())
+            }
+            Statement::StructStatement { name, fields, .. } => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|x| format!("{}: {}", x.name, x.ty))
+                    .collect();
+                write!(
+                    f,
+                    "Defining struct {} with fields {}",
+                    name,
+                    fields.join(", ")
+                )
             }
-            Statement::AssignmentStatement { v_name, expr } => {
-                write!(f, "Assigning {} to value {}", v_name, expr)
+            Statement::EnumStatement { name, variants, .. } => {
+                let variants: Vec<String> = variants.iter().map(|x| x.to_string()).collect();
+                write!(
+                    f,
+                    "Defining enum {} with variants {}",
+                    name,
+                    variants.join(", ")
+                )
             }
         }
     }
 }
 
-pub fn parse_program(tokens: Vec<Token>) -> Result<Program, ParseError> {
-    let statements = parse_statements(tokens)?;
-    Ok(Program {
-        statements: statements,
-    })
+/*
+* Imports and public items can only be at the top level, so the statements between them are parsed
+* as the statements of a block would be
+*/
+pub fn parse_module(tokens: Vec<Token>) -> Result<Module, ParseError> {
+    let mut module = Module {
+        program: Program {
+            statements: Vec::new(),
+        },
+        imports: Vec::new(),
+        public: HashSet::new(),
+    };
+    let mut idx = 0;
+    loop {
+        let statements = parse_statements(&tokens, &mut idx, None)?;
+        module.program.statements.extend(statements);
+        let Some(token) = tokens.get(idx) else {
+            return Ok(module);
+        };
+        if token.t_type == TokenType::Import {
+            let end_idx = find_statement_end(&tokens, idx)?;
+            module
+                .imports
+                .push(parse_import_statement(&tokens[idx..end_idx])?);
+            idx = end_idx + 1;
+            continue;
+        }
+
+        idx += 1;
+        if idx == tokens.len() {
+            return Err(ParseError::InvalidStatement(token.clone()));
+        }
+        let statement = parse_next_statement(&tokens, &mut idx, None)?;
+        match statement.item_name() {
+            Some(name) => module.public.insert(name.to_string()),
+            None => return Err(ParseError::InvalidStatement(token.clone())),
+        };
+        module.program.statements.push(statement);
+    }
 }
 
-fn parse_statements(tokens: Vec<Token>) -> Result<Vec<Statement>, ParseError> {
-    let mut start_idx: usize = 0;
-    let mut end_idx: usize = 0;
+/*
+* Parses statements starting at idx until either the end of the tokens or, when inside of a block,
+* the closing brace for that block. At the top level an import or pub also ends the statements, for
+* the module to parse. On success idx will point at the closing brace, import or pub (if any)
+*/
+fn parse_statements(
+    tokens: &[Token],
+    idx: &mut usize,
+    block_start: Option<&Token>,
+) -> Result<Vec<Statement>, ParseError> {
     let mut statements: Vec<Statement> = Vec::new();
-    let mut v_table: HashMap<String, i32> = HashMap::new();
 
-    while end_idx < tokens.len() {
-        match tokens[end_idx].t_type {
-            TokenType::Semi => {
-                if start_idx == end_idx {
-                    return Err(ParseError::EmptyStatement(tokens[end_idx].clone()));
-                }
-                let statement = parse_statement(&tokens[start_idx..end_idx], &mut v_table)?;
-                statements.push(statement);
-                end_idx += 1;
-                start_idx = end_idx;
-            }
-            _ => end_idx += 1,
+    while *idx < tokens.len() {
+        match tokens[*idx].t_type {
+            TokenType::RBrace if block_start.is_some() => return Ok(statements),
+            TokenType::RBrace => return Err(ParseError::UnmatchedBrace(tokens[*idx].clone())),
+            TokenType::Import | TokenType::Pub if block_start.is_none() => return Ok(statements),
+            _ => statements.push(parse_next_statement(tokens, idx, block_start)?),
         }
     }
 
-    if start_idx != end_idx {
-        Err(ParseError::MissingSemicolon(tokens[start_idx].clone()))
-    } else {
-        Ok(statements)
+    match block_start {
+        Some(x) => Err(ParseError::UnclosedBlock(x.clone())),
+        None => Ok(statements),
     }
 }
 
-fn parse_statement(
+/*
+* Parses the statement starting at idx, on success idx will point after it
+*/
+fn parse_next_statement(
     tokens: &[Token],
-    v_table: &mut HashMap<String, i32>,
+    idx: &mut usize,
+    block_start: Option<&Token>,
 ) -> Result<Statement, ParseError> {
+    match tokens[*idx].t_type {
+        TokenType::Semi => Err(ParseError::EmptyStatement(tokens[*idx].clone())),
+        TokenType::LBrace => parse_block_statement(tokens, idx),
+        TokenType::If => parse_if_statement(tokens, idx),
+        TokenType::While => parse_while_statement(tokens, idx),
+        TokenType::Match => parse_match_statement(tokens, idx),
+        TokenType::Struct if block_start.is_none() => parse_struct_statement(tokens, idx),
+        TokenType::Enum if block_start.is_none() => parse_enum_statement(tokens, idx),
+        TokenType::Struct | TokenType::Enum | TokenType::Import | TokenType::Pub
+            if block_start.is_some() =>
+        {
+            Err(ParseError::NestedDefinition(tokens[*idx].clone()))
+        }
+        TokenType::Else | TokenType::RBrace | TokenType::Import | TokenType::Pub => {
+            Err(ParseError::InvalidStatement(tokens[*idx].clone()))
+        }
+        _ => {
+            let end_idx = find_statement_end(tokens, *idx)?;
+            let statement = parse_statement(&tokens[*idx..end_idx])?;
+            *idx = end_idx + 1;
+            Ok(statement)
+        }
+    }
+}
+
+fn parse_import_statement(tokens: &[Token]) -> Result<Import, ParseError> {
+    let first = match tokens.get(1) {
+        Some(x) => x,
+        None => return Err(ParseError::InvalidStatement(tokens[0].clone())),
+    };
+    expect_token_type(first, TokenType::Variable)?;
+    let mut parser = ExpressionParser::new(&tokens[2..]);
+    let (path, _) = parser.parse_path(first)?;
+    if parser.idx < tokens.len() - 2 {
+        return Err(ParseError::InvalidStatement(tokens[0].clone()));
+    }
+    Ok(Import {
+        path,
+        span: tokens_span(tokens),
+    })
+}
+
+/*
+* A brace directly after a variable opens a struct literal, and the first brace after match opens
+* the arms of a match expression, rather than a block. So statements can contain braces as long as
+* they are matched
+*/
+fn find_statement_end(tokens: &[Token], start_idx: usize) -> Result<usize, ParseError> {
+    let mut depth = 0;
+    let mut in_match = false;
+    for (idx, token) in tokens.iter().enumerate().skip(start_idx) {
+        match token.t_type {
+            TokenType::Semi => return Ok(idx),
+            TokenType::Match => in_match = true,
+            TokenType::LBrace
+                if in_match || idx > start_idx && tokens[idx - 1].t_type == TokenType::Variable =>
+            {
+                depth += 1;
+                in_match = false;
+            }
+            TokenType::RBrace if depth > 0 => depth -= 1,
+            TokenType::LBrace | TokenType::RBrace => break,
+            _ => {}
+        }
+    }
+    Err(ParseError::MissingSemicolon(tokens[start_idx].clone()))
+}
+
+/*
+* Each arm of a match statement is a pattern followed by the block run when it matches. On success
+* idx will point after the closing brace
+*/
+fn parse_match_statement(tokens: &[Token], idx: &mut usize) -> Result<Statement, ParseError> {
+    let match_token = &tokens[*idx];
+    let scrutinee = parse_condition(tokens, idx)?;
+    let block_start = &tokens[*idx];
+    *idx += 1;
+
+    let mut arms = Vec::new();
+    loop {
+        match tokens.get(*idx) {
+            Some(x) if x.t_type == TokenType::RBrace => break,
+            Some(_) => {}
+            None => return Err(ParseError::UnclosedBlock(block_start.clone())),
+        }
+        let mut parser = ExpressionParser::new(&tokens[*idx..]);
+        let pattern = parser.parse_pattern()?;
+        let arrow = parser.next_token()?;
+        expect_token_type(arrow, TokenType::Arrow)?;
+        *idx += parser.idx;
+        if *idx == tokens.len() {
+            return Err(ParseError::MissingBlock(arrow.clone()));
+        }
+        let body = parse_block_statement(tokens, idx)?;
+        arms.push(MatchArm { pattern, body });
+    }
+    let block_end = &tokens[*idx];
+    *idx += 1;
+
+    Ok(Statement::MatchStatement {
+        scrutinee,
+        arms,
+        span: match_token.span.to(block_end.span),
+    })
+}
+
+fn parse_block_statement(tokens: &[Token], idx: &mut usize) -> Result<Statement, ParseError> {
+    let block_start = &tokens[*idx];
+    expect_token_type(block_start, TokenType::LBrace)?;
+    *idx += 1;
+
+    let statements = parse_statements(tokens, idx, Some(block_start))?;
+    let block_end = &tokens[*idx];
+    *idx += 1;
+
+    Ok(Statement::BlockStatement {
+        statements,
+        span: block_start.span.to(block_end.span),
+    })
+}
+
+fn parse_if_statement(tokens: &[Token], idx: &mut usize) -> Result<Statement, ParseError> {
+    let if_token = &tokens[*idx];
+    let condition = parse_condition(tokens, idx)?;
+    let then_block = parse_block_statement(tokens, idx)?;
+
+    let else_block = match tokens.get(*idx) {
+        Some(else_token) if else_token.t_type == TokenType::Else => {
+            *idx += 1;
+            match tokens.get(*idx) {
+                Some(x) if x.t_type == TokenType::If => Some(parse_if_statement(tokens, idx)?),
+                Some(_) => Some(parse_block_statement(tokens, idx)?),
+                None => return Err(ParseError::MissingBlock(else_token.clone())),
+            }
+        }
+        _ => None,
+    };
+
+    let last_block = else_block.as_ref().unwrap_or(&then_block);
+    Ok(Statement::IfStatement {
+        span: if_token.span.to(last_block.span()),
+        condition,
+        then_block: Box::new(then_block),
+        else_block: else_block.map(Box::new),
+    })
+}
+
+fn parse_while_statement(tokens: &[Token], idx: &mut usize) -> Result<Statement, ParseError> {
+    let while_token = &tokens[*idx];
+    let condition = parse_condition(tokens, idx)?;
+    let body = parse_block_statement(tokens, idx)?;
+
+    Ok(Statement::WhileStatement {
+        span: while_token.span.to(body.span()),
+        condition,
+        body: Box::new(body),
+    })
+}
+
+/*
+* The condition of an if or while statement, or the value matched on by a match, is every token
+* between the keyword at idx and the opening brace of its block. On success idx will point at that
+* brace
+*/
+fn parse_condition(tokens: &[Token], idx: &mut usize) -> Result<Expression, ParseError> {
+    let keyword = &tokens[*idx];
+    let start_idx = *idx + 1;
+    let end_idx = match tokens[start_idx..].iter().position(|x| {
+        matches!(
+            x.t_type,
+            TokenType::LBrace | TokenType::RBrace | TokenType::Semi
+        )
+    }) {
+        Some(x) => start_idx + x,
+        None => return Err(ParseError::MissingBlock(keyword.clone())),
+    };
+    expect_token_type(&tokens[end_idx], TokenType::LBrace)?;
+    if end_idx == start_idx {
+        return Err(ParseError::InvalidStatement(keyword.clone()));
+    }
+
+    let condition = expect_expression(&tokens[start_idx..end_idx])?;
+    *idx = end_idx;
+    Ok(condition)
+}
+
+/*
+* A struct has fields with a type and name, ending with a semicolon like a declaration without a
+* value. On success idx will point after the closing brace
+*/
+fn parse_struct_statement(tokens: &[Token], idx: &mut usize) -> Result<Statement, ParseError> {
+    let struct_token = &tokens[*idx];
+    let (name_token, block_start) = parse_definition_start(tokens, idx)?;
+
+    let mut fields = Vec::new();
+    loop {
+        match tokens.get(*idx) {
+            Some(x) if x.t_type == TokenType::RBrace => break,
+            Some(_) => {}
+            None => return Err(ParseError::UnclosedBlock(block_start.clone())),
+        }
+        let end_idx = find_statement_end(tokens, *idx)?;
+        if end_idx == *idx {
+            return Err(ParseError::EmptyStatement(tokens[end_idx].clone()));
+        }
+        let (ty, rest) = parse_type(&tokens[*idx..end_idx])?;
+        match rest {
+            [field] => {
+                expect_token_type(field, TokenType::Variable)?;
+                fields.push(StructField {
+                    name: field.value.clone(),
+                    ty,
+                    span: tokens_span(&tokens[*idx..end_idx]),
+                });
+            }
+            _ => return Err(ParseError::InvalidStatement(tokens[*idx].clone())),
+        }
+        *idx = end_idx + 1;
+    }
+    let block_end = &tokens[*idx];
+    *idx += 1;
+
+    Ok(Statement::StructStatement {
+        name: name_token.value.clone(),
+        fields,
+        span: struct_token.span.to(block_end.span),
+    })
+}
+
+/*
+* Each variant of an enum is a name followed by the types of the values it carries, if any, ending
+* with a semicolon like a struct field. On success idx will point after the closing brace
+*/
+fn parse_enum_statement(tokens: &[Token], idx: &mut usize) -> Result<Statement, ParseError> {
+    let enum_token = &tokens[*idx];
+    let (name_token, block_start) = parse_definition_start(tokens, idx)?;
+
+    let mut variants = Vec::new();
+    loop {
+        match tokens.get(*idx) {
+            Some(x) if x.t_type == TokenType::RBrace => break,
+            Some(_) => {}
+            None => return Err(ParseError::UnclosedBlock(block_start.clone())),
+        }
+        let end_idx = find_statement_end(tokens, *idx)?;
+        let variant_tokens = &tokens[*idx..end_idx];
+        let variant = match variant_tokens.first() {
+            Some(x) => x,
+            None => return Err(ParseError::EmptyStatement(tokens[end_idx].clone())),
+        };
+        expect_token_type(variant, TokenType::Variable)?;
+        let payload = match &variant_tokens[1..] {
+            [] => Vec::new(),
+            [open, types @ .., close] => {
+                expect_token_type(open, TokenType::LParen)?;
+                expect_token_type(close, TokenType::RParen)?;
+                parse_payload_types(types, variant)?
+            }
+            [x] => return Err(ParseError::UnexpectedToken(x.clone(), TokenType::LParen)),
+        };
+        variants.push(EnumVariant {
+            name: variant.value.clone(),
+            payload,
+            span: tokens_span(variant_tokens),
+        });
+        *idx = end_idx + 1;
+    }
+    let block_end = &tokens[*idx];
+    *idx += 1;
+
+    Ok(Statement::EnumStatement {
+        name: name_token.value.clone(),
+        variants,
+        span: enum_token.span.to(block_end.span),
+    })
+}
+
+/*
+* A struct or enum definition starts with its name and the opening brace of its body, returns both
+* of them. On success idx will point after the brace
+*/
+fn parse_definition_start<'a>(
+    tokens: &'a [Token],
+    idx: &mut usize,
+) -> Result<(&'a Token, &'a Token), ParseError> {
+    let name_token = match tokens.get(*idx + 1) {
+        Some(x) => x,
+        None => return Err(ParseError::InvalidStatement(tokens[*idx].clone())),
+    };
+    expect_token_type(name_token, TokenType::Variable)?;
+    let block_start = match tokens.get(*idx + 2) {
+        Some(x) => x,
+        None => return Err(ParseError::MissingBlock(name_token.clone())),
+    };
+    expect_token_type(block_start, TokenType::LBrace)?;
+    *idx += 3;
+    Ok((name_token, block_start))
+}
+
+/*
+* The types carried by a variant are separated by commas, with at least one type and nothing after
+* the last
+*/
+fn parse_payload_types(tokens: &[Token], variant: &Token) -> Result<Vec<Type>, ParseError> {
+    let mut types = Vec::new();
+    let mut rest = tokens;
+    loop {
+        if rest.is_empty() {
+            return Err(ParseError::InvalidStatement(variant.clone()));
+        }
+        let (ty, after) = parse_type(rest)?;
+        types.push(ty);
+        match after {
+            [] => return Ok(types),
+            [comma, next @ ..] if comma.t_type == TokenType::Comma => rest = next,
+            [x, ..] => return Err(ParseError::UnexpectedToken(x.clone(), TokenType::Comma)),
+        }
+    }
+}
+
+fn parse_statement(tokens: &[Token]) -> Result<Statement, ParseError> {
     match tokens[0].t_type {
-        TokenType::Int => parse_declaration_statement(tokens, v_table),
-        TokenType::Variable => parse_assignment_statement(tokens, v_table),
+        TokenType::Type | TokenType::Mut => parse_declaration_statement(tokens),
+        TokenType::Const => parse_const_statement(tokens),
+        TokenType::Variable | TokenType::ArithmeticOp if is_declaration(tokens) => {
+            parse_declaration_statement(tokens)
+        }
+        TokenType::Variable => parse_assignment_statement(tokens),
+        TokenType::ArithmeticOp if is_pointer_prefix(&tokens[0]) => {
+            parse_assignment_statement(tokens)
+        }
         _ => Err(ParseError::InvalidStatement(tokens[0].clone())),
     }
 }
 
-fn parse_declaration_statement(
-    tokens: &[Token],
-    v_table: &mut HashMap<String, i32>,
-) -> Result<Statement, ParseError> {
-    if tokens.len() < MIN_DECLARATION_LENGTH {
+/*
+* Declarations of a struct or pointer typed variable start with a variable or * like assignments
+* can (e.g. p.x = 1 or *p = 1), they are told apart by the type being followed by the name of the
+* variable
+*/
+fn is_declaration(tokens: &[Token]) -> bool {
+    parse_type(tokens).is_ok_and(|(_, rest)| {
+        rest.first()
+            .is_some_and(|x| x.t_type == TokenType::Variable)
+    })
+}
+
+/*
+* Variables are immutable unless their declaration is prefixed with mut. The initial value can be
+* left out, in which case the variable must be assigned before it is read
+*/
+fn parse_declaration_statement(tokens: &[Token]) -> Result<Statement, ParseError> {
+    let mutable = tokens[0].t_type == TokenType::Mut;
+    let decl_tokens = if mutable { &tokens[1..] } else { tokens };
+    if decl_tokens.len() < MIN_DECLARATION_LENGTH {
         return Err(ParseError::InvalidStatement(tokens[0].clone()));
     }
-    expect_token_type(&tokens[0], TokenType::Int)?;
-    expect_token_type(&tokens[1], TokenType::Variable)?;
-    expect_token_type(&tokens[2], TokenType::Eq)?;
-    let expr = expect_arithmetic_expression(&tokens[3..], v_table)?;
-
-    if v_table.contains_key(&tokens[1].value) {
-        return Err(ParseError::RedeclaringVariable(tokens[1].clone()));
+    if matches!(
+        decl_tokens[0].t_type,
+        TokenType::Variable | TokenType::ArithmeticOp
+    ) && !is_declaration(decl_tokens)
+    {
+        return Err(ParseError::UnexpectedToken(
+            decl_tokens[0].clone(),
+            TokenType::Type,
+        ));
     }
+    let (ty, decl_tokens) = parse_type(decl_tokens)?;
+    let v_token = match decl_tokens.first() {
+        Some(x) => x,
+        None => return Err(ParseError::InvalidStatement(tokens[0].clone())),
+    };
+    expect_token_type(v_token, TokenType::Variable)?;
+    let expr = match &decl_tokens[1..] {
+        [] => None,
+        [eq, rest @ ..] => {
+            expect_token_type(eq, TokenType::Eq)?;
+            if rest.is_empty() {
+                return Err(ParseError::InvalidStatement(eq.clone()));
+            }
+            Some(expect_expression(rest)?)
+        }
+    };
 
-    v_table.insert(tokens[1].value.clone(), 1);
     Ok(Statement::DeclarationStatement {
-        v_name: tokens[1].value.clone(),
+        v_name: v_token.value.clone(),
+        v_span: v_token.span,
+        ty,
+        mutable,
+        span: tokens_span(tokens),
         expr,
     })
 }
 
-fn parse_assignment_statement(
-    tokens: &[Token],
-    v_table: &mut HashMap<String, i32>,
-) -> Result<Statement, ParseError> {
-    if tokens.len() < MIN_ASSIGNMENT_LENGTH {
+fn parse_const_statement(tokens: &[Token]) -> Result<Statement, ParseError> {
+    if tokens.len() < MIN_CONST_LENGTH {
         return Err(ParseError::InvalidStatement(tokens[0].clone()));
     }
-    expect_token_type(&tokens[0], TokenType::Variable)?;
-    expect_token_type(&tokens[1], TokenType::Eq)?;
-    let expr = expect_arithmetic_expression(&tokens[2..], v_table)?;
+    expect_token_type(&tokens[0], TokenType::Const)?;
+    let (ty, const_tokens) = parse_type(&tokens[1..])?;
+    let (v_token, expr) = match const_tokens {
+        [v_token, eq, rest @ ..] if !rest.is_empty() => {
+            expect_token_type(v_token, TokenType::Variable)?;
+            expect_token_type(eq, TokenType::Eq)?;
+            (v_token, expect_expression(rest)?)
+        }
+        _ => return Err(ParseError::InvalidStatement(tokens[0].clone())),
+    };
 
-    if !v_table.contains_key(&tokens[0].value) {
-        return Err(ParseError::UndefinedVariable(tokens[0].clone()));
+    Ok(Statement::ConstStatement {
+        v_name: v_token.value.clone(),
+        v_span: v_token.span,
+        ty,
+        span: tokens_span(tokens),
+        expr,
+    })
+}
+
+/*
+* A type is a primitive type or struct name followed by the length of each dimension of an array,
+* e.g. int[3][4] is an array of 3 arrays of 4 integers. Each * before the name makes a pointer to
+* the element type, so *int[3] is an array of 3 pointers. Returns the type along with the tokens
+* after it
+*/
+fn parse_type(tokens: &[Token]) -> Result<(Type, &[Token]), ParseError> {
+    let mut idx = 0;
+    while tokens.get(idx).is_some_and(is_pointer_prefix) {
+        idx += 1;
     }
+    let pointers = idx;
+    let mut ty = match tokens.get(idx) {
+        Some(x) if x.t_type == TokenType::Variable => {
+            let mut name = x.value.clone();
+            while let (Some(colons), Some(segment)) = (tokens.get(idx + 1), tokens.get(idx + 2))
+                && colons.t_type == TokenType::DoubleColon
+                && segment.t_type == TokenType::Variable
+            {
+                name = format!("{}::{}", name, segment.value);
+                idx += 2;
+            }
+            Type::Struct(name)
+        }
+        Some(x) => Type::from_token(x)?,
+        None => match tokens.last() {
+            Some(x) => return Err(ParseError::InvalidStatement(x.clone())),
+            None => return Err(ParseError::MissingType),
+        },
+    };
+    for _ in 0..pointers {
+        ty = Type::Pointer(Box::new(ty));
+    }
+    let mut lengths = Vec::new();
+    idx += 1;
+    while tokens
+        .get(idx)
+        .is_some_and(|x| x.t_type == TokenType::LBracket)
+    {
+        let (length, close) = match (tokens.get(idx + 1), tokens.get(idx + 2)) {
+            (Some(x), Some(y)) => (x, y),
+            _ => return Err(ParseError::InvalidStatement(tokens[idx].clone())),
+        };
+        expect_token_type(length, TokenType::Value)?;
+        match length.value.parse::<usize>() {
+            Ok(x) if x > 0 => lengths.push(x),
+            _ => return Err(ParseError::InvalidArrayLength(length.clone())),
+        }
+        expect_token_type(close, TokenType::RBracket)?;
+        idx += 3;
+    }
+
+    for length in lengths.into_iter().rev() {
+        ty = Type::Array(Box::new(ty), length);
+    }
+    Ok((ty, &tokens[idx..]))
+}
+
+fn is_pointer_prefix(token: &Token) -> bool {
+    token.t_type == TokenType::ArithmeticOp && token.value == "*"
+}
+
+fn parse_assignment_statement(tokens: &[Token]) -> Result<Statement, ParseError> {
+    if tokens.len() < MIN_ASSIGNMENT_LENGTH {
+        return Err(ParseError::InvalidStatement(tokens[0].clone()));
+    }
+    let eq_idx = match tokens.iter().position(|x| x.t_type == TokenType::Eq) {
+        Some(x) => x,
+        None => {
+            return Err(ParseError::UnexpectedToken(
+                tokens[1].clone(),
+                TokenType::Eq,
+            ));
+        }
+    };
+    let mut target = expect_expression(&tokens[..eq_idx])?;
+    /*
+     * A path without values can't be an enum variant when assigned to, so it has to be a variable
+     * of another module
+     */
+    if let ExpressionKind::VariantLiteral(name, variant, values) = &target.kind
+        && values.is_empty()
+    {
+        target.kind = ExpressionKind::VarExpr(format!("{}::{}", name, variant));
+    }
+    if !target.is_place() {
+        return Err(ParseError::InvalidAssignmentTarget(tokens[0].clone()));
+    }
+    if eq_idx + 1 == tokens.len() {
+        return Err(ParseError::InvalidStatement(tokens[eq_idx].clone()));
+    }
+    let expr = expect_expression(&tokens[eq_idx + 1..])?;
 
     Ok(Statement::AssignmentStatement {
-        v_name: tokens[0].value.clone(),
+        target,
+        span: tokens_span(tokens),
         expr,
     })
 }
@@ -277,41 +1753,331 @@ fn expect_token_type(actual: &Token, expected: TokenType) -> Result<(), ParseErr
     }
 }
 
-fn expect_arithmetic_expression(
-    tokens: &[Token],
-    v_table: &HashMap<String, i32>,
-) -> Result<Expression, ParseError> {
-    if tokens.len() == 1 {
-        handle_single_element_expr(&tokens[0], v_table)
-    } else if tokens.len() > 2 && Operator::from_token(&tokens[1]).is_ok() {
-        let lhs = handle_single_element_expr(&tokens[0], v_table)?;
-        let rhs = expect_arithmetic_expression(&tokens[2..], v_table)?;
-        match tokens[1].t_type {
-            TokenType::ArithmeticOp => Ok(Expression::ArithmeticExpr(
-                Box::new(lhs),
-                Operator::from_token(&tokens[1])?,
-                Box::new(rhs),
-            )),
-            _ => Err(ParseError::InvalidExpression(tokens[0].clone())),
-        }
+fn expect_expression(tokens: &[Token]) -> Result<Expression, ParseError> {
+    let mut parser = ExpressionParser::new(tokens);
+    let expr = parser.parse_boolean_expr()?;
+
+    if parser.idx < tokens.len() {
+        Err(ParseError::InvalidExpression(
+            tokens[parser.operand_start].clone(),
+        ))
     } else {
-        Err(ParseError::InvalidExpression(tokens[0].clone()))
+        Ok(expr)
     }
 }
 
-fn handle_single_element_expr(
-    token: &Token,
-    v_table: &HashMap<String, i32>,
-) -> Result<Expression, ParseError> {
-    match token {
-        x if constants::VALUE_REGEX.is_match(&x.value) => Ok(Expression::ValExpr(x.value.clone())),
-        x if constants::VARIABLE_REGEX.is_match(&x.value) => {
-            if v_table.contains_key(&x.value) {
-                Ok(Expression::VarExpr(x.value.clone()))
-            } else {
-                Err(ParseError::UndefinedVariable(x.clone()))
+/*
+* Expressions are parsed by recursive descent over the tokens of a single statement. Arithmetic
+* operators of the same precedence group to the left, so a - b + c is parsed as (a - b) + c
+*/
+struct ExpressionParser<'a> {
+    tokens: &'a [Token],
+    idx: usize,
+    operand_start: usize,
+}
+
+impl<'a> ExpressionParser<'a> {
+    fn new(tokens: &'a [Token]) -> Self {
+        ExpressionParser {
+            tokens,
+            idx: 0,
+            operand_start: 0,
+        }
+    }
+
+    fn peek_type(&self) -> Option<TokenType> {
+        self.tokens.get(self.idx).map(|x| x.t_type)
+    }
+
+    fn next_token(&mut self) -> Result<&'a Token, ParseError> {
+        match self.tokens.get(self.idx) {
+            Some(x) => {
+                self.idx += 1;
+                Ok(x)
             }
+            None => Err(ParseError::InvalidExpression(
+                self.tokens[self.idx - 1].clone(),
+            )),
+        }
+    }
+
+    fn parse_boolean_expr(&mut self) -> Result<Expression, ParseError> {
+        let lhs = self.parse_arithmetic_expr()?;
+        if self.peek_type() != Some(TokenType::BooleanOp) {
+            return Ok(lhs);
+        }
+        let op = Operator::from_token(self.next_token()?)?;
+        let rhs = self.parse_boolean_expr()?;
+        let span = lhs.span.to(rhs.span);
+        Ok(Expression::new(
+            ExpressionKind::BooleanExpr(Box::new(lhs), op, Box::new(rhs)),
+            span,
+        ))
+    }
+
+    fn parse_arithmetic_expr(&mut self) -> Result<Expression, ParseError> {
+        let mut lhs = self.parse_term_expr()?;
+        while self.peek_arithmetic_op(&["+", "-", "+%", "-%", "+|", "-|"]) {
+            let op = Operator::from_token(self.next_token()?)?;
+            let rhs = self.parse_term_expr()?;
+            let span = lhs.span.to(rhs.span);
+            lhs = Expression::new(
+                ExpressionKind::ArithmeticExpr(Box::new(lhs), op, Box::new(rhs)),
+                span,
+            );
+        }
+        Ok(lhs)
+    }
+
+    fn parse_term_expr(&mut self) -> Result<Expression, ParseError> {
+        let mut lhs = self.parse_cast_expr()?;
+        while self.peek_arithmetic_op(&["*", "/", "*%", "*|"]) {
+            let op = Operator::from_token(self.next_token()?)?;
+            let rhs = self.parse_cast_expr()?;
+            let span = lhs.span.to(rhs.span);
+            lhs = Expression::new(
+                ExpressionKind::ArithmeticExpr(Box::new(lhs), op, Box::new(rhs)),
+                span,
+            );
+        }
+        Ok(lhs)
+    }
+
+    fn peek_arithmetic_op(&self, values: &[&str]) -> bool {
+        match self.tokens.get(self.idx) {
+            Some(x) => x.t_type == TokenType::ArithmeticOp && values.contains(&x.value.as_str()),
+            None => false,
+        }
+    }
+
+    fn parse_cast_expr(&mut self) -> Result<Expression, ParseError> {
+        let mut expr = self.parse_primary_expr()?;
+        while self.peek_type() == Some(TokenType::As) {
+            self.next_token()?;
+            let ty_token = self.next_token()?;
+            let ty = Type::from_token(ty_token)?;
+            let span = expr.span.to(ty_token.span);
+            expr = Expression::new(ExpressionKind::CastExpr(Box::new(expr), ty), span);
+        }
+        Ok(expr)
+    }
+
+    fn parse_primary_expr(&mut self) -> Result<Expression, ParseError> {
+        self.operand_start = self.idx;
+        let token = self.next_token()?;
+        match token.t_type {
+            TokenType::Value => Ok(Expression::new(
+                ExpressionKind::ValExpr(token.value.clone()),
+                token.span,
+            )),
+            TokenType::Match => self.parse_match_expr(token),
+            TokenType::Variable => {
+                let (mut path, span) = self.parse_path(token)?;
+                /*
+                 * A path followed by an index or field can't be an enum variant, so it is a
+                 * variable of another module
+                 */
+                let is_variable = path.len() == 1
+                    || matches!(self.peek_type(), Some(TokenType::LBracket | TokenType::Dot));
+                if self.peek_type() == Some(TokenType::LBrace) {
+                    self.parse_struct_literal(path.join("::"), span)
+                } else if is_variable {
+                    let var_expr = Expression::new(ExpressionKind::VarExpr(path.join("::")), span);
+                    self.parse_place_expr(var_expr)
+                } else {
+                    let variant = path.pop().unwrap_or_default();
+                    self.parse_variant_literal(path.join("::"), variant, span)
+                }
+            }
+            TokenType::Ampersand => {
+                let place = self.parse_primary_expr()?;
+                let span = token.span.to(place.span);
+                Ok(Expression::new(
+                    ExpressionKind::AddressOf(Box::new(place)),
+                    span,
+                ))
+            }
+            TokenType::ArithmeticOp if is_pointer_prefix(token) => {
+                let pointer = self.parse_primary_expr()?;
+                let span = token.span.to(pointer.span);
+                Ok(Expression::new(
+                    ExpressionKind::Deref(Box::new(pointer)),
+                    span,
+                ))
+            }
+            _ => Err(ParseError::InvalidExpression(token.clone())),
+        }
+    }
+
+    fn parse_place_expr(&mut self, mut expr: Expression) -> Result<Expression, ParseError> {
+        loop {
+            match self.peek_type() {
+                Some(TokenType::LBracket) => {
+                    self.next_token()?;
+                    let index = self.parse_boolean_expr()?;
+                    let close = self.next_token()?;
+                    expect_token_type(close, TokenType::RBracket)?;
+                    let span = expr.span.to(close.span);
+                    expr = Expression::new(
+                        ExpressionKind::IndexExpr(Box::new(expr), Box::new(index)),
+                        span,
+                    );
+                }
+                Some(TokenType::Dot) => {
+                    self.next_token()?;
+                    let field = self.next_token()?;
+                    expect_token_type(field, TokenType::Variable)?;
+                    let span = expr.span.to(field.span);
+                    expr = Expression::new(
+                        ExpressionKind::FieldExpr(Box::new(expr), field.value.clone()),
+                        span,
+                    );
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    /*
+     * A path is a name followed by any number of names each after a double colon, e.g. the name of
+     * a module's variable (shapes::area) or of an enum's variant (Shape::Circle). Returns the names
+     * and their span
+     */
+    fn parse_path(&mut self, first: &Token) -> Result<(Vec<String>, Span), ParseError> {
+        let mut path = vec![first.value.clone()];
+        let mut span = first.span;
+        while self.peek_type() == Some(TokenType::DoubleColon) {
+            self.next_token()?;
+            let segment = self.next_token()?;
+            expect_token_type(segment, TokenType::Variable)?;
+            path.push(segment.value.clone());
+            span = span.to(segment.span);
         }
-        x => Err(ParseError::InvalidExpression(x.clone())),
+        Ok((path, span))
     }
+
+    /*
+     * Fields are separated by commas, and a comma is allowed after the last field
+     */
+    fn parse_struct_literal(&mut self, name: String, span: Span) -> Result<Expression, ParseError> {
+        self.next_token()?;
+        let mut fields = Vec::new();
+        while self.peek_type() != Some(TokenType::RBrace) {
+            let field = self.next_token()?;
+            expect_token_type(field, TokenType::Variable)?;
+            expect_token_type(self.next_token()?, TokenType::Colon)?;
+            fields.push((field.value.clone(), self.parse_boolean_expr()?));
+            if self.peek_type() != Some(TokenType::Comma) {
+                break;
+            }
+            self.next_token()?;
+        }
+        let close = self.next_token()?;
+        expect_token_type(close, TokenType::RBrace)?;
+
+        Ok(Expression::new(
+            ExpressionKind::StructLiteral(name, fields),
+            span.to(close.span),
+        ))
+    }
+
+    fn parse_variant_literal(
+        &mut self,
+        name: String,
+        variant: String,
+        span: Span,
+    ) -> Result<Expression, ParseError> {
+        let (values, end) = match self.peek_type() {
+            Some(TokenType::LParen) => self.parse_parenthesised(|x| x.parse_boolean_expr())?,
+            _ => (Vec::new(), span),
+        };
+
+        Ok(Expression::new(
+            ExpressionKind::VariantLiteral(name, variant, values),
+            span.to(end),
+        ))
+    }
+
+    /*
+     * The value matched on is found like the condition of an if statement, so it is every token up
+     * to the opening brace of the arms. Arms are separated by commas, and a comma is allowed after
+     * the last arm
+     */
+    fn parse_match_expr(&mut self, match_token: &Token) -> Result<Expression, ParseError> {
+        let mut brace_idx = self.idx - 1;
+        let scrutinee = parse_condition(self.tokens, &mut brace_idx)?;
+        self.idx = brace_idx + 1;
+
+        let mut arms = Vec::new();
+        while self.peek_type() != Some(TokenType::RBrace) {
+            let pattern = self.parse_pattern()?;
+            expect_token_type(self.next_token()?, TokenType::Arrow)?;
+            let body = self.parse_boolean_expr()?;
+            arms.push(MatchArm { pattern, body });
+            if self.peek_type() != Some(TokenType::Comma) {
+                break;
+            }
+            self.next_token()?;
+        }
+        let close = self.next_token()?;
+        expect_token_type(close, TokenType::RBrace)?;
+
+        Ok(Expression::new(
+            ExpressionKind::MatchExpr(Box::new(scrutinee), arms),
+            match_token.span.to(close.span),
+        ))
+    }
+
+    fn parse_pattern(&mut self) -> Result<Pattern, ParseError> {
+        let name = self.next_token()?;
+        if name.t_type == TokenType::Variable && name.value == "_" {
+            return Ok(Pattern {
+                kind: PatternKind::Wildcard,
+                span: name.span,
+            });
+        }
+        if name.t_type != TokenType::Variable || self.peek_type() != Some(TokenType::DoubleColon) {
+            return Err(ParseError::InvalidPattern(name.clone()));
+        }
+        let (mut path, span) = self.parse_path(name)?;
+        let variant = path.pop().unwrap_or_default();
+        let (bindings, end) = match self.peek_type() {
+            Some(TokenType::LParen) => self.parse_parenthesised(|x| {
+                let binding = x.next_token()?;
+                expect_token_type(binding, TokenType::Variable)?;
+                Ok((binding.value.clone(), binding.span))
+            })?,
+            _ => (Vec::new(), span),
+        };
+
+        Ok(Pattern {
+            kind: PatternKind::Variant(path.join("::"), variant, bindings),
+            span: span.to(end),
+        })
+    }
+
+    /*
+     * Parses a list of at least one item separated by commas within parentheses, starting from the
+     * opening parenthesis. Returns the items and the span of the closing parenthesis
+     */
+    fn parse_parenthesised<T>(
+        &mut self,
+        mut parse_item: impl FnMut(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<(Vec<T>, Span), ParseError> {
+        self.next_token()?;
+        let mut items = Vec::new();
+        loop {
+            items.push(parse_item(self)?);
+            let next = self.next_token()?;
+            match next.t_type {
+                TokenType::Comma => {}
+                TokenType::RParen => return Ok((items, next.span)),
+                _ => return Err(ParseError::UnexpectedToken(next.clone(), TokenType::RParen)),
+            }
+        }
+    }
+}
+
+fn tokens_span(tokens: &[Token]) -> Span {
+    tokens[0].span.to(tokens[tokens.len() - 1].span)
 }
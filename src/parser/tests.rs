@@ -4,15 +4,19 @@ use crate::lexer;
 #[test]
 fn test_valid_declaration_statement() {
     let statement = "int x = 10;";
-    let tokens = lexer::parse_text(&statement).unwrap();
-    let program = parse_program(tokens).unwrap();
+    let tokens = lexer::parse_text(statement).unwrap();
+    let program = parse_module(tokens).unwrap().program;
 
     assert!(program.statements.len() == 1);
     match &program.statements[0] {
-        Statement::DeclarationStatement { v_name, expr } => {
+        Statement::DeclarationStatement {
+            v_name,
+            expr: Some(expr),
+            ..
+        } => {
             assert_eq!(*v_name, String::from("x"));
-            match expr {
-                Expression::ValExpr(x) => assert_eq!(*x, String::from("10")),
+            match &expr.kind {
+                ExpressionKind::ValExpr(x) => assert_eq!(*x, String::from("10")),
                 x => panic!("Unexpected expression: {}", x),
             }
         }
@@ -23,14 +27,19 @@ fn test_valid_declaration_statement() {
 #[test]
 fn test_valid_assignment_statement() {
     let statement = "int x = 10;\nx = 20;";
-    let tokens = lexer::parse_text(&statement).unwrap();
-    let program = parse_program(tokens).unwrap();
+    let tokens = lexer::parse_text(statement).unwrap();
+    let program = parse_module(tokens).unwrap().program;
 
     assert!(program.statements.len() == 2);
     match &program.statements[0] {
         Statement::DeclarationStatement {
             v_name,
-            expr: Expression::ValExpr(x),
+            expr:
+                Some(Expression {
+                    kind: ExpressionKind::ValExpr(x),
+                    ..
+                }),
+            ..
         } => {
             assert_eq!(*v_name, String::from("x"));
             assert_eq!(*x, String::from("10"));
@@ -39,8 +48,17 @@ fn test_valid_assignment_statement() {
     }
     match &program.statements[1] {
         Statement::AssignmentStatement {
-            v_name,
-            expr: Expression::ValExpr(x),
+            target:
+                Expression {
+                    kind: ExpressionKind::VarExpr(v_name),
+                    ..
+                },
+            expr:
+                Expression {
+                    kind: ExpressionKind::ValExpr(x),
+                    ..
+                },
+            ..
         } => {
             assert_eq!(*v_name, String::from("x"));
             assert_eq!(*x, String::from("20"));
@@ -52,15 +70,19 @@ fn test_valid_assignment_statement() {
 #[test]
 fn test_valid_variable_expression() {
     let statement = "int x = 10;int y = x;";
-    let tokens = lexer::parse_text(&statement).unwrap();
-    let program = parse_program(tokens).unwrap();
+    let tokens = lexer::parse_text(statement).unwrap();
+    let program = parse_module(tokens).unwrap().program;
 
     assert!(program.statements.len() == 2);
     match &program.statements[1] {
-        Statement::DeclarationStatement { v_name, expr } => {
+        Statement::DeclarationStatement {
+            v_name,
+            expr: Some(expr),
+            ..
+        } => {
             assert_eq!(*v_name, String::from("y"));
-            match expr {
-                Expression::VarExpr(x) => assert_eq!(*x, String::from("x")),
+            match &expr.kind {
+                ExpressionKind::VarExpr(x) => assert_eq!(*x, String::from("x")),
                 x => panic!("Unexpected expression: {}", x),
             }
         }
@@ -71,32 +93,38 @@ fn test_valid_variable_expression() {
 #[test]
 fn test_valid_arithmetic_expression() {
     let statement = "int x = 10 + 8 - 4;";
-    let tokens = lexer::parse_text(&statement).unwrap();
-    let program = parse_program(tokens).unwrap();
+    let tokens = lexer::parse_text(statement).unwrap();
+    let program = parse_module(tokens).unwrap().program;
 
     assert!(program.statements.len() == 1);
     match &program.statements[0] {
-        Statement::DeclarationStatement { v_name, expr } => {
+        Statement::DeclarationStatement {
+            v_name,
+            expr: Some(expr),
+            ..
+        } => {
             assert_eq!(*v_name, String::from("x"));
-            if let Expression::ArithmeticExpr(x, op, y) = expr.clone() {
-                if let Expression::ValExpr(v) = *x {
-                    assert_eq!(v, String::from("10"));
-                    assert_eq!(op, Operator::Add);
-                    if let Expression::ArithmeticExpr(a, op2, b) = *y {
-                        if let Expression::ValExpr(c) = *a {
-                            assert_eq!(c, String::from("8"));
-                            assert_eq!(op2, Operator::Sub);
-                        } else {
-                            panic!("Expected Value expression, but got {}", a);
-                        }
-                        if let Expression::ValExpr(d) = *b {
-                            assert_eq!(d, String::from("4"));
-                        } else {
-                            panic!("Expected Value expression, but got {}", b);
-                        }
+            if let ExpressionKind::ArithmeticExpr(x, op, y) = expr.kind.clone() {
+                assert_eq!(op, Operator::Sub);
+                if let ExpressionKind::ArithmeticExpr(a, op2, b) = x.kind {
+                    if let ExpressionKind::ValExpr(c) = a.kind.clone() {
+                        assert_eq!(c, String::from("10"));
+                        assert_eq!(op2, Operator::Add);
+                    } else {
+                        panic!("Expected Value expression, but got {}", a);
+                    }
+                    if let ExpressionKind::ValExpr(d) = b.kind.clone() {
+                        assert_eq!(d, String::from("8"));
+                    } else {
+                        panic!("Expected Value expression, but got {}", b);
                     }
                 } else {
-                    panic!("Expected Value expression, but got {}", x);
+                    panic!("Expected Arithmetic expression, but got {}", x);
+                }
+                if let ExpressionKind::ValExpr(v) = y.kind.clone() {
+                    assert_eq!(v, String::from("4"));
+                } else {
+                    panic!("Expected Value expression, but got {}", y);
                 }
             } else {
                 panic!("Expected Arithmetic expression, but got {}", expr);
@@ -107,88 +135,818 @@ fn test_valid_arithmetic_expression() {
 }
 
 #[test]
-fn test_declaration_should_error_for_redefined_var() {
-    let statements = "int x = 20;int x = 100;";
-    let tokens = lexer::parse_text(&statements).unwrap();
-    let e = parse_program(tokens).unwrap_err();
+fn test_statement_and_expression_spans() {
+    let statement = "int x = 10;\nx = x + 2;";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let program = parse_module(tokens).unwrap().program;
+
+    assert_eq!(program.statements[0].span(), Span::new(0, 10));
+    assert_eq!(program.statements[1].span(), Span::new(12, 21));
+    match &program.statements[1] {
+        Statement::AssignmentStatement { expr, .. } => {
+            assert_eq!(expr.span, Span::new(16, 21));
+            if let ExpressionKind::ArithmeticExpr(x, _, y) = &expr.kind {
+                assert_eq!(x.span, Span::new(16, 17));
+                assert_eq!(y.span, Span::new(20, 21));
+            } else {
+                panic!("Expected Arithmetic expression, but got {}", expr);
+            }
+        }
+        x => panic!("Unexpected statement: {}", x),
+    }
+}
+
+#[test]
+fn test_should_error_for_empty_statement() {
+    let statement = "int x = 10;;";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let e = parse_module(tokens).unwrap_err();
+
     assert_eq!(
         e.to_string(),
-        String::from("Attempted to redeclare variable: [(Variable: x), Line: 1, Col: 16]")
+        String::from("Found empty statement after token [(Semicolon: ;), Line: 1, Col: 12]")
     );
 }
 
 #[test]
-fn test_assignment_should_error_for_undefined_var() {
-    let statement = "x = 20;";
-    let tokens = lexer::parse_text(&statement).unwrap();
-    let e = parse_program(tokens).unwrap_err();
+fn test_should_error_for_unrecognised_statement() {
+    let statement = "= is not a statement;";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let e = parse_module(tokens).unwrap_err();
+
     assert_eq!(
         e.to_string(),
-        String::from("Undefined variable: [(Variable: x), Line: 1, Col: 1]")
+        String::from(
+            "Unable to parse statement starting from token [(Equals: =), Line: 1, Col: 1]"
+        )
     );
 }
 
 #[test]
-fn test_should_error_for_undefined_var_in_expr() {
-    let statement = "int x = y;";
-    let tokens = lexer::parse_text(&statement).unwrap();
-    let e = parse_program(tokens).unwrap_err();
+fn test_should_error_for_unexpected_token() {
+    let statement = "int = 10;";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let e = parse_module(tokens).unwrap_err();
+
     assert_eq!(
         e.to_string(),
-        String::from("Undefined variable: [(Variable: y), Line: 1, Col: 9]")
+        String::from(
+            "Encountered unexpected token: [(Equals: =), Line: 1, Col: 5], expected token with type: Variable"
+        )
     );
 }
 
 #[test]
-fn test_should_error_for_empty_statement() {
-    let statement = "int x = 10;;";
-    let tokens = lexer::parse_text(&statement).unwrap();
-    let e = parse_program(tokens).unwrap_err();
+fn test_should_error_for_missing_semicolon() {
+    let statement = "int x = 10 int y = 20;";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let e = parse_module(tokens).unwrap_err();
 
     assert_eq!(
         e.to_string(),
-        String::from("Found empty statement after token [(Semicolon: ;), Line: 1, Col: 12]")
+        String::from(
+            "Unable to parse expression starting from token [(Value: 10), Line: 1, Col: 9]"
+        )
     );
 }
 
 #[test]
-fn test_should_error_for_unrecognised_statement() {
-    let statement = "= is not a statement;";
-    let tokens = lexer::parse_text(&statement).unwrap();
-    let e = parse_program(tokens).unwrap_err();
+fn test_valid_block_statement() {
+    let statement = "int x = 10;\n{\n  int y = x;\n  {\n    y = 2;\n  }\n}";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let program = parse_module(tokens).unwrap().program;
+
+    assert!(program.statements.len() == 2);
+    match &program.statements[1] {
+        Statement::BlockStatement { statements, span } => {
+            assert_eq!(*span, Span::new(12, statement.len()));
+            assert!(statements.len() == 2);
+            match &statements[1] {
+                Statement::BlockStatement { statements, .. } => {
+                    assert!(statements.len() == 1);
+                }
+                x => panic!("Unexpected statement: {}", x),
+            }
+        }
+        x => panic!("Unexpected statement: {}", x),
+    }
+}
+
+#[test]
+fn test_should_parse_variables_without_checking_definitions() {
+    let statement = "x = y;";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let program = parse_module(tokens).unwrap().program;
+
+    assert!(program.statements.len() == 1);
+}
+
+#[test]
+fn test_should_error_for_unclosed_block() {
+    let statement = "{ int x = 10;";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let e = parse_module(tokens).unwrap_err();
 
     assert_eq!(
         e.to_string(),
         String::from(
-            "Unable to parse statement starting from token [(Equals: =), Line: 1, Col: 1]"
+            "No closing brace found for block starting at: [(Left Brace: {), Line: 1, Col: 1]"
         )
     );
 }
 
 #[test]
-fn test_should_error_for_unexpected_token() {
-    let statement = "int = 10;";
-    let tokens = lexer::parse_text(&statement).unwrap();
-    let e = parse_program(tokens).unwrap_err();
+fn test_should_error_for_unmatched_brace() {
+    let statement = "int x = 10;\n}";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let e = parse_module(tokens).unwrap_err();
 
     assert_eq!(
         e.to_string(),
         String::from(
-            "Unable to parse statement starting from token [(Integer: int), Line: 1, Col: 1]"
+            "Found closing brace without a matching opening brace: [(Right Brace: }), Line: 2, Col: 1]"
         )
     );
 }
 
 #[test]
-fn test_should_error_for_missing_semicolon() {
-    let statement = "int x = 10 int y = 20;";
-    let tokens = lexer::parse_text(&statement).unwrap();
-    let e = parse_program(tokens).unwrap_err();
+fn test_should_error_for_missing_semicolon_before_block() {
+    let statement = "int x = 10 { int y = 2; }";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let e = parse_module(tokens).unwrap_err();
 
     assert_eq!(
         e.to_string(),
         String::from(
-            "Unable to parse expression starting from token [(Value: 10), Line: 1, Col: 9]"
+            "No semicolon found after statement starting with: [(Type: int), Line: 1, Col: 1]"
+        )
+    );
+}
+
+#[test]
+fn test_valid_cast_and_boolean_expression() {
+    let statement = "bool x = y as i32 < 5 + z;";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let program = parse_module(tokens).unwrap().program;
+
+    match &program.statements[0] {
+        Statement::DeclarationStatement {
+            ty,
+            expr: Some(expr),
+            ..
+        } => {
+            assert_eq!(*ty, Type::Bool);
+            assert_eq!(expr.to_string(), "y as i32 < 5 + z");
+            if let ExpressionKind::BooleanExpr(x, op, y) = &expr.kind {
+                assert_eq!(*op, Operator::LessThan);
+                assert!(matches!(x.kind, ExpressionKind::CastExpr(_, Type::I32)));
+                assert!(matches!(y.kind, ExpressionKind::ArithmeticExpr(..)));
+            } else {
+                panic!("Expected Boolean expression, but got {}", expr);
+            }
+        }
+        x => panic!("Unexpected statement: {}", x),
+    }
+}
+
+#[test]
+fn test_should_parse_all_type_keywords() {
+    let test_cases = [
+        ("i8", Type::I8),
+        ("i16", Type::I16),
+        ("i32", Type::I32),
+        ("i64", Type::I64),
+        ("int", Type::I64),
+        ("u8", Type::U8),
+        ("u16", Type::U16),
+        ("u32", Type::U32),
+        ("u64", Type::U64),
+        ("f32", Type::F32),
+        ("f64", Type::F64),
+        ("bool", Type::Bool),
+        ("char", Type::Char),
+    ];
+
+    for (keyword, expected) in test_cases {
+        let statement = format!("{} x = 1;", keyword);
+        let tokens = lexer::parse_text(&statement).unwrap();
+        let program = parse_module(tokens).unwrap().program;
+        match &program.statements[0] {
+            Statement::DeclarationStatement { ty, .. } => assert_eq!(*ty, expected),
+            x => panic!("Unexpected statement: {}", x),
+        }
+    }
+}
+
+#[test]
+fn test_operators_of_the_same_precedence_group_to_the_left() {
+    let test_cases = [
+        ("a / b / c", Operator::Div, Operator::Div),
+        ("a / b * c", Operator::Mul, Operator::Div),
+        ("a - b - c", Operator::Sub, Operator::Sub),
+        ("a - b + c", Operator::Add, Operator::Sub),
+    ];
+    for (input, outer, inner) in test_cases {
+        let tokens = lexer::parse_text(&format!("int x = {};", input)).unwrap();
+        let program = parse_module(tokens).unwrap().program;
+        match &program.statements[0] {
+            Statement::DeclarationStatement {
+                expr: Some(expr), ..
+            } => match &expr.kind {
+                ExpressionKind::ArithmeticExpr(x, op, y) => {
+                    assert_eq!(*op, outer, "{}", input);
+                    assert_eq!(y.to_string(), "c");
+                    assert!(
+                        matches!(&x.kind, ExpressionKind::ArithmeticExpr(a, op, b) if *op == inner && a.to_string() == "a" && b.to_string() == "b"),
+                        "{}",
+                        input
+                    );
+                }
+                x => panic!("Expected Arithmetic expression, but got {}", x),
+            },
+            x => panic!("Unexpected statement: {}", x),
+        }
+    }
+}
+
+#[test]
+fn test_should_error_for_missing_cast_type() {
+    let statement = "int x = y as;";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let e = parse_module(tokens).unwrap_err();
+
+    assert_eq!(
+        e.to_string(),
+        String::from("Unable to parse expression starting from token [(As: as), Line: 1, Col: 11]")
+    );
+}
+
+#[test]
+fn test_parse_literals() {
+    let test_cases = [
+        ("10", Some(Literal::Int(10))),
+        ("2.5", Some(Literal::Float(2.5))),
+        ("true", Some(Literal::Bool(true))),
+        ("'a'", Some(Literal::Char('a'))),
+        ("'\\n'", Some(Literal::Char('\n'))),
+        ("\"hi\"", Some(Literal::Str(String::from("hi")))),
+        ("'\\q'", None),
+        ("99999999999999999999", None),
+    ];
+
+    for (input, expected) in test_cases {
+        assert_eq!(Literal::parse(input), expected);
+    }
+}
+
+#[test]
+fn test_multiplicative_operators_bind_tighter_than_additive() {
+    let statement = "int x = a * b + c / d - e;";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let program = parse_module(tokens).unwrap().program;
+
+    match &program.statements[0] {
+        Statement::DeclarationStatement {
+            expr: Some(expr), ..
+        } => {
+            if let ExpressionKind::ArithmeticExpr(x, op, y) = &expr.kind {
+                assert_eq!(*op, Operator::Sub);
+                assert!(matches!(y.kind, ExpressionKind::VarExpr(_)));
+                if let ExpressionKind::ArithmeticExpr(a, op2, b) = &x.kind {
+                    assert_eq!(*op2, Operator::Add);
+                    assert!(matches!(
+                        a.kind,
+                        ExpressionKind::ArithmeticExpr(_, Operator::Mul, _)
+                    ));
+                    assert!(matches!(
+                        b.kind,
+                        ExpressionKind::ArithmeticExpr(_, Operator::Div, _)
+                    ));
+                } else {
+                    panic!("Expected Arithmetic expression, but got {}", x);
+                }
+            } else {
+                panic!("Expected Arithmetic expression, but got {}", expr);
+            }
+        }
+        x => panic!("Unexpected statement: {}", x),
+    }
+}
+
+#[test]
+fn test_declarations_are_immutable_unless_marked_mut() {
+    let statement = "int x = 1;\nmut u8 y = 2;";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let program = parse_module(tokens).unwrap().program;
+
+    let expected = [("x", Type::I64, false, 0..9), ("y", Type::U8, true, 11..23)];
+    for (statement, (name, expected_ty, expected_mutable, range)) in
+        program.statements.iter().zip(expected)
+    {
+        match statement {
+            Statement::DeclarationStatement {
+                v_name,
+                ty,
+                mutable,
+                span,
+                ..
+            } => {
+                assert_eq!(v_name, name);
+                assert_eq!(*ty, expected_ty);
+                assert_eq!(*mutable, expected_mutable);
+                assert_eq!(*span, Span::new(range.start, range.end));
+            }
+            x => panic!("Unexpected statement: {}", x),
+        }
+    }
+}
+
+#[test]
+fn test_should_error_for_mut_without_type() {
+    let statement = "mut x = 1 + 2;";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let e = parse_module(tokens).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from(
+            "Encountered unexpected token: [(Variable: x), Line: 1, Col: 5], expected token with type: Type"
+        )
+    );
+}
+
+#[test]
+fn test_declarations_without_a_value() {
+    let statement = "int x;\nmut bool y;\nint z = 1;";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let program = parse_module(tokens).unwrap().program;
+
+    let statements: Vec<String> = program.statements.iter().map(|x| x.to_string()).collect();
+    assert_eq!(
+        statements,
+        vec![
+            "Declaring x",
+            "Declaring mutable y",
+            "Declaring z with value 1"
+        ]
+    );
+    assert_eq!(program.statements[0].span(), Span::new(0, 5));
+}
+
+#[test]
+fn test_should_error_for_declaration_missing_value() {
+    let statement = "int x =;";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let e = parse_module(tokens).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from(
+            "Unable to parse statement starting from token [(Equals: =), Line: 1, Col: 7]"
+        )
+    );
+
+    let statement = "int x 1;";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let e = parse_module(tokens).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from(
+            "Encountered unexpected token: [(Value: 1), Line: 1, Col: 7], expected token with type: Equals"
+        )
+    );
+}
+
+#[test]
+fn test_valid_const_statement() {
+    let statement = "const u8 LIMIT = 10 * 2;";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let program = parse_module(tokens).unwrap().program;
+
+    match &program.statements[0] {
+        Statement::ConstStatement {
+            v_name, ty, expr, ..
+        } => {
+            assert_eq!(v_name, "LIMIT");
+            assert_eq!(*ty, Type::U8);
+            assert_eq!(expr.to_string(), "10 * 2");
+        }
+        x => panic!("Unexpected statement: {}", x),
+    }
+}
+
+#[test]
+fn test_literals_display_in_parseable_form() {
+    let literals = [
+        Literal::Int(-42),
+        Literal::Float(1e-7),
+        Literal::Float(2.0),
+        Literal::Char('\''),
+        Literal::Char('\n'),
+        Literal::Bool(true),
+    ];
+    for literal in literals {
+        assert_eq!(Literal::parse(&literal.to_string()), Some(literal));
+    }
+}
+
+#[test]
+fn test_valid_if_else_statements() {
+    let statement = "if x < 5 {\n  x = 1;\n} else if x < 10 {\n  x = 2;\n} else {\n  x = 3;\n}";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let program = parse_module(tokens).unwrap().program;
+
+    assert_eq!(program.statements.len(), 1);
+    assert_eq!(program.statements[0].span(), Span::new(0, statement.len()));
+    match &program.statements[0] {
+        Statement::IfStatement {
+            condition,
+            then_block,
+            else_block: Some(else_block),
+            ..
+        } => {
+            assert_eq!(condition.to_string(), "x < 5");
+            assert!(matches!(**then_block, Statement::BlockStatement { .. }));
+            assert!(matches!(
+                **else_block,
+                Statement::IfStatement {
+                    else_block: Some(_),
+                    ..
+                }
+            ));
+        }
+        x => panic!("Unexpected statement: {}", x),
+    }
+}
+
+#[test]
+fn test_valid_while_statement() {
+    let statement = "while i < 10 {\n  i = i + 1;\n}\nint y = i;";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let program = parse_module(tokens).unwrap().program;
+
+    assert_eq!(program.statements.len(), 2);
+    match &program.statements[0] {
+        Statement::WhileStatement {
+            condition, body, ..
+        } => {
+            assert_eq!(condition.to_string(), "i < 10");
+            assert_eq!(
+                body.to_string(),
+                "Block:\nAssigning i to value i + 1\nEnd Block"
+            );
+        }
+        x => panic!("Unexpected statement: {}", x),
+    }
+}
+
+#[test]
+fn test_should_error_for_invalid_control_flow() {
+    let test_cases = [
+        (
+            "if x < 5;",
+            "Encountered unexpected token: [(Semicolon: ;), Line: 1, Col: 9], expected token with type: Left Brace",
+        ),
+        (
+            "while {\n}",
+            "Unable to parse statement starting from token [(While: while), Line: 1, Col: 1]",
+        ),
+        (
+            "if true",
+            "No block found after: [(If: if), Line: 1, Col: 1]",
+        ),
+        (
+            "if true {\n} else",
+            "No block found after: [(Else: else), Line: 2, Col: 3]",
+        ),
+        (
+            "else {\n}",
+            "Unable to parse statement starting from token [(Else: else), Line: 1, Col: 1]",
+        ),
+    ];
+    for (statement, expected) in test_cases {
+        let tokens = lexer::parse_text(statement).unwrap();
+        let e = parse_module(tokens).unwrap_err();
+        assert_eq!(e.to_string(), expected);
+    }
+}
+
+#[test]
+fn test_wrapping_and_saturating_operators() {
+    let statement = "int x = a *% b +| c -% d;";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let program = parse_module(tokens).unwrap().program;
+
+    match &program.statements[0] {
+        Statement::DeclarationStatement {
+            expr: Some(expr), ..
+        } => {
+            assert_eq!(expr.to_string(), "a *% b +| c -% d");
+            if let ExpressionKind::ArithmeticExpr(x, op, y) = &expr.kind {
+                assert_eq!(*op, Operator::WrappingSub);
+                assert_eq!(op.overflow_mode(), OverflowMode::Wrapping);
+                assert!(matches!(y.kind, ExpressionKind::VarExpr(_)));
+                if let ExpressionKind::ArithmeticExpr(a, op2, _) = &x.kind {
+                    assert_eq!(*op2, Operator::SaturatingAdd);
+                    assert_eq!(op2.overflow_mode(), OverflowMode::Saturating);
+                    assert_eq!(op2.base_operator(), Operator::Add);
+                    assert!(matches!(
+                        a.kind,
+                        ExpressionKind::ArithmeticExpr(_, Operator::WrappingMul, _)
+                    ));
+                } else {
+                    panic!("Expected Arithmetic expression, but got {}", x);
+                }
+            } else {
+                panic!("Expected Arithmetic expression, but got {}", expr);
+            }
+        }
+        x => panic!("Unexpected statement: {}", x),
+    }
+}
+
+#[test]
+fn test_array_types_and_index_expressions() {
+    let statement = "mut i64[3][4] a;a[i][j + 1] = a[0][j];";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let program = parse_module(tokens).unwrap().program;
+
+    match &program.statements[0] {
+        Statement::DeclarationStatement { ty, expr: None, .. } => {
+            assert_eq!(
+                *ty,
+                Type::Array(Box::new(Type::Array(Box::new(Type::I64), 4)), 3)
+            );
+            assert_eq!(ty.to_string(), "i64[3][4]");
+            assert!(ty.is_aggregate());
+        }
+        x => panic!("Unexpected statement: {}", x),
+    }
+    match &program.statements[1] {
+        Statement::AssignmentStatement { target, expr, .. } => {
+            assert_eq!(target.to_string(), "a[i][j + 1]");
+            assert_eq!(target.root_variable(), Some("a"));
+            assert_eq!(expr.to_string(), "a[0][j]");
+        }
+        x => panic!("Unexpected statement: {}", x),
+    }
+}
+
+#[test]
+fn test_should_error_for_invalid_arrays() {
+    let tokens = lexer::parse_text("i64[0] a;").unwrap();
+    let e = parse_module(tokens).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from(
+            "Invalid array length: [(Value: 0), Line: 1, Col: 5], expected a positive integer"
+        )
+    );
+
+    let tokens = lexer::parse_text("a[0] + 1 = 2;").unwrap();
+    let e = parse_module(tokens).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from(
+            "Unable to assign to expression starting from token [(Variable: a), Line: 1, Col: 1]"
+        )
+    );
+}
+
+#[test]
+fn test_struct_definitions_literals_and_field_access() {
+    let statement = "Point p = Point { x: 1, y: a.b[2], };\nstruct Point { i64 x; Inner[2] y; }\np.y[0].z = p.x;";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let program = parse_module(tokens).unwrap().program;
+
+    match &program.statements[0] {
+        Statement::DeclarationStatement {
+            ty,
+            expr: Some(expr),
+            ..
+        } => {
+            assert_eq!(*ty, Type::Struct(String::from("Point")));
+            assert_eq!(expr.to_string(), "Point { x: 1, y: a.b[2] }");
+        }
+        x => panic!("Unexpected statement: {}", x),
+    }
+    assert_eq!(
+        program.statements[1].to_string(),
+        "Defining struct Point with fields x: i64, y: Inner[2]"
+    );
+    match &program.statements[2] {
+        Statement::AssignmentStatement { target, .. } => {
+            assert_eq!(target.to_string(), "p.y[0].z");
+            assert_eq!(target.root_variable(), Some("p"));
+        }
+        x => panic!("Unexpected statement: {}", x),
+    }
+}
+
+#[test]
+fn test_should_error_for_struct_defined_in_block() {
+    let tokens = lexer::parse_text("if true { struct P { i64 x; } }").unwrap();
+    let e = parse_module(tokens).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from(
+            "Structs can only be defined at the top level of a program, found [(Struct: struct), Line: 1, Col: 11]"
+        )
+    );
+}
+
+#[test]
+fn test_should_error_for_empty_struct_field() {
+    let tokens = lexer::parse_text("struct S { ; }").unwrap();
+    let e = parse_module(tokens).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Found empty statement after token [(Semicolon: ;), Line: 1, Col: 12]")
+    );
+
+    assert!(matches!(parse_type(&[]), Err(ParseError::MissingType)));
+}
+
+#[test]
+fn test_pointer_types_address_of_and_dereference() {
+    let statement = "**int pp = &p;\n*int[2] ps;\n*pp = *q + 1;\nbool b = p == null;";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let program = parse_module(tokens).unwrap().program;
+
+    match &program.statements[0] {
+        Statement::DeclarationStatement {
+            ty,
+            expr: Some(expr),
+            ..
+        } => {
+            assert_eq!(
+                *ty,
+                Type::Pointer(Box::new(Type::Pointer(Box::new(Type::I64))))
+            );
+            assert_eq!(expr.to_string(), "&p");
+        }
+        x => panic!("Unexpected statement: {}", x),
+    }
+    match &program.statements[1] {
+        Statement::DeclarationStatement { ty, .. } => assert_eq!(ty.to_string(), "*i64[2]"),
+        x => panic!("Unexpected statement: {}", x),
+    }
+    match &program.statements[2] {
+        Statement::AssignmentStatement { target, expr, .. } => {
+            assert_eq!(target.to_string(), "*pp");
+            assert_eq!(target.root_variable(), None);
+            assert_eq!(expr.to_string(), "*q + 1");
+        }
+        x => panic!("Unexpected statement: {}", x),
+    }
+    assert_eq!(
+        program.statements[3].to_string(),
+        "Declaring b with value p == null"
+    );
+}
+
+#[test]
+fn test_enum_definitions_variant_literals_and_match() {
+    let statement = "enum Shape { Circle(f64); Rect(i64, i64); Empty; }\nShape s = Shape::Rect(1, a + 2);\ni64 n = match s { Shape::Empty => 0, _ => 1, };\nmatch s { Shape::Rect(w, _) => { n = w; } Shape::Circle(_) => {} _ => {} }";
+    let tokens = lexer::parse_text(statement).unwrap();
+    let program = parse_module(tokens).unwrap().program;
+
+    assert_eq!(
+        program.statements[0].to_string(),
+        "Defining enum Shape with variants Circle(f64), Rect(i64, i64), Empty"
+    );
+    match &program.statements[1] {
+        Statement::DeclarationStatement {
+            ty,
+            expr: Some(expr),
+            ..
+        } => {
+            assert_eq!(*ty, Type::Struct(String::from("Shape")));
+            assert_eq!(expr.to_string(), "Shape::Rect(1, a + 2)");
+        }
+        x => panic!("Unexpected statement: {}", x),
+    }
+    match &program.statements[2] {
+        Statement::DeclarationStatement {
+            expr: Some(expr), ..
+        } => assert_eq!(expr.to_string(), "match s { Shape::Empty => 0, _ => 1 }"),
+        x => panic!("Unexpected statement: {}", x),
+    }
+    match &program.statements[3] {
+        Statement::MatchStatement { arms, .. } => {
+            assert_eq!(arms.len(), 3);
+            assert_eq!(arms[0].pattern.to_string(), "Shape::Rect(w, _)");
+            assert_eq!(arms[0].pattern.bindings().len(), 1);
+            assert!(matches!(arms[2].pattern.kind, PatternKind::Wildcard));
+        }
+        x => panic!("Unexpected statement: {}", x),
+    }
+}
+
+#[test]
+fn test_should_error_for_invalid_enums_and_patterns() {
+    let tokens = lexer::parse_text("if true { enum E { A; } }").unwrap();
+    let e = parse_module(tokens).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from(
+            "Enums can only be defined at the top level of a program, found [(Enum: enum), Line: 1, Col: 11]"
+        )
+    );
+
+    let tokens = lexer::parse_text("enum E { A(); }").unwrap();
+    assert!(parse_module(tokens).is_err());
+
+    let tokens = lexer::parse_text("match e { x => {} }").unwrap();
+    let e = parse_module(tokens).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from(
+            "Unable to parse pattern starting from token [(Variable: x), Line: 1, Col: 11]"
         )
     );
 }
+
+#[test]
+fn test_imports_and_public_items() {
+    let tokens = lexer::parse_text(
+        "import shapes;\nmod geo::scale;\npub struct P { int x; }\nint y = 1;\npub const int Z = 2;",
+    )
+    .unwrap();
+    let module = parse_module(tokens).unwrap();
+    let paths: Vec<Vec<String>> = module.imports.iter().map(|x| x.path.clone()).collect();
+    assert_eq!(paths, vec![vec!["shapes"], vec!["geo", "scale"]]);
+    assert_eq!(module.imports[1].name(), "scale");
+    assert_eq!(module.program.statements.len(), 3);
+    assert_eq!(
+        module.public,
+        HashSet::from([String::from("P"), String::from("Z")])
+    );
+}
+
+#[test]
+fn test_paths_to_items_of_modules() {
+    let tokens = lexer::parse_text(
+        "shapes::Point p = shapes::Point { x: shapes::xs[0] };\nshapes::total = shapes::Shape::Rect(1);\nmatch s { shapes::Shape::Rect(w) => {} }",
+    )
+    .unwrap();
+    let program = parse_module(tokens).unwrap().program;
+    match &program.statements[0] {
+        Statement::DeclarationStatement {
+            ty, expr: Some(x), ..
+        } => {
+            assert_eq!(*ty, Type::Struct(String::from("shapes::Point")));
+            assert_eq!(x.to_string(), "shapes::Point { x: shapes::xs[0] }");
+        }
+        x => panic!("Unexpected statement: {}", x),
+    }
+    match &program.statements[1] {
+        Statement::AssignmentStatement { target, expr, .. } => {
+            assert_eq!(
+                target.kind,
+                ExpressionKind::VarExpr(String::from("shapes::total"))
+            );
+            match &expr.kind {
+                ExpressionKind::VariantLiteral(name, variant, _) => {
+                    assert_eq!((name.as_str(), variant.as_str()), ("shapes::Shape", "Rect"))
+                }
+                x => panic!("Unexpected expression: {}", x),
+            }
+        }
+        x => panic!("Unexpected statement: {}", x),
+    }
+    match &program.statements[2] {
+        Statement::MatchStatement { arms, .. } => {
+            assert_eq!(arms[0].pattern.to_string(), "shapes::Shape::Rect(w)")
+        }
+        x => panic!("Unexpected statement: {}", x),
+    }
+}
+
+#[test]
+fn test_should_error_for_invalid_imports_and_public_items() {
+    let test_cases = [
+        (
+            "{ import shapes; }",
+            "Imports can only be defined at the top level of a program, found [(Import: import), Line: 1, Col: 3]",
+        ),
+        (
+            "while true { pub int x = 1; }",
+            "Public items can only be defined at the top level of a program, found [(Pub: pub), Line: 1, Col: 14]",
+        ),
+        (
+            "pub x = 1;",
+            "Unable to parse statement starting from token [(Pub: pub), Line: 1, Col: 1]",
+        ),
+        (
+            "import shapes::;",
+            "Unable to parse expression starting from token [(Double Colon: ::), Line: 1, Col: 14]",
+        ),
+        (
+            "import;",
+            "Unable to parse statement starting from token [(Import: import), Line: 1, Col: 1]",
+        ),
+    ];
+    for (input, expected) in test_cases {
+        let tokens = lexer::parse_text(input).unwrap();
+        assert_eq!(parse_module(tokens).unwrap_err().to_string(), expected);
+    }
+}
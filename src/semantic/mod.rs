@@ -0,0 +1,1466 @@
+/*
+* The semantic analysis pass walks the parsed program and checks that it is meaningful, e.g. that
+* every variable is declared before it is used. Symbols are tracked in a stack of scopes, a block
+* introduces a new scope and a declaration may shadow one from an enclosing scope, but a name can
+* only be declared once in the same scope. Variables are immutable unless declared with mut, so
+* assigning to one is reported along with where it was declared. Constants can never be assigned
+* to and their value must be an expression that only uses literals and other constants
+*
+* A variable can be declared without a value, as long as it is assigned before it is read on every
+* path through the program. An immutable variable declared this way can be assigned once, which
+* rules out assigning it inside a loop that the declaration is outside of
+*
+* Arrays are always initialised with every element set to zero, so they can be read without being
+* assigned first. An array can't be used as a value, only its elements can be read or assigned by
+* indexing it, and assigning to an element requires the array to be mutable
+*
+* A pointer (e.g. *int) holds the address of a value, taken with & and followed with *. Only places
+* can have their address taken, and only if writing to them would be allowed since the value can be
+* changed through the pointer, so the variable must be mutable and can't be a constant. Arrays can't
+* be pointed to as a whole, a pointer to an element is used instead. A field can be used directly on
+* a pointer to a struct. An integer can be added to or subtracted from a pointer to move it by that
+* many values, and subtracting two pointers gives the number of values between them. null is a
+* pointer to nothing, and like a numeric literal it takes on the pointer type expected by its context
+*
+* An enum value is one of its variants, each of which may carry values of its own (e.g.
+* Shape::Rect(1, 2)). The parser can't tell an enum from a struct when it is used as a type, so
+* named types are resolved to one or the other here. A match must be on an enum value and cover
+* every variant of it, either with an arm for each or with the wildcard _, and an arm which can
+* never be reached because earlier arms cover it is also an error. The values carried by a variant
+* are bound to immutable variables scoped to the arm. Every arm of a match expression must have the
+* same type as the first arm
+*
+* This pass is also responsible for type checking, every expression in the program is annotated
+* with its type. Numeric literals take on the type expected by their context (e.g. the declared
+* type of a variable, or the type of the other operand) and otherwise default to i64 or f64. The
+* wrapping and saturating arithmetic operators can only be used with integers
+*/
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    parser::{
+        EnumVariant, Expression, ExpressionKind, Literal, MatchArm, Operator, OverflowMode,
+        PatternKind, Program, Statement, StructField, Type,
+    },
+    source::{Located, SourceMap, Span},
+    token::TextLocation,
+};
+
+#[derive(Debug)]
+pub enum SemanticError {
+    UndefinedVariable {
+        v_name: String,
+        location: TextLocation,
+    },
+    RedeclaredVariable {
+        v_name: String,
+        location: TextLocation,
+        original_type: Type,
+        original_location: TextLocation,
+    },
+    ImmutableAssignment {
+        v_name: String,
+        location: TextLocation,
+        declared_location: TextLocation,
+    },
+    ConstantAssignment {
+        v_name: String,
+        location: TextLocation,
+        declared_location: TextLocation,
+    },
+    UnassignedVariable {
+        v_name: String,
+        location: TextLocation,
+        declared_location: TextLocation,
+    },
+    NonConstantExpression {
+        v_name: String,
+        location: TextLocation,
+    },
+    TypeMismatch {
+        expected: Type,
+        found: Type,
+        location: TextLocation,
+    },
+    InvalidOperand {
+        op: Operator,
+        ty: Type,
+        location: TextLocation,
+    },
+    InvalidCast {
+        from: Type,
+        to: Type,
+        location: TextLocation,
+    },
+    LiteralOutOfRange {
+        value: String,
+        ty: Type,
+        location: TextLocation,
+    },
+    UnsupportedLiteral {
+        value: String,
+        location: TextLocation,
+    },
+    ArrayValue {
+        ty: Type,
+        location: TextLocation,
+    },
+    InvalidIndex {
+        ty: Type,
+        location: TextLocation,
+    },
+    NotAnArray {
+        ty: Type,
+        location: TextLocation,
+    },
+    RedefinedType {
+        ty: Type,
+        location: TextLocation,
+        original_location: TextLocation,
+    },
+    RecursiveType {
+        ty: Type,
+        location: TextLocation,
+    },
+    UnknownType {
+        name: String,
+        location: TextLocation,
+    },
+    UnknownField {
+        ty: Type,
+        field: String,
+        location: TextLocation,
+    },
+    DuplicateField {
+        field: String,
+        location: TextLocation,
+    },
+    MissingField {
+        ty: Type,
+        field: String,
+        location: TextLocation,
+    },
+    AggregateConstant {
+        ty: Type,
+        location: TextLocation,
+    },
+    NotAddressable {
+        expr: String,
+        location: TextLocation,
+    },
+    ImmutableAddress {
+        v_name: String,
+        location: TextLocation,
+        declared_location: TextLocation,
+    },
+    ArrayAddress {
+        ty: Type,
+        location: TextLocation,
+    },
+    NotAPointer {
+        ty: Type,
+        location: TextLocation,
+    },
+    UntypedNull {
+        location: TextLocation,
+    },
+    NonConstantPointer {
+        location: TextLocation,
+    },
+    EmptyEnum {
+        name: String,
+        location: TextLocation,
+    },
+    DuplicateVariant {
+        variant: String,
+        location: TextLocation,
+    },
+    UnknownVariant {
+        ty: Type,
+        variant: String,
+        location: TextLocation,
+    },
+    PayloadCount {
+        variant: String,
+        expected: usize,
+        found: usize,
+        location: TextLocation,
+    },
+    NotAnEnum {
+        ty: Type,
+        location: TextLocation,
+    },
+    UnreachableArm {
+        pattern: String,
+        location: TextLocation,
+    },
+    NonExhaustiveMatch {
+        ty: Type,
+        missing: Vec<String>,
+        location: TextLocation,
+    },
+    NonConstantMatch {
+        location: TextLocation,
+    },
+}
+
+impl std::error::Error for SemanticError {}
+
+impl Located for SemanticError {
+    fn location_mut(&mut self) -> Option<&mut TextLocation> {
+        match self {
+            SemanticError::UndefinedVariable { location, .. }
+            | SemanticError::RedeclaredVariable { location, .. }
+            | SemanticError::ImmutableAssignment { location, .. }
+            | SemanticError::ConstantAssignment { location, .. }
+            | SemanticError::UnassignedVariable { location, .. }
+            | SemanticError::NonConstantExpression { location, .. }
+            | SemanticError::TypeMismatch { location, .. }
+            | SemanticError::InvalidOperand { location, .. }
+            | SemanticError::InvalidCast { location, .. }
+            | SemanticError::LiteralOutOfRange { location, .. }
+            | SemanticError::UnsupportedLiteral { location, .. }
+            | SemanticError::ArrayValue { location, .. }
+            | SemanticError::InvalidIndex { location, .. }
+            | SemanticError::NotAnArray { location, .. }
+            | SemanticError::RedefinedType { location, .. }
+            | SemanticError::RecursiveType { location, .. }
+            | SemanticError::UnknownType { location, .. }
+            | SemanticError::UnknownField { location, .. }
+            | SemanticError::DuplicateField { location, .. }
+            | SemanticError::MissingField { location, .. }
+            | SemanticError::AggregateConstant { location, .. }
+            | SemanticError::NotAddressable { location, .. }
+            | SemanticError::ImmutableAddress { location, .. }
+            | SemanticError::ArrayAddress { location, .. }
+            | SemanticError::NotAPointer { location, .. }
+            | SemanticError::UntypedNull { location, .. }
+            | SemanticError::NonConstantPointer { location, .. }
+            | SemanticError::EmptyEnum { location, .. }
+            | SemanticError::DuplicateVariant { location, .. }
+            | SemanticError::UnknownVariant { location, .. }
+            | SemanticError::PayloadCount { location, .. }
+            | SemanticError::NotAnEnum { location, .. }
+            | SemanticError::UnreachableArm { location, .. }
+            | SemanticError::NonExhaustiveMatch { location, .. }
+            | SemanticError::NonConstantMatch { location, .. } => Some(location),
+        }
+    }
+}
+
+impl std::fmt::Display for SemanticError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SemanticError::UndefinedVariable { v_name, location } => {
+                write!(f, "Undefined variable: {}, {}", v_name, location)
+            }
+            SemanticError::RedeclaredVariable {
+                v_name,
+                location,
+                original_type,
+                original_location,
+            } => write!(
+                f,
+                "Attempted to redeclare variable: {}, {}, previously declared as {} at {}",
+                v_name, location, original_type, original_location
+            ),
+            SemanticError::ImmutableAssignment {
+                v_name,
+                location,
+                declared_location,
+            } => write!(
+                f,
+                "Cannot assign to immutable variable: {}, {}, declared without mut at {}",
+                v_name, location, declared_location
+            ),
+            SemanticError::ConstantAssignment {
+                v_name,
+                location,
+                declared_location,
+            } => write!(
+                f,
+                "Cannot assign to constant: {}, {}, declared at {}",
+                v_name, location, declared_location
+            ),
+            SemanticError::UnassignedVariable {
+                v_name,
+                location,
+                declared_location,
+            } => write!(
+                f,
+                "Variable may be used before it is assigned: {}, {}, declared without a value at {}",
+                v_name, location, declared_location
+            ),
+            SemanticError::NonConstantExpression { v_name, location } => write!(
+                f,
+                "Constant values cannot use the non-constant variable: {}, {}",
+                v_name, location
+            ),
+            SemanticError::TypeMismatch {
+                expected,
+                found,
+                location,
+            } => write!(
+                f,
+                "Mismatched types, expected {} but found {}, {}",
+                expected, found, location
+            ),
+            SemanticError::InvalidOperand { op, ty, location } => write!(
+                f,
+                "Operator {} cannot be applied to values of type {}, {}",
+                op, ty, location
+            ),
+            SemanticError::InvalidCast { from, to, location } => {
+                write!(f, "Unable to cast from {} to {}, {}", from, to, location)
+            }
+            SemanticError::LiteralOutOfRange {
+                value,
+                ty,
+                location,
+            } => write!(
+                f,
+                "Literal {} is out of range for type {}, {}",
+                value, ty, location
+            ),
+            SemanticError::UnsupportedLiteral { value, location } => {
+                write!(f, "Unsupported literal: {}, {}", value, location)
+            }
+            SemanticError::ArrayValue { ty, location } => write!(
+                f,
+                "Arrays can only be used by indexing their elements, found value of type {}, {}",
+                ty, location
+            ),
+            SemanticError::InvalidIndex { ty, location } => write!(
+                f,
+                "Array indices must be integers, found value of type {}, {}",
+                ty, location
+            ),
+            SemanticError::NotAnArray { ty, location } => {
+                write!(f, "Cannot index into value of type {}, {}", ty, location)
+            }
+            SemanticError::RedefinedType {
+                ty,
+                location,
+                original_location,
+            } => write!(
+                f,
+                "Attempted to redefine {}: {}, {}, previously defined at {}",
+                if let Type::Enum(_) = ty {
+                    "enum"
+                } else {
+                    "struct"
+                },
+                ty,
+                location,
+                original_location
+            ),
+            SemanticError::RecursiveType { ty, location } => write!(
+                f,
+                "{} {} contains itself and would have no finite size, {}",
+                if let Type::Enum(_) = ty {
+                    "Enum"
+                } else {
+                    "Struct"
+                },
+                ty,
+                location
+            ),
+            SemanticError::UnknownType { name, location } => {
+                write!(f, "Unknown type: {}, {}", name, location)
+            }
+            SemanticError::UnknownField {
+                ty,
+                field,
+                location,
+            } => write!(f, "Type {} has no field named {}, {}", ty, field, location),
+            SemanticError::DuplicateField { field, location } => {
+                write!(f, "Field given more than once: {}, {}", field, location)
+            }
+            SemanticError::MissingField {
+                ty,
+                field,
+                location,
+            } => write!(
+                f,
+                "Missing field {} in literal of type {}, {}",
+                field, ty, location
+            ),
+            SemanticError::AggregateConstant { ty, location } => write!(
+                f,
+                "Constants must have a primitive type, found {}, {}",
+                ty, location
+            ),
+            SemanticError::NotAddressable { expr, location } => write!(
+                f,
+                "Cannot take the address of {}, only of a variable, element, field or dereferenced pointer, {}",
+                expr, location
+            ),
+            SemanticError::ImmutableAddress {
+                v_name,
+                location,
+                declared_location,
+            } => write!(
+                f,
+                "Cannot take the address of immutable variable: {}, {}, declared without mut at {}",
+                v_name, location, declared_location
+            ),
+            SemanticError::ArrayAddress { ty, location } => write!(
+                f,
+                "Cannot take the address of an array of type {}, take the address of an element instead, {}",
+                ty, location
+            ),
+            SemanticError::NotAPointer { ty, location } => {
+                write!(f, "Cannot dereference value of type {}, {}", ty, location)
+            }
+            SemanticError::UntypedNull { location } => write!(
+                f,
+                "null can only be used where a pointer type is expected, {}",
+                location
+            ),
+            SemanticError::NonConstantPointer { location } => write!(
+                f,
+                "Constant values cannot take addresses or dereference pointers, {}",
+                location
+            ),
+            SemanticError::EmptyEnum { name, location } => {
+                write!(
+                    f,
+                    "Enum {} must have at least one variant, {}",
+                    name, location
+                )
+            }
+            SemanticError::DuplicateVariant { variant, location } => {
+                write!(
+                    f,
+                    "Variant defined more than once: {}, {}",
+                    variant, location
+                )
+            }
+            SemanticError::UnknownVariant {
+                ty,
+                variant,
+                location,
+            } => write!(
+                f,
+                "Type {} has no variant named {}, {}",
+                ty, variant, location
+            ),
+            SemanticError::PayloadCount {
+                variant,
+                expected,
+                found,
+                location,
+            } => write!(
+                f,
+                "Variant {} carries {} values but {} were given, {}",
+                variant, expected, found, location
+            ),
+            SemanticError::NotAnEnum { ty, location } => {
+                write!(f, "Cannot match on value of type {}, {}", ty, location)
+            }
+            SemanticError::UnreachableArm { pattern, location } => write!(
+                f,
+                "Match arm for {} is unreachable, it is covered by an earlier arm, {}",
+                pattern, location
+            ),
+            SemanticError::NonExhaustiveMatch {
+                ty,
+                missing,
+                location,
+            } => write!(
+                f,
+                "Match on {} does not cover every variant, missing {}, {}",
+                ty,
+                missing.join(", "),
+                location
+            ),
+            SemanticError::NonConstantMatch { location } => {
+                write!(f, "Constant values cannot use match, {}", location)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub ty: Type,
+    pub mutable: bool,
+    pub constant: bool,
+    pub span: Span,
+    pub loop_depth: usize,
+}
+
+struct SymbolTable {
+    scopes: Vec<HashMap<String, Symbol>>,
+}
+
+impl SymbolTable {
+    fn new() -> Self {
+        Self {
+            scopes: vec![HashMap::new()],
+        }
+    }
+
+    fn enter_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn exit_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, v_name: &str, symbol: Symbol) -> Result<(), Symbol> {
+        let scope = self
+            .scopes
+            .last_mut()
+            .expect("Symbol table should always contain the global scope");
+        match scope.get(v_name) {
+            Some(existing) => Err(existing.clone()),
+            None => {
+                scope.insert(v_name.to_string(), symbol);
+                Ok(())
+            }
+        }
+    }
+
+    fn lookup(&self, v_name: &str) -> Option<&Symbol> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(v_name))
+    }
+}
+
+/*
+* Variables are identified by the span of their declared name. A variable may be unassigned if there
+* is a path to the current point on which it hasn't been assigned, and may be assigned if there is a
+* path on which it has
+*/
+#[derive(Clone)]
+struct Assignments {
+    maybe_unassigned: HashSet<Span>,
+    maybe_assigned: HashSet<Span>,
+}
+
+impl Assignments {
+    fn merge(&mut self, other: Assignments) {
+        self.maybe_unassigned.extend(other.maybe_unassigned);
+        self.maybe_assigned.extend(other.maybe_assigned);
+    }
+}
+
+struct Analyser<'a> {
+    source_map: &'a SourceMap,
+    structs: HashMap<String, Vec<StructField>>,
+    enums: HashMap<String, Vec<EnumVariant>>,
+    symbols: SymbolTable,
+    assignments: Assignments,
+    loop_depth: usize,
+}
+
+pub fn analyse(program: &mut Program, source_map: &SourceMap) -> Result<(), SemanticError> {
+    let mut analyser = Analyser {
+        source_map,
+        structs: HashMap::new(),
+        enums: HashMap::new(),
+        symbols: SymbolTable::new(),
+        assignments: Assignments {
+            maybe_unassigned: HashSet::new(),
+            maybe_assigned: HashSet::new(),
+        },
+        loop_depth: 0,
+    };
+    analyser.define_types(&mut program.statements)?;
+    analyser.analyse_statements(&mut program.statements)
+}
+
+impl Analyser<'_> {
+    /*
+     * Every struct and enum is defined before any statement is analysed, so types can be used
+     * before their definition. The types used by their fields and variants are resolved in place,
+     * which leaves the definitions in the program ready for the layout of each type to be computed
+     */
+    fn define_types(&mut self, statements: &mut [Statement]) -> Result<(), SemanticError> {
+        let mut spans = HashMap::new();
+        for statement in statements.iter() {
+            let (ty, span) = match statement {
+                Statement::StructStatement { name, fields, span } => {
+                    if let Some((field, span)) =
+                        find_duplicate(fields.iter().map(|x| (&x.name, x.span)))
+                    {
+                        return Err(SemanticError::DuplicateField {
+                            field: field.clone(),
+                            location: self.source_map.location(span.start),
+                        });
+                    }
+                    self.structs.insert(name.clone(), fields.clone());
+                    (Type::Struct(name.clone()), span)
+                }
+                Statement::EnumStatement {
+                    name,
+                    variants,
+                    span,
+                } => {
+                    if variants.is_empty() {
+                        return Err(SemanticError::EmptyEnum {
+                            name: name.clone(),
+                            location: self.source_map.location(span.start),
+                        });
+                    }
+                    if let Some((variant, span)) =
+                        find_duplicate(variants.iter().map(|x| (&x.name, x.span)))
+                    {
+                        return Err(SemanticError::DuplicateVariant {
+                            variant: variant.clone(),
+                            location: self.source_map.location(span.start),
+                        });
+                    }
+                    self.enums.insert(name.clone(), variants.clone());
+                    (Type::Enum(name.clone()), span)
+                }
+                _ => continue,
+            };
+            let name = ty.to_string();
+            if let Some(original) = spans.insert(name, *span) {
+                return Err(SemanticError::RedefinedType {
+                    ty,
+                    location: self.source_map.location(span.start),
+                    original_location: self.source_map.location(original.start),
+                });
+            }
+        }
+
+        for statement in statements.iter_mut() {
+            match statement {
+                Statement::StructStatement { name, fields, .. } => {
+                    for field in fields.iter_mut() {
+                        self.resolve_type(&mut field.ty, field.span)?;
+                    }
+                    self.structs.insert(name.clone(), fields.clone());
+                }
+                Statement::EnumStatement { name, variants, .. } => {
+                    for variant in variants.iter_mut() {
+                        for ty in variant.payload.iter_mut() {
+                            self.resolve_type(ty, variant.span)?;
+                        }
+                    }
+                    self.enums.insert(name.clone(), variants.clone());
+                }
+                _ => {}
+            }
+        }
+
+        for statement in statements.iter() {
+            let (ty, types, span): (Type, Vec<&Type>, &Span) = match statement {
+                Statement::StructStatement { name, fields, span } => (
+                    Type::Struct(name.clone()),
+                    fields.iter().map(|x| &x.ty).collect(),
+                    span,
+                ),
+                Statement::EnumStatement {
+                    name,
+                    variants,
+                    span,
+                } => (
+                    Type::Enum(name.clone()),
+                    variants.iter().flat_map(|x| &x.payload).collect(),
+                    span,
+                ),
+                _ => continue,
+            };
+            let mut seen = HashSet::new();
+            let name = ty.to_string();
+            if types
+                .iter()
+                .any(|x| self.contains_type(x, &name, &mut seen))
+            {
+                return Err(SemanticError::RecursiveType {
+                    ty,
+                    location: self.source_map.location(span.start),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /*
+     * Whether a value of the given type contains the named struct or enum, either directly or
+     * through the fields and variants of other types. Types which have already been searched are
+     * skipped, and a pointer doesn't contain the value it points to
+     */
+    fn contains_type(&self, ty: &Type, name: &str, seen: &mut HashSet<String>) -> bool {
+        match ty {
+            Type::Array(elem, _) => self.contains_type(elem, name, seen),
+            Type::Struct(x) | Type::Enum(x) if x == name => true,
+            Type::Struct(x) if seen.insert(x.clone()) => self.structs[x]
+                .iter()
+                .any(|field| self.contains_type(&field.ty, name, seen)),
+            Type::Enum(x) if seen.insert(x.clone()) => self.enums[x]
+                .iter()
+                .flat_map(|variant| &variant.payload)
+                .any(|ty| self.contains_type(ty, name, seen)),
+            _ => false,
+        }
+    }
+
+    /*
+     * A named type from the parser is always a struct, this changes it to an enum if that is what
+     * the name refers to and errors if it refers to neither
+     */
+    fn resolve_type(&self, ty: &mut Type, span: Span) -> Result<(), SemanticError> {
+        match ty {
+            Type::Array(elem, _) | Type::Pointer(elem) => self.resolve_type(elem, span),
+            Type::Struct(name) if self.enums.contains_key(name) => {
+                *ty = Type::Enum(name.clone());
+                Ok(())
+            }
+            Type::Struct(name) if !self.structs.contains_key(name) => {
+                Err(SemanticError::UnknownType {
+                    name: name.clone(),
+                    location: self.source_map.location(span.start),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /*
+     * Finds the index of a variant and the values it carries, the name is checked to be an enum
+     */
+    fn find_variant(
+        &self,
+        name: &str,
+        variant: &str,
+        span: Span,
+    ) -> Result<(usize, Vec<Type>), SemanticError> {
+        let mut ty = Type::Struct(name.to_string());
+        self.resolve_type(&mut ty, span)?;
+        let found = self
+            .enums
+            .get(name)
+            .and_then(|x| x.iter().enumerate().find(|(_, x)| x.name == variant));
+        match found {
+            Some((idx, x)) => Ok((idx, x.payload.clone())),
+            None => Err(SemanticError::UnknownVariant {
+                ty,
+                variant: variant.to_string(),
+                location: self.source_map.location(span.start),
+            }),
+        }
+    }
+
+    fn analyse_statements(&mut self, statements: &mut [Statement]) -> Result<(), SemanticError> {
+        for statement in statements {
+            self.analyse_statement(statement)?;
+        }
+        Ok(())
+    }
+
+    fn analyse_statement(&mut self, statement: &mut Statement) -> Result<(), SemanticError> {
+        match statement {
+            Statement::DeclarationStatement {
+                v_name,
+                v_span,
+                ty,
+                mutable,
+                expr,
+                ..
+            } => {
+                self.resolve_type(ty, *v_span)?;
+                if let Some(x) = expr {
+                    self.expect_expression_type(x, ty)?;
+                    self.assignments.maybe_assigned.insert(*v_span);
+                } else if ty.is_aggregate() {
+                    self.assignments.maybe_assigned.insert(*v_span);
+                } else {
+                    self.assignments.maybe_unassigned.insert(*v_span);
+                }
+                self.declare(
+                    v_name,
+                    Symbol {
+                        ty: ty.clone(),
+                        mutable: *mutable,
+                        constant: false,
+                        span: *v_span,
+                        loop_depth: self.loop_depth,
+                    },
+                )
+            }
+            Statement::ConstStatement {
+                v_name,
+                v_span,
+                ty,
+                expr,
+                ..
+            } => {
+                self.resolve_type(ty, *v_span)?;
+                if ty.is_aggregate() {
+                    return Err(SemanticError::AggregateConstant {
+                        ty: ty.clone(),
+                        location: self.source_map.location(v_span.start),
+                    });
+                }
+                self.expect_expression_type(expr, ty)?;
+                self.expect_constant_expression(expr)?;
+                self.declare(
+                    v_name,
+                    Symbol {
+                        ty: ty.clone(),
+                        mutable: false,
+                        constant: true,
+                        span: *v_span,
+                        loop_depth: self.loop_depth,
+                    },
+                )
+            }
+            Statement::AssignmentStatement { target, expr, span } => {
+                let element_ty = match target.kind {
+                    ExpressionKind::VarExpr(_) => None,
+                    _ => Some(self.analyse_expression(target, None)?),
+                };
+                /*
+                 * Writing through a pointer changes the value pointed to, which is always mutable,
+                 * rather than any variable
+                 */
+                if let (None, Some(ty)) = (target.root_variable(), &element_ty) {
+                    return self.expect_expression_type(expr, ty);
+                }
+                let v_name = target.root_variable().unwrap_or_default().to_string();
+                let is_element = element_ty.is_some();
+                match self.symbols.lookup(&v_name).cloned() {
+                    Some(symbol) if symbol.constant => Err(SemanticError::ConstantAssignment {
+                        v_name,
+                        location: self.source_map.location(span.start),
+                        declared_location: self.source_map.location(symbol.span.start),
+                    }),
+                    Some(symbol)
+                        if !symbol.mutable && (is_element || self.may_be_assigned(&symbol)) =>
+                    {
+                        Err(SemanticError::ImmutableAssignment {
+                            v_name,
+                            location: self.source_map.location(span.start),
+                            declared_location: self.source_map.location(symbol.span.start),
+                        })
+                    }
+                    Some(symbol) => match element_ty {
+                        Some(ty) => self.expect_expression_type(expr, &ty),
+                        None => {
+                            target.ty = Some(symbol.ty.clone());
+                            self.expect_expression_type(expr, &symbol.ty)?;
+                            self.assignments.maybe_unassigned.remove(&symbol.span);
+                            self.assignments.maybe_assigned.insert(symbol.span);
+                            Ok(())
+                        }
+                    },
+                    None => {
+                        self.analyse_expression(expr, None)?;
+                        self.resolve(&v_name, *span).map(|_| ())
+                    }
+                }
+            }
+            Statement::BlockStatement { statements, .. } => {
+                self.symbols.enter_scope();
+                let result = self.analyse_statements(statements);
+                self.symbols.exit_scope();
+                result
+            }
+            Statement::IfStatement {
+                condition,
+                then_block,
+                else_block,
+                ..
+            } => {
+                self.expect_expression_type(condition, &Type::Bool)?;
+                let before = self.assignments.clone();
+                self.analyse_statement(then_block)?;
+                let after_then = std::mem::replace(&mut self.assignments, before);
+                if let Some(x) = else_block {
+                    self.analyse_statement(x)?;
+                }
+                self.assignments.merge(after_then);
+                Ok(())
+            }
+            Statement::WhileStatement {
+                condition, body, ..
+            } => {
+                self.expect_expression_type(condition, &Type::Bool)?;
+                let before = self.assignments.clone();
+                self.loop_depth += 1;
+                let result = self.analyse_statement(body);
+                self.loop_depth -= 1;
+                result?;
+                let after_body = std::mem::replace(&mut self.assignments, before);
+                self.assignments.merge(after_body);
+                Ok(())
+            }
+            Statement::MatchStatement {
+                scrutinee,
+                arms,
+                span,
+            } => self.analyse_match(scrutinee, arms, *span, |x, body| x.analyse_statement(body)),
+            Statement::StructStatement { .. } | Statement::EnumStatement { .. } => Ok(()),
+        }
+    }
+
+    /*
+     * Each arm starts from the assignments made before the match, and afterwards a variable may be
+     * assigned or unassigned if it may be after any of the arms
+     */
+    fn analyse_match<T>(
+        &mut self,
+        scrutinee: &mut Expression,
+        arms: &mut [MatchArm<T>],
+        span: Span,
+        mut analyse_body: impl FnMut(&mut Self, &mut T) -> Result<(), SemanticError>,
+    ) -> Result<(), SemanticError> {
+        let ty = self.analyse_expression(scrutinee, None)?;
+        let name = match &ty {
+            Type::Enum(x) => x.clone(),
+            _ => {
+                return Err(SemanticError::NotAnEnum {
+                    ty,
+                    location: self.source_map.location(scrutinee.span.start),
+                });
+            }
+        };
+        let variant_count = self.enums[&name].len();
+
+        let before = self.assignments.clone();
+        let mut after: Option<Assignments> = None;
+        let mut covered = HashSet::new();
+        let mut has_wildcard = false;
+        for arm in arms.iter_mut() {
+            let pattern = &arm.pattern;
+            let location = self.source_map.location(pattern.span.start);
+            let (is_reachable, payload) = match &pattern.kind {
+                PatternKind::Wildcard => {
+                    let is_reachable = !has_wildcard && covered.len() < variant_count;
+                    has_wildcard = true;
+                    (is_reachable, Vec::new())
+                }
+                PatternKind::Variant(enum_name, variant, bindings) => {
+                    let (idx, payload) = self.find_variant(enum_name, variant, pattern.span)?;
+                    if *enum_name != name {
+                        return Err(SemanticError::TypeMismatch {
+                            expected: ty,
+                            found: Type::Enum(enum_name.clone()),
+                            location,
+                        });
+                    }
+                    if bindings.len() != payload.len() {
+                        return Err(SemanticError::PayloadCount {
+                            variant: format!("{}::{}", enum_name, variant),
+                            expected: payload.len(),
+                            found: bindings.len(),
+                            location,
+                        });
+                    }
+                    (!has_wildcard && covered.insert(idx), payload)
+                }
+            };
+            if !is_reachable {
+                return Err(SemanticError::UnreachableArm {
+                    pattern: pattern.to_string(),
+                    location,
+                });
+            }
+
+            self.assignments = before.clone();
+            self.symbols.enter_scope();
+            let mut result = Ok(());
+            for (idx, v_name, v_span) in pattern.bindings() {
+                self.assignments.maybe_assigned.insert(v_span);
+                result = result.and_then(|_| {
+                    self.declare(
+                        v_name,
+                        Symbol {
+                            ty: payload[idx].clone(),
+                            mutable: false,
+                            constant: false,
+                            span: v_span,
+                            loop_depth: self.loop_depth,
+                        },
+                    )
+                });
+            }
+            let result = result.and_then(|_| analyse_body(self, &mut arm.body));
+            self.symbols.exit_scope();
+            result?;
+
+            let assignments = std::mem::replace(&mut self.assignments, before.clone());
+            match &mut after {
+                Some(x) => x.merge(assignments),
+                None => after = Some(assignments),
+            }
+        }
+
+        if !has_wildcard && covered.len() < variant_count {
+            let missing = self.enums[&name]
+                .iter()
+                .enumerate()
+                .filter(|(idx, _)| !covered.contains(idx))
+                .map(|(_, x)| format!("{}::{}", name, x.name))
+                .collect();
+            return Err(SemanticError::NonExhaustiveMatch {
+                ty,
+                missing,
+                location: self.source_map.location(span.start),
+            });
+        }
+        self.assignments = after.unwrap_or(before);
+        Ok(())
+    }
+
+    /*
+     * Assigning inside a loop the variable was declared outside of may happen more than once
+     */
+    fn may_be_assigned(&self, symbol: &Symbol) -> bool {
+        self.assignments.maybe_assigned.contains(&symbol.span)
+            || symbol.loop_depth != self.loop_depth
+    }
+
+    fn declare(&mut self, v_name: &str, symbol: Symbol) -> Result<(), SemanticError> {
+        let v_span = symbol.span;
+        self.symbols
+            .declare(v_name, symbol)
+            .map_err(|existing| SemanticError::RedeclaredVariable {
+                v_name: v_name.to_string(),
+                location: self.source_map.location(v_span.start),
+                original_type: existing.ty.clone(),
+                original_location: self.source_map.location(existing.span.start),
+            })
+    }
+
+    fn expect_constant_expression(&self, expr: &Expression) -> Result<(), SemanticError> {
+        match &expr.kind {
+            ExpressionKind::ValExpr(_) => Ok(()),
+            ExpressionKind::VarExpr(v_name) => match self.symbols.lookup(v_name) {
+                Some(symbol) if symbol.constant => Ok(()),
+                _ => Err(SemanticError::NonConstantExpression {
+                    v_name: v_name.clone(),
+                    location: self.source_map.location(expr.span.start),
+                }),
+            },
+            ExpressionKind::ArithmeticExpr(x, _, y) | ExpressionKind::BooleanExpr(x, _, y) => {
+                self.expect_constant_expression(x)?;
+                self.expect_constant_expression(y)
+            }
+            ExpressionKind::CastExpr(x, _) => self.expect_constant_expression(x),
+            ExpressionKind::IndexExpr(x, index) => {
+                self.expect_constant_expression(x)?;
+                self.expect_constant_expression(index)
+            }
+            ExpressionKind::FieldExpr(x, _) => self.expect_constant_expression(x),
+            ExpressionKind::StructLiteral(_, fields) => fields
+                .iter()
+                .try_for_each(|(_, value)| self.expect_constant_expression(value)),
+            ExpressionKind::VariantLiteral(_, _, values) => values
+                .iter()
+                .try_for_each(|value| self.expect_constant_expression(value)),
+            ExpressionKind::AddressOf(_) | ExpressionKind::Deref(_) => {
+                Err(SemanticError::NonConstantPointer {
+                    location: self.source_map.location(expr.span.start),
+                })
+            }
+            ExpressionKind::MatchExpr(..) => Err(SemanticError::NonConstantMatch {
+                location: self.source_map.location(expr.span.start),
+            }),
+        }
+    }
+
+    fn expect_expression_type(
+        &mut self,
+        expr: &mut Expression,
+        expected: &Type,
+    ) -> Result<(), SemanticError> {
+        let found = self.analyse_expression(expr, Some(expected))?;
+        if found != *expected {
+            return Err(SemanticError::TypeMismatch {
+                expected: expected.clone(),
+                found,
+                location: self.source_map.location(expr.span.start),
+            });
+        }
+        Ok(())
+    }
+
+    /*
+     * Analyses an expression which is used as a value, which rules out arrays
+     */
+    fn analyse_expression(
+        &mut self,
+        expr: &mut Expression,
+        expected: Option<&Type>,
+    ) -> Result<Type, SemanticError> {
+        let ty = self.analyse_expression_kind(expr, expected)?;
+        if let Type::Array(..) = ty {
+            return Err(SemanticError::ArrayValue {
+                ty,
+                location: self.source_map.location(expr.span.start),
+            });
+        }
+        Ok(ty)
+    }
+
+    fn analyse_expression_kind(
+        &mut self,
+        expr: &mut Expression,
+        expected: Option<&Type>,
+    ) -> Result<Type, SemanticError> {
+        let location = self.source_map.location(expr.span.start);
+        let ty = match &mut expr.kind {
+            ExpressionKind::ValExpr(value) => self.analyse_literal(value, expected, expr.span)?,
+            ExpressionKind::VarExpr(v_name) => {
+                let symbol = self.resolve(v_name, expr.span)?;
+                if self.assignments.maybe_unassigned.contains(&symbol.span) {
+                    return Err(SemanticError::UnassignedVariable {
+                        v_name: v_name.clone(),
+                        location,
+                        declared_location: self.source_map.location(symbol.span.start),
+                    });
+                }
+                symbol.ty.clone()
+            }
+            ExpressionKind::ArithmeticExpr(x, op, y) => {
+                let (lhs_ty, rhs_ty) = self.analyse_operands(x, y, expected)?;
+                if let Type::Pointer(_) = lhs_ty {
+                    self.analyse_pointer_arithmetic(lhs_ty, *op, rhs_ty, y, location)?
+                } else {
+                    let ty = self.expect_same_type(lhs_ty, rhs_ty, y)?;
+                    let is_checked = op.overflow_mode() == OverflowMode::Checked;
+                    if !(ty.is_integer() || ty.is_float() && is_checked) {
+                        return Err(SemanticError::InvalidOperand {
+                            op: *op,
+                            ty,
+                            location,
+                        });
+                    }
+                    ty
+                }
+            }
+            ExpressionKind::BooleanExpr(x, op, y) => {
+                let (lhs_ty, rhs_ty) = self.analyse_operands(x, y, None)?;
+                let ty = self.expect_same_type(lhs_ty, rhs_ty, y)?;
+                let is_ordered = ty.is_numeric() || matches!(ty, Type::Char | Type::Pointer(_));
+                let is_equality = matches!(op, Operator::Equal | Operator::NotEqual);
+                if ty.is_aggregate() || !is_ordered && !is_equality {
+                    return Err(SemanticError::InvalidOperand {
+                        op: *op,
+                        ty,
+                        location,
+                    });
+                }
+                Type::Bool
+            }
+            ExpressionKind::CastExpr(x, ty) => {
+                let from = self.analyse_expression(x, None)?;
+                if from.is_aggregate() || !is_valid_cast(&from, ty) {
+                    return Err(SemanticError::InvalidCast {
+                        from,
+                        to: ty.clone(),
+                        location,
+                    });
+                }
+                ty.clone()
+            }
+            ExpressionKind::IndexExpr(x, index) => {
+                let array_ty = self.analyse_expression_kind(x, None)?;
+                let index_ty = self.analyse_expression(index, None)?;
+                if !index_ty.is_integer() {
+                    return Err(SemanticError::InvalidIndex {
+                        ty: index_ty,
+                        location: self.source_map.location(index.span.start),
+                    });
+                }
+                match array_ty {
+                    Type::Array(elem, _) => *elem,
+                    ty => return Err(SemanticError::NotAnArray { ty, location }),
+                }
+            }
+            ExpressionKind::FieldExpr(x, field) => {
+                let ty = self.analyse_expression_kind(x, None)?;
+                let found = ty
+                    .struct_name()
+                    .and_then(|name| self.structs[name].iter().find(|x| x.name == *field));
+                match found {
+                    Some(x) => x.ty.clone(),
+                    None => {
+                        return Err(SemanticError::UnknownField {
+                            ty,
+                            field: field.clone(),
+                            location,
+                        });
+                    }
+                }
+            }
+            ExpressionKind::StructLiteral(name, values) => {
+                let mut ty = Type::Struct(name.clone());
+                self.resolve_type(&mut ty, expr.span)?;
+                let fields = match self.structs.get(name) {
+                    Some(x) => x.clone(),
+                    None => {
+                        return Err(SemanticError::TypeMismatch {
+                            expected: Type::Struct(name.clone()),
+                            found: ty,
+                            location,
+                        });
+                    }
+                };
+                self.analyse_struct_literal(&ty, &fields, values, expr.span)?;
+                ty
+            }
+            ExpressionKind::AddressOf(x) => {
+                if !x.is_place() {
+                    return Err(SemanticError::NotAddressable {
+                        expr: x.to_string(),
+                        location,
+                    });
+                }
+                let ty = self.analyse_expression_kind(x, None)?;
+                if let Type::Array(..) = ty {
+                    return Err(SemanticError::ArrayAddress { ty, location });
+                }
+                if let Some(v_name) = x.root_variable() {
+                    let symbol = self.resolve(v_name, x.span)?;
+                    if symbol.constant {
+                        return Err(SemanticError::NotAddressable {
+                            expr: x.to_string(),
+                            location,
+                        });
+                    }
+                    if !symbol.mutable {
+                        return Err(SemanticError::ImmutableAddress {
+                            v_name: v_name.to_string(),
+                            location,
+                            declared_location: self.source_map.location(symbol.span.start),
+                        });
+                    }
+                }
+                Type::Pointer(Box::new(ty))
+            }
+            ExpressionKind::Deref(x) => match self.analyse_expression(x, None)? {
+                Type::Pointer(ty) => *ty,
+                ty => return Err(SemanticError::NotAPointer { ty, location }),
+            },
+            ExpressionKind::VariantLiteral(name, variant, values) => {
+                let (_, payload) = self.find_variant(name, variant, expr.span)?;
+                if values.len() != payload.len() {
+                    return Err(SemanticError::PayloadCount {
+                        variant: format!("{}::{}", name, variant),
+                        expected: payload.len(),
+                        found: values.len(),
+                        location,
+                    });
+                }
+                for (value, ty) in values.iter_mut().zip(&payload) {
+                    self.expect_expression_type(value, ty)?;
+                }
+                Type::Enum(name.clone())
+            }
+            ExpressionKind::MatchExpr(scrutinee, arms) => {
+                let mut arm_ty: Option<Type> = None;
+                self.analyse_match(scrutinee, arms, expr.span, |x, body| match arm_ty.clone() {
+                    Some(ty) => x.expect_expression_type(body, &ty),
+                    None => {
+                        arm_ty = Some(x.analyse_expression(body, expected)?);
+                        Ok(())
+                    }
+                })?;
+                arm_ty.expect("An exhaustive match should have at least one arm")
+            }
+        };
+        expr.ty = Some(ty.clone());
+        Ok(ty)
+    }
+
+    fn analyse_struct_literal(
+        &mut self,
+        ty: &Type,
+        fields: &[StructField],
+        values: &mut [(String, Expression)],
+        span: Span,
+    ) -> Result<(), SemanticError> {
+        let mut given = HashSet::new();
+        for (field, value) in values.iter_mut() {
+            let location = self.source_map.location(value.span.start);
+            let field_ty = match fields.iter().find(|x| x.name == *field) {
+                Some(x) => &x.ty,
+                None => {
+                    return Err(SemanticError::UnknownField {
+                        ty: ty.clone(),
+                        field: field.clone(),
+                        location,
+                    });
+                }
+            };
+            if !given.insert(field.clone()) {
+                return Err(SemanticError::DuplicateField {
+                    field: field.clone(),
+                    location,
+                });
+            }
+            self.expect_expression_type(value, field_ty)?;
+        }
+
+        match fields
+            .iter()
+            .find(|x| !matches!(x.ty, Type::Array(..)) && !given.contains(&x.name))
+        {
+            Some(x) => Err(SemanticError::MissingField {
+                ty: ty.clone(),
+                field: x.name.clone(),
+                location: self.source_map.location(span.start),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /*
+     * When only one operand of a binary operator is an untyped literal the other side is analysed
+     * first, so that the literal can take on its type
+     */
+    fn analyse_operands(
+        &mut self,
+        lhs: &mut Expression,
+        rhs: &mut Expression,
+        expected: Option<&Type>,
+    ) -> Result<(Type, Type), SemanticError> {
+        if is_untyped_literal(lhs) && !is_untyped_literal(rhs) {
+            let rhs_ty = self.analyse_expression(rhs, expected)?;
+            Ok((self.analyse_expression(lhs, Some(&rhs_ty))?, rhs_ty))
+        } else {
+            let lhs_ty = self.analyse_expression(lhs, expected)?;
+            let rhs_ty = self.analyse_expression(rhs, Some(&lhs_ty))?;
+            Ok((lhs_ty, rhs_ty))
+        }
+    }
+
+    /*
+     * Other than in pointer arithmetic both operands of a binary operator must have the same type
+     */
+    fn expect_same_type(
+        &self,
+        lhs_ty: Type,
+        rhs_ty: Type,
+        rhs: &Expression,
+    ) -> Result<Type, SemanticError> {
+        if lhs_ty != rhs_ty {
+            return Err(SemanticError::TypeMismatch {
+                expected: lhs_ty,
+                found: rhs_ty,
+                location: self.source_map.location(rhs.span.start),
+            });
+        }
+        Ok(lhs_ty)
+    }
+
+    /*
+     * A pointer can be moved by adding or subtracting an integer of any type, or subtracted from
+     * another pointer of the same type. Pointer arithmetic isn't checked for overflow, so the
+     * wrapping and saturating operators can't be used with it
+     */
+    fn analyse_pointer_arithmetic(
+        &self,
+        ty: Type,
+        op: Operator,
+        rhs_ty: Type,
+        rhs: &Expression,
+        location: TextLocation,
+    ) -> Result<Type, SemanticError> {
+        if !matches!(op, Operator::Add | Operator::Sub) {
+            return Err(SemanticError::InvalidOperand { op, ty, location });
+        }
+        match rhs_ty {
+            x if x.is_integer() => Ok(ty),
+            x if x == ty && op == Operator::Sub => Ok(Type::I64),
+            found => Err(SemanticError::TypeMismatch {
+                expected: Type::I64,
+                found,
+                location: self.source_map.location(rhs.span.start),
+            }),
+        }
+    }
+
+    fn analyse_literal(
+        &self,
+        value: &str,
+        expected: Option<&Type>,
+        span: Span,
+    ) -> Result<Type, SemanticError> {
+        let location = self.source_map.location(span.start);
+        let numeric_ty = expected.filter(|x| x.is_numeric()).cloned();
+
+        match Literal::parse(value) {
+            Some(Literal::Int(x)) => {
+                let ty = numeric_ty.unwrap_or(Type::I64);
+                match ty.int_range() {
+                    Some((min, max)) if x < min || x > max => {
+                        Err(SemanticError::LiteralOutOfRange {
+                            value: value.to_string(),
+                            ty,
+                            location,
+                        })
+                    }
+                    _ => Ok(ty),
+                }
+            }
+            Some(Literal::Float(_)) => Ok(numeric_ty.filter(|x| x.is_float()).unwrap_or(Type::F64)),
+            Some(Literal::Bool(_)) => Ok(Type::Bool),
+            Some(Literal::Char(_)) => Ok(Type::Char),
+            Some(Literal::Null) => match expected {
+                Some(x @ Type::Pointer(_)) => Ok(x.clone()),
+                _ => Err(SemanticError::UntypedNull { location }),
+            },
+            None if value.chars().all(|x| x.is_ascii_digit()) => {
+                Err(SemanticError::LiteralOutOfRange {
+                    value: value.to_string(),
+                    ty: numeric_ty.unwrap_or(Type::I64),
+                    location,
+                })
+            }
+            Some(Literal::Str(_)) | None => Err(SemanticError::UnsupportedLiteral {
+                value: value.to_string(),
+                location,
+            }),
+        }
+    }
+
+    fn resolve(&self, v_name: &str, use_span: Span) -> Result<&Symbol, SemanticError> {
+        match self.symbols.lookup(v_name) {
+            Some(x) => Ok(x),
+            None => Err(SemanticError::UndefinedVariable {
+                v_name: v_name.to_string(),
+                location: self.source_map.location(use_span.start),
+            }),
+        }
+    }
+}
+
+fn find_duplicate<'a>(
+    mut names: impl Iterator<Item = (&'a String, Span)>,
+) -> Option<(&'a String, Span)> {
+    let mut seen = HashSet::new();
+    names.find(|(name, _)| !seen.insert(*name))
+}
+
+fn is_untyped_literal(expr: &Expression) -> bool {
+    match &expr.kind {
+        ExpressionKind::ValExpr(x) => matches!(
+            Literal::parse(x),
+            Some(Literal::Int(_) | Literal::Float(_) | Literal::Null)
+        ),
+        _ => false,
+    }
+}
+
+fn is_valid_cast(from: &Type, to: &Type) -> bool {
+    match (from, to) {
+        (x, y) if x == y => true,
+        (x, y) if x.is_numeric() && y.is_numeric() => true,
+        (Type::Bool | Type::Char, y) => y.is_integer(),
+        (Type::U8, Type::Char) => true,
+        (Type::Pointer(_), Type::I64 | Type::U64) => true,
+        _ => false,
+    }
+}
@@ -0,0 +1,615 @@
+use super::*;
+use crate::test_utils;
+
+fn analyse_text(contents: &str) -> Result<Program, SemanticError> {
+    let mut program = test_utils::parse_text(contents);
+    analyse(&mut program, &SourceMap::new(contents))?;
+    Ok(program)
+}
+
+fn declared_expression(program: &Program, idx: usize) -> &Expression {
+    match &program.statements[idx] {
+        Statement::DeclarationStatement {
+            expr: Some(expr), ..
+        } => expr,
+        x => panic!("Unexpected statement: {}", x),
+    }
+}
+
+#[test]
+fn test_valid_program() {
+    analyse_text("mut int x = 10;\nint y = x + 2;\nx = y - x;").unwrap();
+}
+
+#[test]
+fn test_declaration_should_error_for_redefined_var() {
+    let e = analyse_text("int x = 20;int x = 100;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from(
+            "Attempted to redeclare variable: x, Line: 1, Col: 16, previously declared as i64 at Line: 1, Col: 5"
+        )
+    );
+}
+
+#[test]
+fn test_assignment_should_error_for_undefined_var() {
+    let e = analyse_text("x = 20;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Undefined variable: x, Line: 1, Col: 1")
+    );
+}
+
+#[test]
+fn test_should_error_for_undefined_var_in_expr() {
+    let e = analyse_text("int x = y;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Undefined variable: y, Line: 1, Col: 9")
+    );
+}
+
+#[test]
+fn test_assignment_should_error_for_immutable_var() {
+    let e = analyse_text("int x = 10;\n{\n  x = 20;\n}").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from(
+            "Cannot assign to immutable variable: x, Line: 3, Col: 3, declared without mut at Line: 1, Col: 5"
+        )
+    );
+}
+
+#[test]
+fn test_shadowing_declaration_can_be_mutable() {
+    analyse_text("int x = 10;\n{\n  mut int x = 2;\n  x = x + 1;\n}").unwrap();
+}
+
+#[test]
+fn test_declaration_should_not_see_itself() {
+    let e = analyse_text("int x = x;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Undefined variable: x, Line: 1, Col: 9")
+    );
+}
+
+#[test]
+fn test_block_can_use_outer_variables() {
+    analyse_text("mut int x = 10;\n{\n  int y = x;\n  x = y + 1;\n}").unwrap();
+}
+
+#[test]
+fn test_block_can_shadow_outer_variables() {
+    analyse_text("int x = 10;\n{\n  int x = 2;\n  {\n    int x = x + 1;\n  }\n}").unwrap();
+}
+
+#[test]
+fn test_should_error_for_redefined_var_in_same_block() {
+    let e = analyse_text("int x = 10;\n{\n  int y = 2;\n  int y = x;\n}").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from(
+            "Attempted to redeclare variable: y, Line: 4, Col: 7, previously declared as i64 at Line: 3, Col: 7"
+        )
+    );
+}
+
+#[test]
+fn test_should_error_when_using_variable_after_its_block() {
+    let e = analyse_text("{\n  int y = 2;\n}\nint x = y;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Undefined variable: y, Line: 4, Col: 9")
+    );
+}
+
+#[test]
+fn test_should_annotate_expression_types() {
+    let program =
+        analyse_text("u8 x = 10;\nu8 y = 2 + x;\nbool z = x < 3;\ni32 w = x as i32;").unwrap();
+
+    let expr = declared_expression(&program, 1);
+    assert_eq!(expr.ty, Some(Type::U8));
+    if let ExpressionKind::ArithmeticExpr(x, _, y) = &expr.kind {
+        assert_eq!(x.ty, Some(Type::U8));
+        assert_eq!(y.ty, Some(Type::U8));
+    } else {
+        panic!("Expected Arithmetic expression, but got {}", expr);
+    }
+
+    let expr = declared_expression(&program, 2);
+    assert_eq!(expr.ty, Some(Type::Bool));
+    if let ExpressionKind::BooleanExpr(_, _, y) = &expr.kind {
+        assert_eq!(y.ty, Some(Type::U8));
+    } else {
+        panic!("Expected Boolean expression, but got {}", expr);
+    }
+
+    assert_eq!(declared_expression(&program, 3).ty, Some(Type::I32));
+}
+
+#[test]
+fn test_literals_should_default_without_context() {
+    let program = analyse_text("bool x = 1 < 2;\nbool y = 1.5 > 2.5;\nu64 z = 5 as u64;").unwrap();
+
+    if let ExpressionKind::BooleanExpr(x, _, _) = &declared_expression(&program, 0).kind {
+        assert_eq!(x.ty, Some(Type::I64));
+    }
+    if let ExpressionKind::BooleanExpr(x, _, _) = &declared_expression(&program, 1).kind {
+        assert_eq!(x.ty, Some(Type::F64));
+    }
+    if let ExpressionKind::CastExpr(x, _) = &declared_expression(&program, 2).kind {
+        assert_eq!(x.ty, Some(Type::I64));
+    }
+}
+
+#[test]
+fn test_valid_literal_types() {
+    analyse_text("f32 a = 1.5;\nf64 b = 2;\nchar c = 'x';\nbool d = false;\nchar e = '\\n';")
+        .unwrap();
+}
+
+#[test]
+fn test_should_error_for_mismatched_declaration() {
+    let e = analyse_text("i64 x = 10;\ni32 y = x;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Mismatched types, expected i32 but found i64, Line: 2, Col: 9")
+    );
+}
+
+#[test]
+fn test_should_error_for_mismatched_assignment() {
+    let e = analyse_text("mut bool x = true;\nx = 1;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Mismatched types, expected bool but found i64, Line: 2, Col: 5")
+    );
+}
+
+#[test]
+fn test_should_error_for_mismatched_operands() {
+    let e = analyse_text("u8 x = 1;\ni16 y = 2;\nbool z = x < y;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Mismatched types, expected u8 but found i16, Line: 3, Col: 14")
+    );
+}
+
+#[test]
+fn test_should_error_for_float_literal_in_integer_context() {
+    let e = analyse_text("int x = 3.14;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Mismatched types, expected i64 but found f64, Line: 1, Col: 9")
+    );
+}
+
+#[test]
+fn test_should_error_for_arithmetic_on_bool() {
+    let e = analyse_text("bool x = true + false;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Operator + cannot be applied to values of type bool, Line: 1, Col: 10")
+    );
+}
+
+#[test]
+fn test_should_error_for_ordering_bools() {
+    let e = analyse_text("bool x = true < false;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Operator < cannot be applied to values of type bool, Line: 1, Col: 10")
+    );
+}
+
+#[test]
+fn test_should_error_for_literal_out_of_range() {
+    let test_cases = [
+        (
+            "u8 x = 256;",
+            "Literal 256 is out of range for type u8, Line: 1, Col: 8",
+        ),
+        (
+            "i8 x = 128;",
+            "Literal 128 is out of range for type i8, Line: 1, Col: 8",
+        ),
+        (
+            "u64 x = 18446744073709551616;",
+            "Literal 18446744073709551616 is out of range for type u64, Line: 1, Col: 9",
+        ),
+    ];
+
+    for (input, expected) in test_cases {
+        let e = analyse_text(input).unwrap_err();
+        assert_eq!(e.to_string(), expected);
+    }
+}
+
+#[test]
+fn test_should_error_for_string_literal() {
+    let e = analyse_text("int x = \"hello world\";").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Unsupported literal: \"hello world\", Line: 1, Col: 9")
+    );
+}
+
+#[test]
+fn test_should_error_for_invalid_cast() {
+    let e = analyse_text("int x = 10;\nbool y = x as bool;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Unable to cast from i64 to bool, Line: 2, Col: 10")
+    );
+}
+
+#[test]
+fn test_valid_casts() {
+    analyse_text(
+        "int x = 10;\nf64 y = x as f64;\nu8 z = 'a' as u8;\nchar c = z as char;\ni8 b = true as i8;",
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_multiplicative_operators_on_numbers() {
+    analyse_text("f64 x = 1.5 * 2 / 0.5;\nu8 y = 3 * 2;\nbool z = x / 2.0 > 1;").unwrap();
+}
+
+#[test]
+fn test_should_error_for_mixing_ints_and_floats() {
+    let e = analyse_text("f64 x = 1.5;\nint y = 2;\nf64 z = x * y;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Mismatched types, expected f64 but found i64, Line: 3, Col: 13")
+    );
+}
+
+#[test]
+fn test_constants_can_be_used_in_constant_expressions() {
+    analyse_text("const u8 A = 2;\nconst u16 B = A as u16 * 300;\nmut u16 c = B;\nc = c + B;")
+        .unwrap();
+}
+
+#[test]
+fn test_should_error_for_assignment_to_constant() {
+    let e = analyse_text("const int A = 1;\n{\n  A = 2;\n}").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Cannot assign to constant: A, Line: 3, Col: 3, declared at Line: 1, Col: 11")
+    );
+}
+
+#[test]
+fn test_should_error_for_non_constant_value_in_constant() {
+    let e = analyse_text("const int A = 1;\nint x = 2;\nconst int B = A + x;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Constant values cannot use the non-constant variable: x, Line: 3, Col: 19")
+    );
+}
+
+#[test]
+fn test_control_flow_conditions_must_be_bool() {
+    analyse_text("mut int x = 1;\nwhile x < 10 {\n  if x > 5 {\n    x = x + 2;\n  } else {\n    x = x + 1;\n  }\n}")
+        .unwrap();
+    let e = analyse_text("int x = 1;\nif x {\n}").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Mismatched types, expected bool but found i64, Line: 2, Col: 4")
+    );
+}
+
+#[test]
+fn test_control_flow_blocks_introduce_scopes() {
+    let e = analyse_text("if true {\n  int x = 1;\n}\nint y = x;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Undefined variable: x, Line: 4, Col: 9")
+    );
+}
+
+#[test]
+fn test_variables_declared_without_value_must_be_assigned_before_use() {
+    analyse_text("int x;\nx = 1;\nint y = x;").unwrap();
+    analyse_text("int x;\nif true {\n  x = 1;\n} else {\n  x = 2;\n}\nint y = x;").unwrap();
+    analyse_text("mut int x;\nwhile true {\n  x = 1;\n  int y = x;\n}").unwrap();
+
+    let e = analyse_text("int x;\nint y = x + 1;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from(
+            "Variable may be used before it is assigned: x, Line: 2, Col: 9, declared without a value at Line: 1, Col: 5"
+        )
+    );
+
+    let e = analyse_text("int x;\nif true {\n  x = 1;\n}\nint y = x;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from(
+            "Variable may be used before it is assigned: x, Line: 5, Col: 9, declared without a value at Line: 1, Col: 5"
+        )
+    );
+
+    let e = analyse_text("mut int x;\nwhile false {\n  x = 1;\n}\nbool y = x == 1;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from(
+            "Variable may be used before it is assigned: x, Line: 5, Col: 10, declared without a value at Line: 1, Col: 9"
+        )
+    );
+
+    let e = analyse_text("mut int x;\nwhile x < 10 {\n  x = 1;\n}").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from(
+            "Variable may be used before it is assigned: x, Line: 2, Col: 7, declared without a value at Line: 1, Col: 9"
+        )
+    );
+}
+
+#[test]
+fn test_immutable_variables_declared_without_value_can_be_assigned_once() {
+    analyse_text("int x;\nif true {\n  x = 1;\n} else {\n  x = 2;\n}").unwrap();
+    analyse_text("int x;\nwhile true {\n  int y;\n  y = 1;\n}").unwrap();
+
+    let e = analyse_text("int x;\nx = 1;\nx = 2;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from(
+            "Cannot assign to immutable variable: x, Line: 3, Col: 1, declared without mut at Line: 1, Col: 5"
+        )
+    );
+
+    let e = analyse_text("int x;\nif true {\n  x = 1;\n}\nx = 2;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from(
+            "Cannot assign to immutable variable: x, Line: 5, Col: 1, declared without mut at Line: 1, Col: 5"
+        )
+    );
+
+    let e = analyse_text("int x;\nwhile true {\n  x = 1;\n}").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from(
+            "Cannot assign to immutable variable: x, Line: 3, Col: 3, declared without mut at Line: 1, Col: 5"
+        )
+    );
+}
+
+#[test]
+fn test_wrapping_and_saturating_operators_only_apply_to_integers() {
+    analyse_text("u8 x = 250;\nu8 y = x +% 10 *| 2;\ni16 z = 5 -| 7;").unwrap();
+    let e = analyse_text("f64 x = 1.5;\nf64 y = x +% 2.0;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Operator +% cannot be applied to values of type f64, Line: 2, Col: 9")
+    );
+}
+
+#[test]
+fn test_arrays_are_used_by_indexing_their_elements() {
+    analyse_text("mut i64[3][2] a;\nmut int i = 0;\na[i][1] = 5;\ni64 x = a[2][i] + 1;").unwrap();
+
+    let e = analyse_text("mut i64[3] a;\ni64[3] b = a;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from(
+            "Arrays can only be used by indexing their elements, found value of type i64[3], Line: 2, Col: 12"
+        )
+    );
+
+    let e = analyse_text("mut u8[3] a;\na[1.5] = 2;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Array indices must be integers, found value of type f64, Line: 2, Col: 3")
+    );
+
+    let e = analyse_text("int x = 1;\nint y = x[0];").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Cannot index into value of type i64, Line: 2, Col: 9")
+    );
+}
+
+#[test]
+fn test_array_elements_can_only_be_assigned_when_mutable() {
+    let e = analyse_text("i64[3] a;\na[0] = 1;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from(
+            "Cannot assign to immutable variable: a, Line: 2, Col: 1, declared without mut at Line: 1, Col: 8"
+        )
+    );
+}
+
+#[test]
+fn test_structs_can_be_used_before_they_are_defined() {
+    let program = analyse_text(
+        "mut Line l;\nl.end = Point { x: 1, y: 2 };\nPoint p = l.end;\ni32 x = p.x + l.start.y;\n\
+         struct Line { Point start; Point end; u8[4] tag; }\nstruct Point { i32 x; i32 y; }",
+    )
+    .unwrap();
+    assert_eq!(declared_expression(&program, 3).ty, Some(Type::I32));
+
+    let e = analyse_text("struct P { Q q; }\nstruct Q { P[2] p; }").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Struct P contains itself and would have no finite size, Line: 1, Col: 1")
+    );
+
+    let e = analyse_text("struct P { i64 x; }\nstruct P { u8 y; }").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from(
+            "Attempted to redefine struct: P, Line: 2, Col: 1, previously defined at Line: 1, Col: 1"
+        )
+    );
+
+    let e = analyse_text("Q[2] q;").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        String::from("Unknown type: Q, Line: 1, Col: 6")
+    );
+}
+
+#[test]
+fn test_struct_literals_and_fields_are_checked() {
+    let definition = "struct P { i64 x; bool y; u8[2] z; }\n";
+    analyse_text(&format!("{}P p = P {{ y: true, x: 1 }};", definition)).unwrap();
+
+    let test_cases = vec![
+        (
+            "P p = P { x: 1 };",
+            "Missing field y in literal of type P, Line: 2, Col: 7",
+        ),
+        (
+            "P p = P { x: 1, y: true, x: 2 };",
+            "Field given more than once: x, Line: 2, Col: 29",
+        ),
+        (
+            "P p = P { x: 1, y: 2 };",
+            "Mismatched types, expected bool but found i64, Line: 2, Col: 20",
+        ),
+        (
+            "P p = P { x: 1, y: true };\ni64 w = p.w;",
+            "Type P has no field named w, Line: 3, Col: 9",
+        ),
+        (
+            "P p = P { x: 1, y: true };\nbool b = p != p;",
+            "Operator != cannot be applied to values of type P, Line: 3, Col: 10",
+        ),
+        (
+            "P p = P { x: 1, y: true };\np.x = 2;",
+            "Cannot assign to immutable variable: p, Line: 3, Col: 1, declared without mut at Line: 2, Col: 3",
+        ),
+        (
+            "const P c = P { x: 1, y: true };",
+            "Constants must have a primitive type, found P, Line: 2, Col: 9",
+        ),
+    ];
+    for (input, expected) in test_cases {
+        let e = analyse_text(&format!("{}{}", definition, input)).unwrap_err();
+        assert_eq!(e.to_string(), expected);
+    }
+}
+
+#[test]
+fn test_pointer_expressions_are_typed() {
+    let program = analyse_text(
+        "struct P { i32 x; }\nmut P s;\nmut int[4] xs;\n*P p = &s;\n*int q = &xs[1] + 2;\n\
+         int d = q - &xs[0];\ni32 x = p.x;\n*int n = null;\nbool b = q > n;\nu64 a = q as u64;",
+    )
+    .unwrap();
+    assert_eq!(
+        declared_expression(&program, 4).ty,
+        Some(Type::Pointer(Box::new(Type::I64)))
+    );
+    assert_eq!(declared_expression(&program, 5).ty, Some(Type::I64));
+    assert_eq!(declared_expression(&program, 6).ty, Some(Type::I32));
+    assert_eq!(
+        declared_expression(&program, 7).ty,
+        Some(Type::Pointer(Box::new(Type::I64)))
+    );
+}
+
+#[test]
+fn test_should_error_for_invalid_pointer_expressions() {
+    let test_cases = vec![
+        (
+            "int x = 1;\n*int p = &x;",
+            "Cannot take the address of immutable variable: x, Line: 2, Col: 10, declared without mut at Line: 1, Col: 5",
+        ),
+        (
+            "*int p = &5;",
+            "Cannot take the address of 5, only of a variable, element, field or dereferenced pointer, Line: 1, Col: 10",
+        ),
+        (
+            "mut int[2] xs;\n*int[2] p = &xs;",
+            "Cannot take the address of an array of type i64[2], take the address of an element instead, Line: 2, Col: 13",
+        ),
+        (
+            "int x = 1;\nint y = *x;",
+            "Cannot dereference value of type i64, Line: 2, Col: 9",
+        ),
+        (
+            "bool b = null == null;",
+            "null can only be used where a pointer type is expected, Line: 1, Col: 10",
+        ),
+        (
+            "mut int x = 1;\n*int p = &x;\n*int q = p * 2;",
+            "Operator * cannot be applied to values of type *i64, Line: 3, Col: 10",
+        ),
+        (
+            "mut int x = 1;\n*u8 p = &x;",
+            "Mismatched types, expected *u8 but found *i64, Line: 2, Col: 9",
+        ),
+    ];
+    for (input, expected) in test_cases {
+        let e = analyse_text(input).unwrap_err();
+        assert_eq!(e.to_string(), expected);
+    }
+}
+
+#[test]
+fn test_enums_and_match_are_checked() {
+    let definition = "enum Shape { Circle(i64); Rect(i64, i64); Empty; }\nstruct Box { Shape s; }\n\
+                      Shape s = Shape::Rect(3, 4);\n";
+    let program = analyse_text(&format!(
+        "{}Box b = Box {{ s: s }};\ni64 a = match b.s {{ Shape::Rect(w, h) => w * h, Shape::Circle(r) => r, _ => 0 }};",
+        definition
+    ))
+    .unwrap();
+    assert_eq!(
+        declared_expression(&program, 2).ty,
+        Some(Type::Enum(String::from("Shape")))
+    );
+    assert_eq!(declared_expression(&program, 4).ty, Some(Type::I64));
+
+    let test_cases = vec![
+        (
+            "match s { Shape::Circle(_) => {} Shape::Empty => {} }",
+            "Match on Shape does not cover every variant, missing Shape::Rect, Line: 4, Col: 1",
+        ),
+        (
+            "match s { _ => {} Shape::Empty => {} }",
+            "Match arm for Shape::Empty is unreachable, it is covered by an earlier arm, Line: 4, Col: 19",
+        ),
+        (
+            "Shape t = Shape::Rect(1);",
+            "Variant Shape::Rect carries 2 values but 1 were given, Line: 4, Col: 11",
+        ),
+        (
+            "Shape t = Shape::Square(1);",
+            "Type Shape has no variant named Square, Line: 4, Col: 11",
+        ),
+        (
+            "i64 x = 1;\nmatch x { _ => {} }",
+            "Cannot match on value of type i64, Line: 5, Col: 7",
+        ),
+        (
+            "i64 x = match s { Shape::Circle(r) => r, _ => true };",
+            "Mismatched types, expected i64 but found bool, Line: 4, Col: 47",
+        ),
+        (
+            "match s { Shape::Circle(r) => { r = 1; } _ => {} }",
+            "Cannot assign to immutable variable: r, Line: 4, Col: 33, declared without mut at Line: 4, Col: 25",
+        ),
+        (
+            "enum Empty {}",
+            "Enum Empty must have at least one variant, Line: 4, Col: 1",
+        ),
+        (
+            "enum E { A; B; A(i64); }",
+            "Variant defined more than once: A, Line: 4, Col: 16",
+        ),
+    ];
+    for (input, expected) in test_cases {
+        let e = analyse_text(&format!("{}{}", definition, input)).unwrap_err();
+        assert_eq!(e.to_string(), expected);
+    }
+}
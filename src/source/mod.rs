@@ -0,0 +1,157 @@
+#[cfg(test)]
+mod tests;
+
+use std::rc::Rc;
+
+use crate::token::TextLocation;
+
+/*
+* A span is a half-open range of byte offsets [start, end) into the source text. Byte offsets are
+* used rather than character indices so that spans can be used to slice the source directly, the
+* SourceMap is then responsible for turning these back into human readable line/column positions
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    pub fn to(&self, other: Span) -> Span {
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+}
+
+impl std::fmt::Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+/*
+* A source map can hold several files one after another, so that a span is an offset into all of
+* them and identifies both the file and the position in it. Lines and columns are counted from the
+* start of each file, and locations in any file but the first are given that file's name
+*/
+pub struct SourceMap {
+    contents: String,
+    line_starts: Vec<usize>,
+    files: Vec<SourceFile>,
+}
+
+struct SourceFile {
+    name: Rc<str>,
+    start: usize,
+    first_line: usize,
+}
+
+impl SourceMap {
+    pub fn new(contents: &str) -> Self {
+        let mut source_map = Self {
+            contents: String::new(),
+            line_starts: Vec::new(),
+            files: Vec::new(),
+        };
+        source_map.add_file("", contents);
+        source_map
+    }
+
+    /*
+     * Adds a file after the existing ones, returning the offset it starts at
+     */
+    pub fn add_file(&mut self, name: &str, contents: &str) -> usize {
+        let start = self.contents.len();
+        if self.line_starts.last() != Some(&start) {
+            self.line_starts.push(start);
+        }
+        self.files.push(SourceFile {
+            name: Rc::from(name),
+            start,
+            first_line: self.line_starts.len() - 1,
+        });
+        for (idx, c) in contents.char_indices() {
+            if c == '\n' {
+                self.line_starts.push(start + idx + 1);
+            }
+        }
+        self.contents.push_str(contents);
+        start
+    }
+
+    pub fn location(&self, offset: usize) -> TextLocation {
+        let offset = offset.min(self.contents.len());
+        let line_idx = match self.line_starts.binary_search(&offset) {
+            Ok(idx) => idx,
+            Err(idx) => idx - 1,
+        };
+        let line_start = self.line_starts[line_idx];
+        let col_num = self.contents[line_start..offset].chars().count() + 1;
+        let file_idx = self.files.partition_point(|x| x.start <= offset).max(1) - 1;
+        let file = &self.files[file_idx];
+
+        TextLocation {
+            line_num: line_idx - file.first_line + 1,
+            col_num,
+            file: (file_idx > 0).then(|| file.name.clone()),
+        }
+    }
+}
+
+/*
+* An error found in a source file, shown with the name the source was given so that errors are
+* readable when the source came from stdin or several files are compiled
+*/
+#[derive(Debug)]
+pub struct SourceError {
+    pub name: String,
+    pub error: Box<dyn std::error::Error>,
+}
+
+impl SourceError {
+    pub fn new(name: &str, error: impl Into<Box<dyn std::error::Error>>) -> Self {
+        Self {
+            name: name.to_string(),
+            error: error.into(),
+        }
+    }
+}
+
+/*
+* An error reported at a place in the source, which may be in any of the files in the source map
+*/
+pub trait Located {
+    fn location_mut(&mut self) -> Option<&mut TextLocation>;
+}
+
+impl SourceError {
+    /*
+     * Shows the error with the name of the file it was found in, which is the compiled file unless
+     * the error's location is in a module it imports. The location then leaves out the file since
+     * the error already starts with it
+     */
+    pub fn located(name: &str, mut error: impl Located + std::error::Error + 'static) -> Self {
+        let name = match error.location_mut().and_then(|x| x.file.take()) {
+            Some(file) => file.to_string(),
+            None => name.to_string(),
+        };
+        Self {
+            name,
+            error: Box::new(error),
+        }
+    }
+}
+
+impl std::error::Error for SourceError {}
+
+impl std::fmt::Display for SourceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.name, self.error)
+    }
+}
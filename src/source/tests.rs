@@ -0,0 +1,76 @@
+use super::*;
+use crate::lexer;
+
+#[test]
+fn test_span_to_should_cover_both_spans() {
+    let span = Span::new(4, 6).to(Span::new(1, 3));
+    assert_eq!(span, Span::new(1, 6));
+}
+
+#[test]
+fn test_location_for_single_line() {
+    let source_map = SourceMap::new("int x = 10;");
+    let test_cases = [(0, 1), (4, 5), (8, 9), (10, 11)];
+
+    for (offset, col) in test_cases {
+        let loc = source_map.location(offset);
+        assert_eq!(loc.line_num, 1);
+        assert_eq!(loc.col_num, col);
+    }
+}
+
+#[test]
+fn test_location_for_multiple_lines() {
+    let source_map = SourceMap::new("int x = 10;\n\nint y = x;");
+    let loc = source_map.location(13);
+    assert_eq!((loc.line_num, loc.col_num), (3, 1));
+
+    let loc = source_map.location(21);
+    assert_eq!((loc.line_num, loc.col_num), (3, 9));
+}
+
+#[test]
+fn test_location_should_count_multi_byte_chars_as_one_column() {
+    let source_map = SourceMap::new("int xéy = 10;");
+    let loc = source_map.location(9);
+    assert_eq!((loc.line_num, loc.col_num), (1, 9));
+}
+
+#[test]
+fn test_source_error_is_prefixed_with_the_source_name() {
+    let e = SourceError::new("<stdin>", "Unexpected token ;, Line: 1, Col: 4");
+    assert_eq!(
+        e.to_string(),
+        "<stdin>: Unexpected token ;, Line: 1, Col: 4"
+    );
+}
+
+#[test]
+fn test_location_in_added_file() {
+    let mut source_map = SourceMap::new("int x = 10;");
+    let start = source_map.add_file("shapes.mgs", "int y = 1;\nint z = y;");
+    assert_eq!(start, 11);
+
+    let loc = source_map.location(4);
+    assert_eq!((loc.line_num, loc.col_num, loc.file), (1, 5, None));
+    let loc = source_map.location(start + 15);
+    assert_eq!(loc.to_string(), "Line: 2, Col: 5 in shapes.mgs");
+}
+
+#[test]
+fn test_located_error_starts_with_its_file() {
+    let mut source_map = SourceMap::new("int x = 10;");
+    let start = source_map.add_file("shapes.mgs", "int y = 1;\nint z = $;");
+
+    let e = lexer::parse_file(&source_map, start, "int y = 1;\nint z = $;").unwrap_err();
+    assert_eq!(
+        SourceError::located("main.mgs", e).to_string(),
+        "shapes.mgs: Unable to parse token: [(Unknown: $), Line: 2, Col: 9]"
+    );
+
+    let e = lexer::parse_text("int x = $;").unwrap_err();
+    assert_eq!(
+        SourceError::located("main.mgs", e).to_string(),
+        "main.mgs: Unable to parse token: [(Unknown: $), Line: 1, Col: 9]"
+    );
+}
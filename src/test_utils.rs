@@ -0,0 +1,29 @@
+/*
+* Setup shared by the tests of the passes which run on an analysed program, each of these takes
+* the source of a single module and panics if an earlier stage fails
+*/
+
+use crate::{
+    folding, lexer,
+    parser::{self, Program},
+    semantic,
+    source::SourceMap,
+};
+
+pub fn parse_text(contents: &str) -> Program {
+    let tokens = lexer::parse_text(contents).unwrap();
+    parser::parse_module(tokens).unwrap().program
+}
+
+pub fn analyse_text(contents: &str) -> (Program, SourceMap) {
+    let mut program = parse_text(contents);
+    let source_map = SourceMap::new(contents);
+    semantic::analyse(&mut program, &source_map).unwrap();
+    (program, source_map)
+}
+
+pub fn fold_text(contents: &str) -> (Program, SourceMap) {
+    let (mut program, source_map) = analyse_text(contents);
+    folding::fold_program(&mut program, &source_map).unwrap();
+    (program, source_map)
+}
@@ -1,15 +1,40 @@
 #[cfg(test)]
 mod tests;
 
-use std::fmt;
+use std::{fmt, rc::Rc};
+
+use crate::source::Span;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum TokenType {
     Value,
     Variable,
-    Int,
+    Type,
+    As,
+    Mut,
+    Const,
+    If,
+    Else,
+    While,
+    Struct,
+    Enum,
+    Match,
+    Import,
+    Pub,
     Eq,
     Semi,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    LParen,
+    RParen,
+    Dot,
+    Colon,
+    DoubleColon,
+    Comma,
+    Ampersand,
+    Arrow,
     Unknown,
     ArithmeticOp,
     BooleanOp,
@@ -20,9 +45,32 @@ impl fmt::Display for TokenType {
         match self {
             TokenType::Value => write!(f, "Value"),
             TokenType::Variable => write!(f, "Variable"),
-            TokenType::Int => write!(f, "Integer"),
+            TokenType::Type => write!(f, "Type"),
+            TokenType::As => write!(f, "As"),
+            TokenType::Mut => write!(f, "Mut"),
+            TokenType::Const => write!(f, "Const"),
+            TokenType::If => write!(f, "If"),
+            TokenType::Else => write!(f, "Else"),
+            TokenType::While => write!(f, "While"),
+            TokenType::Struct => write!(f, "Struct"),
+            TokenType::Enum => write!(f, "Enum"),
+            TokenType::Match => write!(f, "Match"),
+            TokenType::Import => write!(f, "Import"),
+            TokenType::Pub => write!(f, "Pub"),
             TokenType::Eq => write!(f, "Equals"),
             TokenType::Semi => write!(f, "Semicolon"),
+            TokenType::LBrace => write!(f, "Left Brace"),
+            TokenType::RBrace => write!(f, "Right Brace"),
+            TokenType::LBracket => write!(f, "Left Bracket"),
+            TokenType::RBracket => write!(f, "Right Bracket"),
+            TokenType::LParen => write!(f, "Left Paren"),
+            TokenType::RParen => write!(f, "Right Paren"),
+            TokenType::Dot => write!(f, "Dot"),
+            TokenType::Colon => write!(f, "Colon"),
+            TokenType::DoubleColon => write!(f, "Double Colon"),
+            TokenType::Comma => write!(f, "Comma"),
+            TokenType::Ampersand => write!(f, "Ampersand"),
+            TokenType::Arrow => write!(f, "Arrow"),
             TokenType::ArithmeticOp => write!(f, "Arithmetic Operator"),
             TokenType::BooleanOp => write!(f, "Boolean Operator"),
             TokenType::Unknown => write!(f, "Unknown"),
@@ -30,24 +78,23 @@ impl fmt::Display for TokenType {
     }
 }
 
+/*
+* A location in a file other than the one being compiled also names that file
+*/
 #[derive(Clone, Debug)]
 pub struct TextLocation {
     pub line_num: usize,
     pub col_num: usize,
-}
-
-impl TextLocation {
-    pub fn new() -> Self {
-        Self {
-            line_num: 1,
-            col_num: 0,
-        }
-    }
+    pub file: Option<Rc<str>>,
 }
 
 impl fmt::Display for TextLocation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Line: {}, Col: {}", self.line_num, self.col_num)
+        write!(f, "Line: {}, Col: {}", self.line_num, self.col_num)?;
+        match &self.file {
+            Some(file) => write!(f, " in {}", file),
+            None => Ok(()),
+        }
     }
 }
 
@@ -56,6 +103,7 @@ pub struct Token {
     pub t_type: TokenType,
     pub value: String,
     pub location: TextLocation,
+    pub span: Span,
 }
 
 impl fmt::Display for Token {
@@ -5,9 +5,12 @@ fn test_display_token_types() {
     let test_cases = [
         (TokenType::Value, "Value"),
         (TokenType::Variable, "Variable"),
-        (TokenType::Int, "Integer"),
+        (TokenType::Type, "Type"),
+        (TokenType::As, "As"),
         (TokenType::Eq, "Equals"),
         (TokenType::Semi, "Semicolon"),
+        (TokenType::LBrace, "Left Brace"),
+        (TokenType::RBrace, "Right Brace"),
         (TokenType::ArithmeticOp, "Arithmetic Operator"),
         (TokenType::BooleanOp, "Boolean Operator"),
         (TokenType::Unknown, "Unknown"),
@@ -23,9 +26,16 @@ fn test_display_text_location() {
     let loc = TextLocation {
         line_num: 10,
         col_num: 5,
+        file: None,
     };
 
     assert_eq!(format!("{}", loc), "Line: 10, Col: 5");
+
+    let loc = TextLocation {
+        file: Some(Rc::from("lib/shapes.mgs")),
+        ..loc
+    };
+    assert_eq!(format!("{}", loc), "Line: 10, Col: 5 in lib/shapes.mgs");
 }
 
 #[test]
@@ -34,9 +44,11 @@ fn test_display_token() {
         location: TextLocation {
             line_num: 10,
             col_num: 5,
+            file: None,
         },
         value: String::from("test"),
         t_type: TokenType::Value,
+        span: Span::new(20, 24),
     };
 
     assert_eq!(format!("{}", token), "[(Value: test), Line: 10, Col: 5]");
@@ -0,0 +1,303 @@
+/*
+* The assembler and linker used for a target are taken from the first of the command line, the
+* MGS_AS and MGS_LD environment variables and the target's table in the config file (e.g. as and ld
+* under [arm64]). When none of those name a tool, the PATH is searched for the tools known to work
+* for the target, cross tools first and then the host's own tools when they produce the target's
+* code. A tool is given as a command line, so extra arguments can be included
+* (e.g. "clang --target=aarch64-linux-gnu -c")
+*
+* The runner used to run a built program is chosen the same way, with qemu-user searched for when
+* the host can't run the target's code itself
+*/
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::{self, Config, ConfigError},
+    target::Target,
+};
+
+const CONFIG_FILE: &str = "mgs/config.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolKind {
+    Assembler,
+    Linker,
+    Runner,
+}
+
+impl ToolKind {
+    fn env_var(&self) -> &'static str {
+        match self {
+            ToolKind::Assembler => "MGS_AS",
+            ToolKind::Linker => "MGS_LD",
+            ToolKind::Runner => "MGS_RUNNER",
+        }
+    }
+
+    fn config_key(&self) -> &'static str {
+        match self {
+            ToolKind::Assembler => "as",
+            ToolKind::Linker => "ld",
+            ToolKind::Runner => "runner",
+        }
+    }
+
+    fn flag(&self) -> &'static str {
+        match self {
+            ToolKind::Assembler => "--assembler",
+            ToolKind::Linker => "--linker",
+            ToolKind::Runner => "--runner",
+        }
+    }
+
+    fn candidates(&self, target: &Target) -> Vec<&'static str> {
+        let (cross, native, generic) = match (self, target) {
+            (ToolKind::Runner, _) if target.is_native() => return Vec::new(),
+            (ToolKind::Runner, Target::ARM64) => {
+                return vec!["qemu-aarch64", "qemu-aarch64-static"];
+            }
+            (ToolKind::Assembler, Target::ARM64) => (
+                "aarch64-linux-gnu-as",
+                "as",
+                "clang --target=aarch64-linux-gnu -c",
+            ),
+            (ToolKind::Linker, Target::ARM64) => ("aarch64-linux-gnu-ld", "ld", "ld.lld"),
+        };
+        if target.is_native() {
+            vec![cross, native, generic]
+        } else {
+            vec![cross, generic]
+        }
+    }
+}
+
+impl std::fmt::Display for ToolKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ToolKind::Assembler => write!(f, "assembler"),
+            ToolKind::Linker => write!(f, "linker"),
+            ToolKind::Runner => write!(f, "runner"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ToolchainError {
+    NotFound {
+        kind: ToolKind,
+        target: String,
+        searched: Vec<String>,
+    },
+    EmptyCommand(ToolKind),
+    ConfigNotFound(PathBuf),
+    ConfigReadFailure(PathBuf, io::Error),
+    InvalidConfig(PathBuf, ConfigError),
+}
+
+impl std::error::Error for ToolchainError {}
+
+impl std::fmt::Display for ToolchainError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ToolchainError::NotFound {
+                kind,
+                target,
+                searched,
+            } => write!(
+                f,
+                "No {} found for target {}, searched the PATH for: {}. Choose one with {}, the {} environment variable or {} in the [{}] table of the config file",
+                kind,
+                target,
+                searched.join(", "),
+                kind.flag(),
+                kind.env_var(),
+                kind.config_key(),
+                target
+            ),
+            ToolchainError::EmptyCommand(kind) => write!(f, "The {} command is empty", kind),
+            ToolchainError::ConfigNotFound(x) => {
+                write!(f, "Unable to find config file: {}", x.display())
+            }
+            ToolchainError::ConfigReadFailure(x, e) => {
+                write!(
+                    f,
+                    "Unable to read config file {}, caused by: {}",
+                    x.display(),
+                    e
+                )
+            }
+            ToolchainError::InvalidConfig(x, e) => {
+                write!(f, "Invalid config file {}: {}", x.display(), e)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tool {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl Tool {
+    fn parse(command: &str) -> Option<Tool> {
+        let mut words = command.split_whitespace().map(|x| x.to_string());
+        Some(Tool {
+            program: words.next()?,
+            args: words.collect(),
+        })
+    }
+
+    pub fn command(&self) -> std::process::Command {
+        let mut command = std::process::Command::new(&self.program);
+        command.args(&self.args);
+        command
+    }
+}
+
+impl std::fmt::Display for Tool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.program)?;
+        for arg in &self.args {
+            write!(f, " {}", arg)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ToolchainOptions {
+    pub assembler: Option<String>,
+    pub linker: Option<String>,
+    pub runner: Option<String>,
+    pub config: Option<PathBuf>,
+}
+
+impl ToolchainOptions {
+    fn get(&self, kind: ToolKind) -> Option<&str> {
+        match kind {
+            ToolKind::Assembler => self.assembler.as_deref(),
+            ToolKind::Linker => self.linker.as_deref(),
+            ToolKind::Runner => self.runner.as_deref(),
+        }
+    }
+}
+
+pub struct Toolchain<'a> {
+    target: &'a Target,
+    options: &'a ToolchainOptions,
+    config: Config,
+}
+
+impl<'a> Toolchain<'a> {
+    /*
+     * A config file given on the command line must exist, whereas the default one in the user's
+     * config directory is optional
+     */
+    pub fn new(target: &'a Target, options: &'a ToolchainOptions) -> Result<Self, ToolchainError> {
+        let config = match &options.config {
+            Some(path) => read_config(path)?,
+            None => match default_config_path() {
+                Some(path) if path.is_file() => read_config(&path)?,
+                _ => Config::default(),
+            },
+        };
+        Ok(Toolchain {
+            target,
+            options,
+            config,
+        })
+    }
+
+    pub fn assembler(&self) -> Result<Tool, ToolchainError> {
+        self.resolve(ToolKind::Assembler)
+    }
+
+    pub fn linker(&self) -> Result<Tool, ToolchainError> {
+        self.resolve(ToolKind::Linker)
+    }
+
+    /*
+     * Returns None when the program can be run directly on the host
+     */
+    pub fn runner(&self) -> Result<Option<Tool>, ToolchainError> {
+        match self.resolve(ToolKind::Runner) {
+            Err(ToolchainError::NotFound { searched, .. }) if searched.is_empty() => Ok(None),
+            x => x.map(Some),
+        }
+    }
+
+    fn resolve(&self, kind: ToolKind) -> Result<Tool, ToolchainError> {
+        resolve_tool(
+            kind,
+            self.target,
+            self.options.get(kind),
+            env::var(kind.env_var()).ok().as_deref(),
+            &self.config,
+            |x| find_on_path(x).is_some(),
+        )
+    }
+}
+
+fn resolve_tool(
+    kind: ToolKind,
+    target: &Target,
+    flag: Option<&str>,
+    env_var: Option<&str>,
+    config: &Config,
+    is_available: impl Fn(&str) -> bool,
+) -> Result<Tool, ToolchainError> {
+    let configured = flag
+        .or(env_var)
+        .or(config.get_str(&target.config_name(), kind.config_key()));
+    if let Some(command) = configured {
+        return Tool::parse(command).ok_or(ToolchainError::EmptyCommand(kind));
+    }
+
+    let candidates: Vec<Tool> = kind
+        .candidates(target)
+        .into_iter()
+        .filter_map(Tool::parse)
+        .collect();
+    match candidates.iter().find(|x| is_available(&x.program)) {
+        Some(tool) => Ok(tool.clone()),
+        None => Err(ToolchainError::NotFound {
+            kind,
+            target: target.config_name(),
+            searched: candidates.into_iter().map(|x| x.program).collect(),
+        }),
+    }
+}
+
+fn read_config(path: &Path) -> Result<Config, ToolchainError> {
+    let contents = fs::read_to_string(path).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => ToolchainError::ConfigNotFound(path.to_path_buf()),
+        _ => ToolchainError::ConfigReadFailure(path.to_path_buf(), e),
+    })?;
+    config::parse(&contents).map_err(|e| ToolchainError::InvalidConfig(path.to_path_buf(), e))
+}
+
+fn default_config_path() -> Option<PathBuf> {
+    let config_dir = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join(CONFIG_FILE))
+}
+
+pub fn find_on_path(program: &str) -> Option<PathBuf> {
+    if program.contains('/') {
+        let path = PathBuf::from(program);
+        return path.is_file().then_some(path);
+    }
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|x| x.join(program))
+        .find(|x| x.is_file())
+}
@@ -0,0 +1,73 @@
+use super::*;
+
+fn resolve(
+    flag: Option<&str>,
+    env_var: Option<&str>,
+    config: &str,
+    available: &[&str],
+) -> Result<Tool, ToolchainError> {
+    resolve_tool(
+        ToolKind::Assembler,
+        &Target::ARM64,
+        flag,
+        env_var,
+        &config::parse(config).unwrap(),
+        |x| available.contains(&x),
+    )
+}
+
+#[test]
+fn test_tools_are_chosen_in_order_of_precedence() {
+    let config = "[arm64]\nas = \"clang --target=aarch64-linux-gnu -c\"";
+    let tool = resolve(Some("my-as"), Some("env-as"), config, &[]).unwrap();
+    assert_eq!(tool.to_string(), "my-as");
+    let tool = resolve(None, Some("env-as -g"), config, &[]).unwrap();
+    assert_eq!(tool.program, "env-as");
+    assert_eq!(tool.args, vec!["-g"]);
+    let tool = resolve(None, None, config, &[]).unwrap();
+    assert_eq!(tool.to_string(), "clang --target=aarch64-linux-gnu -c");
+}
+
+#[test]
+fn test_tools_are_detected_on_the_path() {
+    let tool = resolve(None, None, "", &["aarch64-linux-gnu-as", "clang"]).unwrap();
+    assert_eq!(tool.to_string(), "aarch64-linux-gnu-as");
+    let tool = resolve(None, None, "[x86]\nas = \"as\"", &["clang"]).unwrap();
+    assert_eq!(tool.to_string(), "clang --target=aarch64-linux-gnu -c");
+}
+
+#[test]
+fn test_should_error_when_no_tool_is_found() {
+    let e = resolve(None, None, "", &[]).unwrap_err();
+    match &e {
+        ToolchainError::NotFound { searched, .. } => {
+            assert_eq!(
+                searched.first().map(|x| x.as_str()),
+                Some("aarch64-linux-gnu-as")
+            );
+            assert_eq!(searched.last().map(|x| x.as_str()), Some("clang"));
+        }
+        x => panic!("Unexpected error: {}", x),
+    }
+    assert!(
+        e.to_string()
+            .starts_with("No assembler found for target arm64")
+    );
+
+    let e = resolve_tool(
+        ToolKind::Runner,
+        &Target::ARM64,
+        None,
+        None,
+        &Config::default(),
+        |_| false,
+    )
+    .unwrap_err();
+    assert!(
+        e.to_string()
+            .contains("--runner, the MGS_RUNNER environment variable")
+    );
+
+    let e = resolve(Some("  "), None, "", &[]).unwrap_err();
+    assert_eq!(e.to_string(), "The assembler command is empty");
+}